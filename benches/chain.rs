@@ -0,0 +1,34 @@
+//! Hashing and validation benchmarks: `cargo bench`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rustchain::blockchain::Blockchain;
+use rustchain::crypto::calculate_hash;
+use rustchain::validation::validate_chain;
+
+fn bench_calculate_hash(c: &mut Criterion) {
+    let input = "a".repeat(256);
+
+    let mut group = c.benchmark_group("crypto");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("calculate_hash 256B", |b| b.iter(|| calculate_hash(black_box(&input))));
+    group.finish();
+}
+
+fn bench_block_hash(c: &mut Criterion) {
+    let blockchain = Blockchain::build_benchmark_chain(1, 100);
+    let block = blockchain.get_latest_block();
+
+    c.bench_function("Block::calculate_hash 100 txs", |b| b.iter(|| black_box(block).calculate_hash()));
+}
+
+fn bench_validate_chain(c: &mut Criterion) {
+    let blockchain = Blockchain::build_benchmark_chain(1_000, 1);
+
+    let mut group = c.benchmark_group("validation");
+    group.sample_size(20);
+    group.bench_function("validate_chain 1000 blocks", |b| b.iter(|| validate_chain(black_box(&blockchain))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_calculate_hash, bench_block_hash, bench_validate_chain);
+criterion_main!(benches);
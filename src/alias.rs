@@ -0,0 +1,123 @@
+//! Short names for addresses, for display and input only
+//!
+//! An `Aliases` registry maps names like `alice` to full addresses. Front ends
+//! resolve a name typed where an address is expected, and show the name in
+//! place of the address it stands for. Aliases never reach the chain: blocks,
+//! the mempool and saved files always hold the raw address.
+//!
+//! A name may not be an address already in use, or typing it would send to
+//! the aliased address instead of the one meant.
+
+use crate::transaction::Transaction;
+use std::collections::HashMap;
+
+/// Names standing in for addresses (alias → address)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aliases {
+    names: HashMap<String, String>,
+}
+
+impl Aliases {
+    pub fn new() -> Self {
+        Aliases::default()
+    }
+
+    /// Points `name` at `address`; returns the address it pointed at before
+    /// `is_address` says whether a name is already an address in use, which
+    /// an alias may not shadow
+    pub fn set(&mut self, name: &str, address: &str, is_address: impl Fn(&str) -> bool) -> Result<Option<String>, String> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(format!("Alias '{}' must be a single word", name));
+        }
+        if is_address(name) {
+            return Err(format!("Alias '{}' is already an address", name));
+        }
+        if address.trim().is_empty() {
+            return Err("Alias address cannot be empty".to_string());
+        }
+        if name == address {
+            return Err(format!("Alias '{}' would point at itself", name));
+        }
+        Ok(self.names.insert(name.to_string(), address.to_string()))
+    }
+
+    /// Forgets `name`; returns the address it pointed at
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.names.remove(name)
+    }
+
+    /// The address `name` points at, if it is an alias
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.names.get(name).map(String::as_str)
+    }
+
+    /// The address meant by `input`: the aliased address, or `input` itself
+    pub fn resolve<'a>(&'a self, input: &'a str) -> &'a str {
+        self.get(input).unwrap_or(input)
+    }
+
+    /// How to show `address`: its alias (the first by name if it has several),
+    /// otherwise the address itself
+    pub fn label(&self, address: &str) -> String {
+        self.names
+            .iter()
+            .filter(|(_, target)| target.as_str() == address)
+            .map(|(name, _)| name)
+            .min()
+            .cloned()
+            .unwrap_or_else(|| address.to_string())
+    }
+
+    /// `tx` as its `Display` shows it, with sender and receiver labelled
+    pub fn transaction(&self, tx: &Transaction) -> String {
+        let mut shown = tx.clone();
+        shown.sender = self.label(&tx.sender);
+        shown.receiver = self.label(&tx.receiver);
+        shown.to_string()
+    }
+
+    /// Every alias and its address, sorted by alias
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut entries: Vec<_> = self.names.iter().map(|(name, address)| (name.as_str(), address.as_str())).collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::COIN;
+
+    #[test]
+    fn test_labels_prefer_aliases_and_show_addresses_in_full() {
+        let long = "0x9f3a7c21e4b8d6f05a1c3e7b9d2f4a6c8e0b1d3f";
+        let mut aliases = Aliases::new();
+        assert_eq!(aliases.label(long), long);
+        assert_eq!(aliases.set("alice", long, |_| false), Ok(None));
+        assert!(aliases.set("two words", long, |_| false).is_err());
+        assert_eq!(aliases.resolve("alice"), long);
+        assert_eq!(aliases.resolve("bob"), "bob");
+        assert_eq!(aliases.label(long), "alice");
+
+        let tx = Transaction::new(long.to_string(), String::from("Bob"), COIN).unwrap();
+        assert_eq!(aliases.transaction(&tx), "alice -> Bob : 1.00");
+    }
+
+    #[test]
+    fn test_alias_cannot_shadow_an_address() {
+        let mut aliases = Aliases::new();
+        let known = |name: &str| name == "Bob";
+        assert!(aliases.set("Bob", "Mallory", known).is_err());
+        assert_eq!(aliases.resolve("Bob"), "Bob");
+        assert_eq!(aliases.set("bob", "Bob", known), Ok(None));
+    }
+}
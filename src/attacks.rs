@@ -16,13 +16,17 @@
 //! These methods are for EDUCATIONAL PURPOSES ONLY.
 //! In production blockchains, many of these capabilities would not exist.
 
-use crate::blockchain::Blockchain;
-use crate::transaction::Transaction;
-use crate::validation::{self, ValidationError, ValidationResult};
+use crate::block::Block;
+use crate::blockchain::{Blockchain, MEDIAN_TIME_PAST_WINDOW};
+use crate::crypto::{short_hash, DEFAULT_HASH_LEN};
+use crate::transaction::{format_amount, Transaction, COIN};
+use crate::validation::{self, ValidationError};
+use serde::Serialize;
+use std::cmp::Ordering;
 use std::fmt;
 
 /// Result of an attack simulation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct AttackResult {
     /// Name of the attack
     pub attack_name: String,
@@ -38,21 +42,77 @@ pub struct AttackResult {
     pub blocks_affected: usize,
     /// The blockchain state after the attack (should be invalid)
     pub is_chain_valid: bool,
+    /// Every error full validation reported on the attacked chain
+    pub validation_errors: Vec<ValidationError>,
+    /// The chain the attacker produced, kept when chain capture is enabled
+    /// A debugging aid: it takes no part in equality or ordering
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attacked_chain: Option<Blockchain>,
+}
+
+impl AttackResult {
+    /// Severity score for ranking reports (higher is worse)
+    /// An undetected attack outranks every detected one; within each group,
+    /// more blocks affected is worse
+    pub fn severity(&self) -> u32 {
+        let undetected = if self.detected { 0 } else { 1 << 16 };
+        undetected + self.blocks_affected.min(u16::MAX as usize) as u32
+    }
+}
+
+/// Results order by severity first; the remaining fields only break ties
+impl Ord for AttackResult {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.severity()
+            .cmp(&other.severity())
+            .then_with(|| self.attack_name.cmp(&other.attack_name))
+            .then_with(|| self.description.cmp(&other.description))
+            .then_with(|| self.detected.cmp(&other.detected))
+            .then_with(|| self.detection_method.cmp(&other.detection_method))
+            .then_with(|| self.explanation.cmp(&other.explanation))
+            .then_with(|| self.blocks_affected.cmp(&other.blocks_affected))
+            .then_with(|| self.is_chain_valid.cmp(&other.is_chain_valid))
+            .then_with(|| self.validation_errors.cmp(&other.validation_errors))
+    }
+}
+
+impl PartialEq for AttackResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AttackResult {}
+
+impl PartialOrd for AttackResult {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ordering for the attack summary report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SummaryOrder {
+    /// The order the attacks were run in
+    #[default]
+    RunOrder,
+    /// Most severe first
+    Severity,
 }
 
 impl fmt::Display for AttackResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "\n=== Attack: {} ===\n", self.attack_name)?;
-        write!(f, "Description: {}\n", self.description)?;
-        write!(f, "Detected: {}\n", if self.detected { "YES ✓" } else { "NO ✗" })?;
+        writeln!(f, "\n=== Attack: {} ===", self.attack_name)?;
+        writeln!(f, "Description: {}", self.description)?;
+        writeln!(f, "Detected: {}", if self.detected { "YES ✓" } else { "NO ✗" })?;
 
         if let Some(method) = &self.detection_method {
-            write!(f, "Detection Method: {}\n", method)?;
+            writeln!(f, "Detection Method: {}", method)?;
         }
 
-        write!(f, "Blocks Affected: {}\n", self.blocks_affected)?;
-        write!(f, "Chain Valid After Attack: {}\n", if self.is_chain_valid { "Yes" } else { "No ✗" })?;
-        write!(f, "\nEducational Note:\n  {}\n", self.explanation)?;
+        writeln!(f, "Blocks Affected: {}", self.blocks_affected)?;
+        writeln!(f, "Chain Valid After Attack: {}", if self.is_chain_valid { "Yes" } else { "No ✗" })?;
+        writeln!(f, "\nEducational Note:\n  {}", self.explanation)?;
 
         Ok(())
     }
@@ -81,6 +141,8 @@ pub enum AttackType {
     HashRecalculation,
     /// Double spend attack simulation
     DoubleSpend,
+    /// Backdate a block and re-mine it so only the timestamp rule objects
+    TimestampManipulation,
 }
 
 impl fmt::Display for AttackType {
@@ -96,6 +158,7 @@ impl fmt::Display for AttackType {
             AttackType::ChainReplacement => write!(f, "Chain Replacement"),
             AttackType::HashRecalculation => write!(f, "Hash Recalculation"),
             AttackType::DoubleSpend => write!(f, "Double Spend"),
+            AttackType::TimestampManipulation => write!(f, "Timestamp Manipulation"),
         }
     }
 }
@@ -114,6 +177,7 @@ impl AttackType {
             Self::ChainReplacement,
             Self::HashRecalculation,
             Self::DoubleSpend,
+            Self::TimestampManipulation,
         ]
     }
 
@@ -150,16 +214,164 @@ impl AttackType {
             Self::DoubleSpend => {
                 "Simulates spending the same coins twice by modifying historical transactions"
             }
+            Self::TimestampManipulation => {
+                "Backdates the tip block to the median time past and re-mines it, so its hash and proof-of-work hold"
+            }
+        }
+    }
+
+    /// Canonical short name accepted by `attack run <name>`
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Self::TransactionTampering => "tx-tamper",
+            Self::HashReplacement => "hash-replace",
+            Self::BlockRemoval => "block-remove",
+            Self::BlockInsertion => "block-insert",
+            Self::ProofOfWorkBypass => "pow-bypass",
+            Self::GenesisTampering => "genesis-tamper",
+            Self::MetadataCorruption => "metadata-corrupt",
+            Self::ChainReplacement => "chain-replace",
+            Self::HashRecalculation => "hash-recalc",
+            Self::DoubleSpend => "double-spend",
+            Self::TimestampManipulation => "timestamp-backdate",
         }
     }
 }
 
+impl TryFrom<&str> for AttackType {
+    type Error = String;
+
+    /// Exact, case-insensitive match on the short name
+    /// Partial names are rejected, listing the attacks they could mean
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        let name = name.to_lowercase();
+        if let Some(attack) = Self::all().into_iter().find(|t| t.short_name() == name) {
+            return Ok(attack);
+        }
+
+        let candidates: Vec<&str> = Self::all()
+            .iter()
+            .map(Self::short_name)
+            .filter(|short| short.contains(name.as_str()))
+            .collect();
+
+        match candidates.as_slice() {
+            [] => {
+                let valid: Vec<&str> = Self::all().iter().map(Self::short_name).collect();
+                Err(format!("Unknown attack '{}'. Valid names: {}", name, valid.join(", ")))
+            }
+            [only] => Err(format!("Unknown attack '{}'. Did you mean '{}'?", name, only)),
+            _ => Err(format!("Ambiguous attack '{}': matches {}", name, candidates.join(", "))),
+        }
+    }
+}
+
+/// One change an attack made to the chain, with what undoing it needs
+enum Edit {
+    /// The block at `at` was changed in place; `block` is how it was
+    Changed { at: usize, block: Block },
+    /// Only the `index` field of the block at `at` was changed; it was `index`
+    Reindexed { at: usize, index: u64 },
+    /// A block was inserted at `at`
+    Inserted { at: usize },
+    /// The block at `at` was removed
+    Removed { at: usize, block: Block },
+}
+
+/// Undo log for an attack: attacks change the chain through it, so it saves
+/// exactly the blocks they touch and nothing else. Cheaper than cloning the
+/// chain, and `restore` puts everything back
+pub struct ChainSnapshot {
+    edits: Vec<Edit>,
+    pending: Vec<Transaction>,
+    difficulty: u32,
+}
+
+impl ChainSnapshot {
+    /// Starts an undo log for `blockchain`, saving its mempool and difficulty
+    pub fn take(blockchain: &Blockchain) -> Self {
+        ChainSnapshot {
+            edits: Vec::new(),
+            pending: blockchain.pending_transactions.clone(),
+            difficulty: blockchain.difficulty,
+        }
+    }
+
+    /// The block at `at`, to change in place; saved first
+    pub fn edit<'a>(&mut self, blockchain: &'a mut Blockchain, at: usize) -> Option<&'a mut Block> {
+        let block = blockchain.get_block_mut(at)?;
+        self.edits.push(Edit::Changed { at, block: block.clone() });
+        Some(block)
+    }
+
+    /// Sets the `index` field of the block at `at`, saving only the old index
+    pub fn set_index(&mut self, blockchain: &mut Blockchain, at: usize, index: u64) {
+        if let Some(block) = blockchain.get_block_mut(at) {
+            self.edits.push(Edit::Reindexed { at, index: block.index });
+            block.index = index;
+        }
+    }
+
+    /// Inserts `block` at position `at`
+    pub fn insert(&mut self, blockchain: &mut Blockchain, at: usize, block: Block) {
+        blockchain.chain.insert(at, block);
+        self.edits.push(Edit::Inserted { at });
+    }
+
+    /// Removes the block at `at`
+    pub fn remove(&mut self, blockchain: &mut Blockchain, at: usize) {
+        let block = blockchain.chain.remove(at);
+        self.edits.push(Edit::Removed { at, block });
+    }
+
+    /// Records the block just mined onto the tip of `blockchain`
+    pub fn mined(&mut self, blockchain: &Blockchain) {
+        self.edits.push(Edit::Inserted { at: blockchain.len() - 1 });
+    }
+
+    /// Positions the attack changed, inserted or removed, as each edit saw them
+    pub fn positions(&self) -> Vec<usize> {
+        self.edits
+            .iter()
+            .map(|edit| match edit {
+                Edit::Changed { at, .. } | Edit::Reindexed { at, .. } | Edit::Inserted { at } | Edit::Removed { at, .. } => *at,
+            })
+            .collect()
+    }
+
+    /// How many whole blocks the log holds copies of
+    pub fn saved_blocks(&self) -> usize {
+        self.edits
+            .iter()
+            .filter(|edit| matches!(edit, Edit::Changed { .. } | Edit::Removed { .. }))
+            .count()
+    }
+
+    /// Undoes every edit, newest first, and puts the mempool and difficulty back
+    pub fn restore(self, blockchain: &mut Blockchain) {
+        for edit in self.edits.into_iter().rev() {
+            match edit {
+                Edit::Changed { at, block } => blockchain.chain[at] = block,
+                Edit::Reindexed { at, index } => blockchain.chain[at].index = index,
+                Edit::Inserted { at } => {
+                    blockchain.chain.remove(at);
+                }
+                Edit::Removed { at, block } => blockchain.chain.insert(at, block),
+            }
+        }
+        blockchain.pending_transactions = self.pending;
+        blockchain.difficulty = self.difficulty;
+    }
+}
+
 /// Attack simulator that runs various attacks on a blockchain
 pub struct AttackSimulator {
     /// Original blockchain before attacks (for comparison)
     original_chain: Option<Blockchain>,
     /// Results from attack runs
     pub results: Vec<AttackResult>,
+    /// Keep each attacked chain on its result (off by default)
+    capture_chains: bool,
 }
 
 impl AttackSimulator {
@@ -168,9 +380,15 @@ impl AttackSimulator {
         AttackSimulator {
             original_chain: None,
             results: Vec::new(),
+            capture_chains: false,
         }
     }
 
+    /// Keep the tampered chain on every `AttackResult` from now on
+    pub fn set_capture_chains(&mut self, capture: bool) {
+        self.capture_chains = capture;
+    }
+
     /// Save the original chain for comparison
     pub fn save_original(&mut self, blockchain: &Blockchain) {
         self.original_chain = Some(blockchain.clone());
@@ -178,49 +396,72 @@ impl AttackSimulator {
 
     /// Run a specific attack on a blockchain copy
     pub fn run_attack(&mut self, attack_type: AttackType, blockchain: &Blockchain) -> AttackResult {
-        // Create a copy to attack
-        let mut attacked_chain = blockchain.clone();
+        let result = self.simulate(attack_type, blockchain);
+        self.results.push(result.clone());
+        result
+    }
+
+    /// Attacks a fresh copy of `blockchain`
+    fn simulate(&self, attack_type: AttackType, blockchain: &Blockchain) -> AttackResult {
+        self.simulate_in_place(attack_type, &mut blockchain.clone())
+    }
+
+    /// Attacks `chain`, records the full validation errors and, if enabled, a
+    /// copy of the attacked chain on the result, then undoes the attack
+    fn simulate_in_place(&self, attack_type: AttackType, chain: &mut Blockchain) -> AttackResult {
+        let mut snapshot = ChainSnapshot::take(chain);
+        let mut result = self.attack(attack_type, chain, &mut snapshot);
+
+        result.validation_errors = validation::validate_chain(chain).errors;
+        if self.capture_chains {
+            result.attacked_chain = Some(chain.clone());
+        }
+        snapshot.restore(chain);
+        result
+    }
 
-        let result = match attack_type {
+    /// Runs one attack, leaving `chain` tampered; every change goes through `edits`
+    fn attack(&self, attack_type: AttackType, chain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
+        match attack_type {
             AttackType::TransactionTampering => {
-                self.attack_transaction_tampering(&mut attacked_chain)
+                self.attack_transaction_tampering(chain, edits)
             }
             AttackType::HashReplacement => {
-                self.attack_hash_replacement(&mut attacked_chain)
+                self.attack_hash_replacement(chain, edits)
             }
             AttackType::BlockRemoval => {
-                self.attack_block_removal(&mut attacked_chain)
+                self.attack_block_removal(chain, edits)
             }
             AttackType::BlockInsertion => {
-                self.attack_block_insertion(&mut attacked_chain)
+                self.attack_block_insertion(chain, edits)
             }
             AttackType::ProofOfWorkBypass => {
-                self.attack_pow_bypass(&mut attacked_chain)
+                self.attack_pow_bypass(chain, edits)
             }
             AttackType::GenesisTampering => {
-                self.attack_genesis_tampering(&mut attacked_chain)
+                self.attack_genesis_tampering(chain, edits)
             }
             AttackType::MetadataCorruption => {
-                self.attack_metadata_corruption(&mut attacked_chain)
+                self.attack_metadata_corruption(chain, edits)
             }
             AttackType::ChainReplacement => {
-                self.attack_chain_replacement(&mut attacked_chain)
+                self.attack_chain_replacement(chain, edits)
             }
             AttackType::HashRecalculation => {
-                self.attack_hash_recalculation(&mut attacked_chain)
+                self.attack_hash_recalculation(chain, edits)
             }
             AttackType::DoubleSpend => {
-                self.attack_double_spend(&mut attacked_chain)
+                self.attack_double_spend(chain, edits)
             }
-        };
-
-        self.results.push(result.clone());
-        result
+            AttackType::TimestampManipulation => {
+                self.attack_timestamp_manipulation(chain, edits)
+            }
+        }
     }
 
     /// Attack 1: Transaction Tampering
     /// Modify transaction amounts in an existing block
-    fn attack_transaction_tampering(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_transaction_tampering(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         // Need at least 2 blocks (genesis + 1 mined)
         if blockchain.len() < 2 {
             return AttackResult {
@@ -231,6 +472,7 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - chain too short".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
@@ -240,10 +482,10 @@ impl AttackSimulator {
             .map(|tx| tx.amount);
 
         // Tamper with transaction in block 1
-        if let Some(block) = blockchain.get_block_mut(1) {
-            if !block.transactions.is_empty() {
-                block.transactions[0].amount = 999999.0;
-            }
+        if let Some(block) = edits.edit(blockchain, 1)
+            && !block.transactions.is_empty()
+        {
+            block.transactions[0].amount = 999999 * COIN;
         }
 
         // Run validation to detect the attack
@@ -263,8 +505,8 @@ impl AttackSimulator {
 
         AttackResult {
             attack_name: AttackType::TransactionTampering.to_string(),
-            description: format!("Changed transaction amount from {:.2} to 999999.0 in block #1",
-                original_amount.unwrap_or(0.0)),
+            description: format!("Changed transaction amount from {} to 999999.00 in block #1",
+                format_amount(original_amount.unwrap_or(0))),
             detected,
             detection_method,
             explanation: "When transaction data changes, the block's hash changes. \
@@ -272,12 +514,13 @@ impl AttackSimulator {
                          This demonstrates how cryptographic linking makes data tampering detectable.".to_string(),
             blocks_affected: blockchain.len() - 1, // All blocks after tampered block
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 2: Hash Replacement
     /// Try to replace a block's hash with a fake one
-    fn attack_hash_replacement(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_hash_replacement(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 2 {
             return AttackResult {
                 attack_name: AttackType::HashReplacement.to_string(),
@@ -287,20 +530,23 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - chain too short".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
         let original_hash = blockchain.get_block(1).map(|b| b.hash.clone()).unwrap_or_default();
 
         // Replace with fake hash that looks valid (starts with zeros)
-        blockchain.tamper_with_hash(1, "0000000000000000000000000000000000000000000000000000000000000000".to_string());
+        if let Some(block) = edits.edit(blockchain, 1) {
+            block.hash = "0".repeat(64);
+        }
 
         let validation_result = validation::validate_chain(blockchain);
         let detected = !validation_result.is_valid;
 
         AttackResult {
             attack_name: AttackType::HashReplacement.to_string(),
-            description: format!("Replaced block #1's hash with fake all-zeros hash\nOriginal: {}...", &original_hash[..16.min(original_hash.len())]),
+            description: format!("Replaced block #1's hash with fake all-zeros hash\nOriginal: {}...", short_hash(&original_hash, DEFAULT_HASH_LEN)),
             detected,
             detection_method: Some("Hash Validation - stored hash doesn't match computed hash".to_string()),
             explanation: "The stored hash must match the hash computed from the block's data. \
@@ -308,12 +554,13 @@ impl AttackSimulator {
                          This demonstrates why hashes provide integrity guarantees.".to_string(),
             blocks_affected: 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 3: Block Removal
     /// Remove a block from the middle of the chain
-    fn attack_block_removal(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_block_removal(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 3 {
             return AttackResult {
                 attack_name: AttackType::BlockRemoval.to_string(),
@@ -323,6 +570,7 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - need at least 3 blocks".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
@@ -333,15 +581,15 @@ impl AttackSimulator {
         let genesis_hash = blockchain.chain[0].hash.clone();
 
         // Remove block 1
-        blockchain.chain.remove(1);
+        edits.remove(blockchain, 1);
 
         // Try to fix by updating next block's previous_hash
-        if blockchain.len() > 1 {
-            if let Some(block) = blockchain.get_block_mut(1) {
-                // This was block 2, now block 1 - try to point to genesis
-                block.previous_hash = genesis_hash;
-                block.index = 1;
-            }
+        if blockchain.len() > 1
+            && let Some(block) = edits.edit(blockchain, 1)
+        {
+            // This was block 2, now block 1 - try to point to genesis
+            block.previous_hash = genesis_hash;
+            block.index = 1;
         }
 
         let detected = !blockchain.is_valid();
@@ -349,7 +597,7 @@ impl AttackSimulator {
         AttackResult {
             attack_name: AttackType::BlockRemoval.to_string(),
             description: format!("Removed block #1 (hash: {}...) from chain of {} blocks",
-                &removed_hash[..16.min(removed_hash.len())], chain_len_before),
+                short_hash(&removed_hash, DEFAULT_HASH_LEN), chain_len_before),
             detected,
             detection_method: Some("Chain Link Validation - broken reference chain".to_string()),
             explanation: "Removing a block breaks the cryptographic chain. Each block contains \
@@ -358,12 +606,13 @@ impl AttackSimulator {
                          to update references, the modified block's hash won't match.".to_string(),
             blocks_affected: chain_len_before - 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 4: Block Insertion
     /// Insert a fake block into the chain
-    fn attack_block_insertion(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_block_insertion(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 2 {
             return AttackResult {
                 attack_name: AttackType::BlockInsertion.to_string(),
@@ -373,6 +622,7 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - chain too short".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
@@ -380,7 +630,7 @@ impl AttackSimulator {
         let fake_block = crate::block::Block::new(
             1, // Will cause index conflict
             1234567890,
-            vec![Transaction::new("Attacker".to_string(), "Victim".to_string(), 1000.0).unwrap()],
+            vec![Transaction::new("Attacker".to_string(), "Victim".to_string(), 1000 * COIN).unwrap()],
             blockchain.chain[0].hash.clone(),
             blockchain.difficulty,
         );
@@ -388,13 +638,11 @@ impl AttackSimulator {
         let chain_len_before = blockchain.len();
 
         // Insert at position 1
-        blockchain.chain.insert(1, fake_block);
+        edits.insert(blockchain, 1, fake_block);
 
         // Update indices of subsequent blocks (try to hide the attack)
         for i in 2..blockchain.chain.len() {
-            if let Some(block) = blockchain.get_block_mut(i) {
-                block.index = i as u64;
-            }
+            edits.set_index(blockchain, i, i as u64);
         }
 
         let detected = !blockchain.is_valid();
@@ -410,12 +658,13 @@ impl AttackSimulator {
                          This demonstrates why blockchains are append-only.".to_string(),
             blocks_affected: chain_len_before,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 5: Proof-of-Work Bypass
     /// Skip mining and set a hash that looks valid
-    fn attack_pow_bypass(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_pow_bypass(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 2 {
             return AttackResult {
                 attack_name: AttackType::ProofOfWorkBypass.to_string(),
@@ -425,6 +674,7 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - chain too short".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
@@ -434,7 +684,9 @@ impl AttackSimulator {
         let fake_hash = "0".repeat(difficulty as usize) +
             &"a".repeat(64 - difficulty as usize);
 
-        blockchain.tamper_with_hash(1, fake_hash.clone());
+        if let Some(block) = edits.edit(blockchain, 1) {
+            block.hash = fake_hash.clone();
+        }
 
         let detected = !blockchain.is_valid();
 
@@ -451,16 +703,17 @@ impl AttackSimulator {
                          rewriting history expensive - you must actually do the work.".to_string(),
             blocks_affected: 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 6: Genesis Tampering
     /// Try to modify the genesis block
-    fn attack_genesis_tampering(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_genesis_tampering(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         let original_hash = blockchain.get_block(0).map(|b| b.hash.clone()).unwrap_or_default();
 
         // Tamper with genesis block
-        if let Some(block) = blockchain.get_block_mut(0) {
+        if let Some(block) = edits.edit(blockchain, 0) {
             block.timestamp = 999999999999;
         }
 
@@ -470,7 +723,7 @@ impl AttackSimulator {
         AttackResult {
             attack_name: AttackType::GenesisTampering.to_string(),
             description: format!("Modified genesis block timestamp (original hash: {}...)",
-                &original_hash[..16.min(original_hash.len())]),
+                short_hash(&original_hash, DEFAULT_HASH_LEN)),
             detected,
             detection_method: Some("Hash Validation and Cascading Failure".to_string()),
             explanation: "The genesis block is the foundation of the entire chain. EVERY subsequent \
@@ -480,12 +733,13 @@ impl AttackSimulator {
                          recalculating the entire chain.".to_string(),
             blocks_affected: blockchain.len(), // Entire chain
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 7: Metadata Corruption
     /// Modify only block metadata, not transactions
-    fn attack_metadata_corruption(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_metadata_corruption(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 2 {
             return AttackResult {
                 attack_name: AttackType::MetadataCorruption.to_string(),
@@ -495,11 +749,12 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - chain too short".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
         // Modify metadata only
-        if let Some(block) = blockchain.get_block_mut(1) {
+        if let Some(block) = edits.edit(blockchain, 1) {
             let original_timestamp = block.timestamp;
             let original_nonce = block.nonce;
 
@@ -521,12 +776,13 @@ impl AttackSimulator {
                          different outputs.".to_string(),
             blocks_affected: blockchain.len() - 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 8: Chain Replacement
     /// Try to replace chain suffix with alternate chain
-    fn attack_chain_replacement(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_chain_replacement(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 3 {
             return AttackResult {
                 attack_name: AttackType::ChainReplacement.to_string(),
@@ -536,6 +792,7 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - need at least 3 blocks".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
@@ -544,16 +801,19 @@ impl AttackSimulator {
         alternate_chain.set_difficulty(blockchain.difficulty);
 
         // Add different transaction
-        alternate_chain.add_transaction("Alice".to_string(), "Eve".to_string(), 99999.0).unwrap();
+        alternate_chain.add_transaction("Alice".to_string(), "Eve".to_string(), 99999 * COIN).unwrap();
         alternate_chain.mine_block();
-        alternate_chain.add_transaction("Eve".to_string(), "Mallory".to_string(), 88888.0).unwrap();
+        alternate_chain.add_transaction("Eve".to_string(), "Mallory".to_string(), 88888 * COIN).unwrap();
         alternate_chain.mine_block();
 
         let original_len = blockchain.len();
 
         // Try to replace suffix starting from block 1
-        blockchain.chain[1] = alternate_chain.chain[1].clone();
-        blockchain.chain[1].previous_hash = blockchain.chain[0].hash.clone();
+        let genesis_hash = blockchain.chain[0].hash.clone();
+        if let Some(block) = edits.edit(blockchain, 1) {
+            *block = alternate_chain.chain[1].clone();
+            block.previous_hash = genesis_hash;
+        }
 
         let detected = !blockchain.is_valid();
 
@@ -569,12 +829,13 @@ impl AttackSimulator {
                          you must re-mine everything after the change.".to_string(),
             blocks_affected: original_len - 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 9: Hash Recalculation
     /// Try to hide tampering by recalculating hashes
-    fn attack_hash_recalculation(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_hash_recalculation(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         if blockchain.len() < 3 {
             return AttackResult {
                 attack_name: AttackType::HashRecalculation.to_string(),
@@ -584,13 +845,14 @@ impl AttackSimulator {
                 explanation: "Cannot run attack - need at least 3 blocks".to_string(),
                 blocks_affected: 0,
                 is_chain_valid: true,
+                ..Default::default()
             };
         }
 
         // Tamper with block 1
-        if let Some(block) = blockchain.get_block_mut(1) {
+        if let Some(block) = edits.edit(blockchain, 1) {
             if !block.transactions.is_empty() {
-                block.transactions[0].amount = 55555.0;
+                block.transactions[0].amount = 55555 * COIN;
             }
             // Recalculate hash for THIS block only
             block.hash = block.calculate_hash();
@@ -611,26 +873,28 @@ impl AttackSimulator {
                          infeasible.".to_string(),
             blocks_affected: blockchain.len() - 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Attack 10: Double Spend
     /// Simulate spending the same coins twice
-    fn attack_double_spend(&self, blockchain: &mut Blockchain) -> AttackResult {
+    fn attack_double_spend(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
         // Create a blockchain with a transaction
-        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10.0).unwrap();
+        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
         blockchain.mine_block();
+        edits.mined(blockchain);
 
         let original_tx_hash = blockchain.get_block(1)
             .and_then(|b| b.transactions.first())
-            .map(|tx| format!("{}->{}:{:.2}", tx.sender, tx.receiver, tx.amount))
+            .map(|tx| format!("{}->{}:{}", tx.sender, tx.receiver, format_amount(tx.amount)))
             .unwrap_or_default();
 
         // Now try to change the past to make Alice give to Carol instead
-        if let Some(block) = blockchain.get_block_mut(1) {
-            if !block.transactions.is_empty() {
-                block.transactions[0].receiver = "Carol".to_string();
-            }
+        if let Some(block) = edits.edit(blockchain, 1)
+            && !block.transactions.is_empty()
+        {
+            block.transactions[0].receiver = "Carol".to_string();
         }
 
         let detected = !blockchain.is_valid();
@@ -648,51 +912,70 @@ impl AttackSimulator {
                          expensive to attempt.".to_string(),
             blocks_affected: 1,
             is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
+        }
+    }
+
+    /// Attack 11: Timestamp Manipulation
+    /// Backdate the tip block and redo its proof-of-work
+    fn attack_timestamp_manipulation(&self, blockchain: &mut Blockchain, edits: &mut ChainSnapshot) -> AttackResult {
+        if blockchain.len() < 2 {
+            return AttackResult {
+                attack_name: AttackType::TimestampManipulation.to_string(),
+                description: AttackType::TimestampManipulation.description().to_string(),
+                detected: false,
+                detection_method: None,
+                explanation: "Cannot run attack - chain too short".to_string(),
+                blocks_affected: 0,
+                is_chain_valid: true,
+                ..Default::default()
+            };
+        }
+
+        // The latest timestamp the rule rejects for the tip
+        let tip = blockchain.len() - 1;
+        let median = blockchain.median_time_past(tip, MEDIAN_TIME_PAST_WINDOW).unwrap_or(0);
+        let prefix = blockchain.pow_prefix_char();
+        let algorithm = blockchain.pow_algorithm;
+        if let Some(block) = edits.edit(blockchain, tip) {
+            block.timestamp = median;
+            // Re-mining makes the hash and proof-of-work valid again
+            block.hash = block.calculate_hash();
+            block.mine_block_with_algorithm(prefix, algorithm);
+        }
+
+        let detected = !blockchain.is_valid();
+
+        AttackResult {
+            attack_name: AttackType::TimestampManipulation.to_string(),
+            description: format!(
+                "Backdated block #{} to the median time past of the {} blocks before it ({}) and re-mined it",
+                tip, MEDIAN_TIME_PAST_WINDOW, median
+            ),
+            detected,
+            detection_method: Some("Timestamp Validation - not after the median time past".to_string()),
+            explanation: "Miners pick their own timestamps, so a dishonest miner may lie about the time, \
+                         for example to drag down the difficulty. Clocks disagree, so a block may not be \
+                         rejected just for being early; instead it must be later than the MEDIAN timestamp \
+                         of the previous 11 blocks. One miner can't move a median on its own, and the chain's \
+                         notion of time can only go forward. Re-mining kept this block's hash and \
+                         proof-of-work valid, so the timestamp rule is the only check that catches it.".to_string(),
+            blocks_affected: 1,
+            is_chain_valid: blockchain.is_valid(),
+            ..Default::default()
         }
     }
 
     /// Run all attacks and return results
+    /// The chain is copied once; each attack is undone before the next one runs
     pub fn run_all_attacks(&mut self, blockchain: &Blockchain) -> Vec<AttackResult> {
         let mut results = Vec::new();
+        let mut chain = blockchain.clone();
 
         for attack_type in AttackType::all() {
-            // Create fresh copy for each attack
-            let mut chain_copy = blockchain.clone();
+            let result = self.simulate_in_place(attack_type, &mut chain);
 
-            let result = match attack_type {
-                AttackType::TransactionTampering => {
-                    self.attack_transaction_tampering(&mut chain_copy)
-                }
-                AttackType::HashReplacement => {
-                    self.attack_hash_replacement(&mut chain_copy)
-                }
-                AttackType::BlockRemoval => {
-                    self.attack_block_removal(&mut chain_copy)
-                }
-                AttackType::BlockInsertion => {
-                    self.attack_block_insertion(&mut chain_copy)
-                }
-                AttackType::ProofOfWorkBypass => {
-                    self.attack_pow_bypass(&mut chain_copy)
-                }
-                AttackType::GenesisTampering => {
-                    self.attack_genesis_tampering(&mut chain_copy)
-                }
-                AttackType::MetadataCorruption => {
-                    self.attack_metadata_corruption(&mut chain_copy)
-                }
-                AttackType::ChainReplacement => {
-                    self.attack_chain_replacement(&mut chain_copy)
-                }
-                AttackType::HashRecalculation => {
-                    self.attack_hash_recalculation(&mut chain_copy)
-                }
-                AttackType::DoubleSpend => {
-                    self.attack_double_spend(&mut chain_copy)
-                }
-            };
-
-            println!("{}", result);
+            log::info!("{}", result);
             results.push(result);
         }
 
@@ -700,8 +983,33 @@ impl AttackSimulator {
         results
     }
 
-    /// Generate summary report of all attacks
+    /// Machine-readable results: a JSON array in run order, each entry
+    /// carrying the result fields plus its `severity`
+    pub fn results_as_json(&self) -> String {
+        self.results_as_json_ordered(SummaryOrder::RunOrder)
+    }
+
+    /// `results_as_json` with the entries in the given order
+    pub fn results_as_json_ordered(&self, order: SummaryOrder) -> String {
+        let entries: Vec<serde_json::Value> = self.ordered_results(order)
+            .into_iter()
+            .map(|result| {
+                let mut entry = serde_json::to_value(result).expect("AttackResult serializes");
+                entry["severity"] = serde_json::Value::from(result.severity());
+                entry
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&entries).expect("JSON values serialize")
+    }
+
+    /// Generate summary report of all attacks, in run order
     pub fn generate_summary(&self) -> String {
+        self.generate_summary_ordered(SummaryOrder::RunOrder)
+    }
+
+    /// Generate summary report with the individual results in the given order
+    pub fn generate_summary_ordered(&self, order: SummaryOrder) -> String {
         if self.results.is_empty() {
             return "No attack results available. Run attacks first.".to_string();
         }
@@ -709,9 +1017,9 @@ impl AttackSimulator {
         let detected_count = self.results.iter().filter(|r| r.detected).count();
         let total_count = self.results.len();
 
-        let mut report = format!("\n╔════════════════════════════════════════════════════════╗\n");
-        report.push_str(&format!("║           Attack Simulation Summary Report              ║\n"));
-        report.push_str(&format!("╚════════════════════════════════════════════════════════╝\n\n"));
+        let mut report = String::from("\n╔════════════════════════════════════════════════════════╗\n");
+        report.push_str("║           Attack Simulation Summary Report              ║\n");
+        report.push_str("╚════════════════════════════════════════════════════════╝\n\n");
         report.push_str(&format!("Total Attacks Run:     {}\n", total_count));
         report.push_str(&format!("Attacks Detected:     {} / {} ({:.0}%)\n",
             detected_count, total_count,
@@ -729,10 +1037,13 @@ impl AttackSimulator {
         report.push_str("Individual Attack Results:\n");
         report.push_str("─────────────────────────────────────────────────────────────\n");
 
-        for (i, result) in self.results.iter().enumerate() {
+        for (i, result) in self.ordered_results(order).into_iter().enumerate() {
             report.push_str(&format!("\n{}. {}\n", i + 1, result.attack_name));
             report.push_str(&format!("   Detected: {}\n", if result.detected { "✓ YES" } else { "✗ NO" }));
             report.push_str(&format!("   Blocks Affected: {}\n", result.blocks_affected));
+            if order == SummaryOrder::Severity {
+                report.push_str(&format!("   Severity: {}\n", result.severity()));
+            }
         }
 
         report.push_str("\n─────────────────────────────────────────────────────────────\n");
@@ -746,6 +1057,16 @@ impl AttackSimulator {
 
         report
     }
+
+    /// The results in `order`
+    fn ordered_results(&self, order: SummaryOrder) -> Vec<&AttackResult> {
+        let mut ordered: Vec<&AttackResult> = self.results.iter().collect();
+        if order == SummaryOrder::Severity {
+            // Stable sort keeps run order among equally severe attacks
+            ordered.sort_by_key(|result| std::cmp::Reverse(result.severity()));
+        }
+        ordered
+    }
 }
 
 impl Default for AttackSimulator {
@@ -760,9 +1081,9 @@ mod tests {
 
     fn create_test_blockchain() -> Blockchain {
         let mut blockchain = Blockchain::new();
-        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10.0).unwrap();
+        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
         blockchain.mine_block();
-        blockchain.add_transaction("Bob".to_string(), "Charlie".to_string(), 5.0).unwrap();
+        blockchain.add_transaction("Bob".to_string(), "Charlie".to_string(), 5 * COIN).unwrap();
         blockchain.mine_block();
         blockchain
     }
@@ -771,7 +1092,7 @@ mod tests {
     fn test_attack_transaction_tampering() {
         let blockchain = create_test_blockchain();
         let simulator = AttackSimulator::new();
-        let result = simulator.attack_transaction_tampering(&mut blockchain.clone());
+        let result = simulator.attack_transaction_tampering(&mut blockchain.clone(), &mut ChainSnapshot::take(&blockchain));
 
         assert!(result.detected);
         assert!(!result.is_chain_valid);
@@ -781,7 +1102,7 @@ mod tests {
     fn test_attack_hash_replacement() {
         let blockchain = create_test_blockchain();
         let simulator = AttackSimulator::new();
-        let result = simulator.attack_hash_replacement(&mut blockchain.clone());
+        let result = simulator.attack_hash_replacement(&mut blockchain.clone(), &mut ChainSnapshot::take(&blockchain));
 
         assert!(result.detected);
         assert!(!result.is_chain_valid);
@@ -791,12 +1112,28 @@ mod tests {
     fn test_attack_genesis_tampering() {
         let blockchain = create_test_blockchain();
         let simulator = AttackSimulator::new();
-        let result = simulator.attack_genesis_tampering(&mut blockchain.clone());
+        let result = simulator.attack_genesis_tampering(&mut blockchain.clone(), &mut ChainSnapshot::take(&blockchain));
 
         assert!(result.detected);
         assert_eq!(result.blocks_affected, blockchain.len()); // Entire chain
     }
 
+    #[test]
+    fn test_timestamp_manipulation_is_caught_only_by_the_timestamp_rule() {
+        let blockchain = create_test_blockchain();
+        let mut simulator = AttackSimulator::new();
+        let result = simulator.run_attack(AttackType::TimestampManipulation, &blockchain);
+
+        assert!(result.detected);
+        assert_eq!(result.validation_errors.len(), 1, "{:?}", result.validation_errors);
+        let tip_median = blockchain.median_time_past(2, MEDIAN_TIME_PAST_WINDOW).unwrap();
+        assert!(matches!(
+            result.validation_errors[0],
+            ValidationError::TimestampTooEarly { index: 2, timestamp, median_time_past } if timestamp == tip_median && median_time_past == tip_median
+        ));
+        assert!(blockchain.is_valid(), "the attack ran on a copy");
+    }
+
     #[test]
     fn test_all_attacks_detected() {
         let blockchain = create_test_blockchain();
@@ -808,6 +1145,98 @@ mod tests {
         assert!(all_detected, "Not all attacks were detected");
     }
 
+    #[test]
+    fn test_run_all_attacks_reuses_one_copy() {
+        let mut blockchain = create_test_blockchain();
+        blockchain.add_transaction("Charlie".to_string(), "Dave".to_string(), COIN).unwrap();
+        let before = serde_json::to_string(&blockchain).unwrap();
+
+        let mut simulator = AttackSimulator::new();
+        simulator.set_capture_chains(true);
+        let results = simulator.run_all_attacks(&blockchain);
+        assert_eq!(serde_json::to_string(&blockchain).unwrap(), before);
+
+        // Same results as attacking a fresh copy each time; chain replacement
+        // mines on the system clock, so compare errors by kind and block
+        let summary = |errors: &[ValidationError]| -> Vec<(&'static str, usize)> {
+            errors.iter().map(|e| (e.kind(), e.block_index())).collect()
+        };
+        for (attack_type, result) in AttackType::all().into_iter().zip(&results) {
+            let naive = simulator.simulate(attack_type, &blockchain);
+            assert_eq!(result.description, naive.description, "{}", attack_type);
+            assert_eq!(result.blocks_affected, naive.blocks_affected, "{}", attack_type);
+            assert_eq!(summary(&result.validation_errors), summary(&naive.validation_errors), "{}", attack_type);
+            assert_eq!(result.attacked_chain.as_ref().map(Blockchain::len), naive.attacked_chain.as_ref().map(Blockchain::len));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restores_removed_block() {
+        let mut blockchain = create_test_blockchain();
+        let before = serde_json::to_string(&blockchain).unwrap();
+
+        let mut snapshot = ChainSnapshot::take(&blockchain);
+        snapshot.remove(&mut blockchain, 1);
+        snapshot.set_index(&mut blockchain, 1, 1);
+        assert_eq!(snapshot.saved_blocks(), 1);
+        snapshot.restore(&mut blockchain);
+        assert_eq!(serde_json::to_string(&blockchain).unwrap(), before);
+    }
+
+    #[test]
+    fn test_attacks_save_only_the_blocks_they_change() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for i in 0..20 {
+            blockchain.add_transaction(format!("Sender{}", i), String::from("Bob"), COIN).unwrap();
+            blockchain.mine_block();
+        }
+        let original: Vec<String> = blockchain.blocks().map(|block| serde_json::to_string(block).unwrap()).collect();
+        let before = serde_json::to_string(&blockchain).unwrap();
+        let simulator = AttackSimulator::new();
+
+        for attack_type in AttackType::all() {
+            let mut snapshot = ChainSnapshot::take(&blockchain);
+            simulator.attack(attack_type, &mut blockchain, &mut snapshot);
+            assert!(snapshot.saved_blocks() <= 2, "{} saved {} blocks", attack_type, snapshot.saved_blocks());
+
+            // Every block the log doesn't name is one of the original blocks, as it was
+            let named = snapshot.positions();
+            for (at, block) in blockchain.blocks().enumerate().filter(|(at, _)| !named.contains(at)) {
+                assert!(original.contains(&serde_json::to_string(block).unwrap()), "{} changed block {}", attack_type, at);
+            }
+
+            snapshot.restore(&mut blockchain);
+            assert_eq!(serde_json::to_string(&blockchain).unwrap(), before, "{}", attack_type);
+        }
+    }
+
+    #[test]
+    fn test_captured_chain_differs_from_original() {
+        let blockchain = create_test_blockchain();
+        let mut simulator = AttackSimulator::new();
+
+        // Off by default
+        let result = simulator.run_attack(AttackType::TransactionTampering, &blockchain);
+        assert!(result.attacked_chain.is_none());
+
+        simulator.set_capture_chains(true);
+        let result = simulator.run_attack(AttackType::TransactionTampering, &blockchain);
+        let attacked = result.attacked_chain.as_ref().expect("chain captured");
+
+        assert_eq!(attacked.len(), blockchain.len());
+        assert_ne!(attacked.chain[1].transactions, blockchain.chain[1].transactions);
+        assert_eq!(attacked.chain[2].transactions, blockchain.chain[2].transactions);
+        assert!(blockchain.is_valid());
+
+        // The specific errors are kept, not just the verdict
+        assert!(matches!(
+            result.validation_errors.first(),
+            Some(ValidationError::InvalidHash { index: 1, .. })
+        ));
+        assert_eq!(result.validation_errors, validation::validate_chain(attacked).errors);
+    }
+
     #[test]
     fn test_attack_type_display() {
         assert_eq!(AttackType::TransactionTampering.to_string(), "Transaction Tampering");
@@ -817,6 +1246,119 @@ mod tests {
     #[test]
     fn test_attack_type_all() {
         let all = AttackType::all();
-        assert_eq!(all.len(), 10);
+        assert_eq!(all.len(), 11);
+    }
+
+    #[test]
+    fn test_attack_type_from_short_name() {
+        let expected = [
+            ("tx-tamper", AttackType::TransactionTampering),
+            ("hash-replace", AttackType::HashReplacement),
+            ("block-remove", AttackType::BlockRemoval),
+            ("block-insert", AttackType::BlockInsertion),
+            ("pow-bypass", AttackType::ProofOfWorkBypass),
+            ("genesis-tamper", AttackType::GenesisTampering),
+            ("metadata-corrupt", AttackType::MetadataCorruption),
+            ("chain-replace", AttackType::ChainReplacement),
+            ("hash-recalc", AttackType::HashRecalculation),
+            ("double-spend", AttackType::DoubleSpend),
+            ("timestamp-backdate", AttackType::TimestampManipulation),
+        ];
+        assert_eq!(expected.len(), AttackType::all().len());
+
+        for (name, attack) in expected {
+            assert_eq!(AttackType::try_from(name), Ok(attack));
+            assert_eq!(attack.short_name(), name);
+        }
+        assert_eq!(AttackType::try_from("Double-Spend"), Ok(AttackType::DoubleSpend));
+    }
+
+    #[test]
+    fn test_attack_type_rejects_ambiguous_and_unknown_names() {
+        let ambiguous = AttackType::try_from("hash").unwrap_err();
+        assert!(ambiguous.contains("Ambiguous"));
+        assert!(ambiguous.contains("hash-replace") && ambiguous.contains("hash-recalc"));
+
+        let partial = AttackType::try_from("recalc").unwrap_err();
+        assert!(partial.contains("Did you mean 'hash-recalc'"));
+
+        let unknown = AttackType::try_from("sybil").unwrap_err();
+        assert!(unknown.contains("Valid names: tx-tamper,"));
+    }
+
+    fn sample_result(name: &str, detected: bool, blocks_affected: usize) -> AttackResult {
+        AttackResult {
+            attack_name: name.to_string(),
+            description: String::new(),
+            detected,
+            detection_method: None,
+            explanation: String::new(),
+            blocks_affected,
+            is_chain_valid: !detected,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_attack_severity_ordering() {
+        let minor = sample_result("Minor", true, 1);
+        let major = sample_result("Major", true, 5);
+        let missed = sample_result("Missed", false, 0);
+
+        assert!(major.severity() > minor.severity());
+        assert!(missed.severity() > major.severity());
+        assert!(missed > major && major > minor);
+
+        let mut simulator = AttackSimulator::new();
+        simulator.results = vec![minor, missed, major];
+
+        // Default keeps run order
+        let summary = simulator.generate_summary();
+        let positions: Vec<usize> = ["1. Minor", "2. Missed", "3. Major"]
+            .iter()
+            .map(|entry| summary.find(entry).expect("entry in run order"))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Severity order puts the undetected attack first
+        let sorted = simulator.generate_summary_ordered(SummaryOrder::Severity);
+        assert!(sorted.contains("1. Missed"));
+        assert!(sorted.contains("2. Major"));
+        assert!(sorted.contains("3. Minor"));
+    }
+
+    #[test]
+    fn test_results_as_json_shape() {
+        let mut simulator = AttackSimulator::new();
+        simulator.results = vec![sample_result("Tamper", true, 2)];
+
+        let json: serde_json::Value = serde_json::from_str(&simulator.results_as_json()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(entry["attack_name"], "Tamper");
+        assert_eq!(entry["detected"], true);
+        assert_eq!(entry["detection_method"], serde_json::Value::Null);
+        assert_eq!(entry["blocks_affected"], 2);
+        assert_eq!(entry["is_chain_valid"], false);
+        assert_eq!(entry["severity"], 2);
+    }
+
+    #[test]
+    fn test_results_as_json_by_severity() {
+        let mut simulator = AttackSimulator::new();
+        simulator.results = vec![
+            sample_result("Minor", true, 1),
+            sample_result("Missed", false, 1),
+            sample_result("Major", true, 5),
+        ];
+
+        let names = |order| {
+            let json: serde_json::Value = serde_json::from_str(&simulator.results_as_json_ordered(order)).unwrap();
+            json.as_array().unwrap().iter().map(|entry| entry["attack_name"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(SummaryOrder::RunOrder), vec!["Minor", "Missed", "Major"]);
+        assert_eq!(names(SummaryOrder::Severity), vec!["Missed", "Major", "Minor"]);
     }
 }
@@ -0,0 +1,94 @@
+//! A replayable record of how a chain was built
+//!
+//! A chain made with `Blockchain::with_audit` appends an `AuditEvent` for
+//! every mutating operation: transactions queued, blocks mined or accepted,
+//! tampering, re-mining and reorgs. Unlike observers, the tamper methods are
+//! logged too, so a lesson can show exactly which step broke the chain.
+//! Timestamps are read from the chain's clock without advancing it, so
+//! auditing never changes the timestamps blocks are mined with. The log keeps
+//! the latest `AUDIT_LOG_CAPACITY` events and is not persisted.
+
+use crate::crypto::{short_hash, DEFAULT_HASH_LEN};
+use std::fmt;
+
+/// Most events an audit log keeps; older ones are dropped first
+pub const AUDIT_LOG_CAPACITY: usize = 1024;
+
+/// What changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditKind {
+    /// A transaction was queued in the mempool
+    TransactionAdded { id: String, sender: String, receiver: String, amount: u64 },
+    /// A block was mined or accepted onto the tip
+    BlockMined { index: u64, hash: String, transactions: usize },
+    /// The tip block was removed
+    BlockPopped { index: u64 },
+    /// A `tamper_with_*` method changed `field` of block `index`
+    Tampered { index: usize, field: &'static str },
+    /// `remine_from` re-mined `blocks` blocks starting at `from`
+    Remined { from: usize, blocks: usize },
+    /// The chain switched to a fork, from tip `old_tip` to `new_tip`
+    Reorg { old_tip: String, new_tip: String, replaced: usize, adopted: usize },
+}
+
+/// One mutating operation on a chain, stamped by the chain's clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub timestamp: u128,
+    pub kind: AuditKind,
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditKind::TransactionAdded { id, sender, receiver, amount } => write!(
+                f,
+                "transaction added: {} -> {} : {} ({})",
+                sender,
+                receiver,
+                crate::transaction::format_amount(*amount),
+                short_hash(id, DEFAULT_HASH_LEN)
+            ),
+            AuditKind::BlockMined { index, hash, transactions } => write!(
+                f,
+                "block #{} mined with {} transaction(s), hash {}",
+                index,
+                transactions,
+                short_hash(hash, DEFAULT_HASH_LEN)
+            ),
+            AuditKind::BlockPopped { index } => write!(f, "block #{} popped", index),
+            AuditKind::Tampered { index, field } => write!(f, "TAMPERED: {} of block #{}", field, index),
+            AuditKind::Remined { from, blocks } => write!(f, "re-mined {} block(s) from #{}", blocks, from),
+            AuditKind::Reorg { old_tip, new_tip, replaced, adopted } => write!(
+                f,
+                "reorg: replaced {} block(s) with {}, tip {} -> {}",
+                replaced,
+                adopted,
+                short_hash(old_tip, DEFAULT_HASH_LEN),
+                short_hash(new_tip, DEFAULT_HASH_LEN)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.timestamp, self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_are_shortened_like_everywhere_else() {
+        let hash = "a".repeat(64);
+        let mined = AuditKind::BlockMined { index: 1, hash: hash.clone(), transactions: 0 };
+        assert!(mined.to_string().ends_with(&format!("hash {}", short_hash(&hash, DEFAULT_HASH_LEN))));
+
+        // A tampered tip shorter than the display length is shown whole
+        let reorg = AuditKind::Reorg { old_tip: String::from("wrong"), new_tip: hash, replaced: 1, adopted: 2 };
+        assert!(reorg.to_string().ends_with(&format!("tip wrong -> {}", "a".repeat(DEFAULT_HASH_LEN))));
+    }
+}
@@ -1,30 +1,128 @@
+use crate::consensus::{PowAlgorithm, DEFAULT_POW_PREFIX};
 use crate::crypto::calculate_hash;
+use crate::merkle;
 use crate::transaction::Transaction;
+use crate::validation::{self, ValidationError};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Format version stamped on newly created blocks. Version 0 marks blocks
+/// saved before versions existed (and genesis); from version 1 the hash
+/// commits to the version and the declared difficulty, and from version 2
+/// (`merkle::TAGGED_MERKLE_VERSION`) the Merkle root uses the tagged tree
+pub const BLOCK_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
+    /// Format version (see `BLOCK_VERSION`); absent in older saves, which read as 0
+    #[serde(default)]
+    pub version: u32,
     pub index: u64,
     pub timestamp: u128,
     pub transactions: Vec<Transaction>,
     pub previous_hash: String,
+    /// Merkle root of the transaction ids, committed to by the block hash
+    #[serde(default)]
+    pub merkle_root: String,
     pub nonce: u64,
     pub difficulty: u32,
     pub hash: String,
+    /// Validator that produced the block under proof-of-stake (None for mined blocks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer: Option<String>,
+    /// Commitment to every balance once this block is applied (see
+    /// `validation::state_root`), committed to by the block hash
+    /// None for genesis and for blocks saved before state roots existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_root: Option<String>,
+}
+
+/// Block header: everything except the transactions
+/// Enough to check links, indexes, and proof-of-work (like an SPV client)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    #[serde(default)]
+    pub version: u32,
+    pub index: u64,
+    pub timestamp: u128,
+    pub previous_hash: String,
+    pub merkle_root: String,
+    pub nonce: u64,
+    pub difficulty: u32,
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_root: Option<String>,
+}
+
+impl BlockHeader {
+    /// Recomputes the block hash from the header fields alone
+    pub fn calculate_hash(&self) -> String {
+        HashedFields {
+            version: self.version,
+            index: self.index,
+            timestamp: self.timestamp,
+            merkle_root: &self.merkle_root,
+            previous_hash: &self.previous_hash,
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            producer: self.producer.as_deref(),
+            state_root: self.state_root.as_deref(),
+        }
+        .hash()
+    }
+}
+
+/// The header fields the block hash commits to, borrowed from a block or header
+struct HashedFields<'a> {
+    version: u32,
+    index: u64,
+    timestamp: u128,
+    merkle_root: &'a str,
+    previous_hash: &'a str,
+    nonce: u64,
+    difficulty: u32,
+    producer: Option<&'a str>,
+    state_root: Option<&'a str>,
+}
+
+impl HashedFields<'_> {
+    /// Hashes the header fields; the block hash never looks at transactions directly
+    /// Versioned blocks commit to their version and difficulty too, so a block
+    /// can't be relabelled with more work than it claimed when mined; version 0
+    /// blocks hash as they were saved. A proof-of-stake producer and a state root
+    /// are appended last, so blocks without them hash as before too
+    fn hash(&self) -> String {
+        let mut block_string = format!("{}{}{}{}{}", self.index, self.timestamp, self.merkle_root, self.previous_hash, self.nonce);
+        if self.version > 0 {
+            block_string.push_str(&format!("v{}difficulty:{}", self.version, self.difficulty));
+        }
+        block_string.push_str(self.producer.unwrap_or_default());
+        if let Some(state_root) = self.state_root {
+            block_string.push_str(&format!("state:{}", state_root));
+        }
+        calculate_hash(&block_string)
+    }
 }
 
 impl Block {
     /// Creates a new block and calculates its hash
     pub fn new(index: u64, timestamp: u128, transactions: Vec<Transaction>, previous_hash: String, difficulty: u32) -> Self {
         let mut block = Block {
+            version: BLOCK_VERSION,
             index,
             timestamp,
             transactions,
             previous_hash,
+            merkle_root: String::new(),
             nonce: 0,
             difficulty,
             hash: String::new(),
+            producer: None,
+            state_root: None,
         };
+        block.merkle_root = block.compute_merkle_root();
         block.hash = block.calculate_hash();
         block
     }
@@ -32,65 +130,216 @@ impl Block {
     /// Creates a new block without mining (for testing)
     #[cfg(test)]
     pub fn new_unmined(index: u64, timestamp: u128, transactions: Vec<Transaction>, previous_hash: String, difficulty: u32) -> Self {
-        Block {
+        let mut block = Block {
+            version: BLOCK_VERSION,
             index,
             timestamp,
             transactions,
             previous_hash,
+            merkle_root: String::new(),
             nonce: 0,
             difficulty,
             hash: String::new(),
+            producer: None,
+            state_root: None,
+        };
+        block.merkle_root = block.compute_merkle_root();
+        block
+    }
+
+    /// Ids of the block's transactions, in order (the Merkle leaves)
+    pub fn transaction_ids(&self) -> Vec<String> {
+        self.transactions.iter().map(|tx| tx.id()).collect()
+    }
+
+    /// Position of the first transaction with `tx_id`, which is also its Merkle leaf index
+    pub fn transaction_index(&self, tx_id: &str) -> Option<usize> {
+        self.transactions.iter().position(|tx| tx.id() == tx_id)
+    }
+
+    /// Approximate encoded size: header field byte lengths plus every transaction
+    pub fn serialized_size(&self) -> usize {
+        size_of::<u64>() * 2
+            + size_of::<u128>()
+            + size_of::<u32>() * 2
+            + self.previous_hash.len()
+            + self.merkle_root.len()
+            + self.hash.len()
+            + self.producer.as_ref().map_or(0, String::len)
+            + self.state_root.as_ref().map_or(0, String::len)
+            + self.transactions.iter().map(Transaction::serialized_size).sum::<usize>()
+    }
+
+    /// First transaction id that appears more than once in this block, if any
+    pub fn duplicate_transaction_id(&self) -> Option<String> {
+        let mut seen = HashSet::new();
+        self.transaction_ids().into_iter().find(|id| !seen.insert(id.clone()))
+    }
+
+    /// Computes the Merkle root from the block's current transactions, with
+    /// the tree its version calls for
+    pub fn compute_merkle_root(&self) -> String {
+        if self.version >= merkle::TAGGED_MERKLE_VERSION {
+            merkle::merkle_root(&self.transaction_ids())
+        } else {
+            merkle::legacy_merkle_root(&self.transaction_ids())
         }
     }
 
     /// Calculates the hash of the block based on its contents
+    /// Transactions are committed through their Merkle root
     pub fn calculate_hash(&self) -> String {
-        // Create a deterministic string representation of transactions
-        let transactions_string: String = self.transactions
-            .iter()
-            .map(|tx| format!("{}{}{}", tx.sender, tx.receiver, tx.amount))
-            .collect();
+        self.hash_with_merkle_root(&self.compute_merkle_root())
+    }
 
-        let block_string = format!(
-            "{}{}{}{}{}",
-            self.index, self.timestamp, transactions_string, self.previous_hash, self.nonce
-        );
-        calculate_hash(&block_string)
+    /// Hashes the header with an already-computed Merkle root (saves work while mining)
+    fn hash_with_merkle_root(&self, merkle_root: &str) -> String {
+        HashedFields {
+            version: self.version,
+            index: self.index,
+            timestamp: self.timestamp,
+            merkle_root,
+            previous_hash: &self.previous_hash,
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            producer: self.producer.as_deref(),
+            state_root: self.state_root.as_deref(),
+        }
+        .hash()
+    }
+
+    /// Sets the state root and recomputes the hash (before mining)
+    pub fn with_state_root(mut self, state_root: String) -> Self {
+        self.state_root = Some(state_root);
+        self.hash = self.calculate_hash();
+        self
+    }
+
+    /// Recomputes the hash and Merkle root and checks them against the stored ones
+    /// Use after deserializing, so a hand-edited block fails on its own
+    pub fn verify_integrity(&self) -> Result<(), ValidationError> {
+        validation::verify_block_hash(self)?;
+        validation::verify_merkle_root(self)
+    }
+
+    /// Returns the block's header (drops the transactions)
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            version: self.version,
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            merkle_root: self.merkle_root.clone(),
+            nonce: self.nonce,
+            difficulty: self.difficulty,
+            hash: self.hash.clone(),
+            producer: self.producer.clone(),
+            state_root: self.state_root.clone(),
+        }
     }
 
     /// Checks if a hash meets the difficulty requirement
     /// Returns true if the hash starts with the specified number of zeros
     pub fn is_hash_valid(hash: &str, difficulty: u32) -> bool {
-        let prefix = "0".repeat(difficulty as usize);
-        hash.starts_with(&prefix)
+        Block::meets_target(hash, difficulty, DEFAULT_POW_PREFIX)
+    }
+
+    /// Like `is_hash_valid`, but the leading digits must all be `prefix`
+    pub fn meets_target(hash: &str, difficulty: u32, prefix: char) -> bool {
+        let target = prefix.to_string().repeat(difficulty as usize);
+        hash.starts_with(&target)
+    }
+
+    /// Whether the block's `algorithm` digest meets its difficulty in `prefix` digits
+    pub fn meets_pow(&self, prefix: char, algorithm: PowAlgorithm) -> bool {
+        Block::meets_target(&algorithm.pow_hash(&self.hash), self.difficulty, prefix)
     }
 
     /// Mines the block by finding a nonce that produces a valid hash
     /// This is the proof-of-work algorithm - brute force search for valid hash
     pub fn mine_block(&mut self) {
-        // Target string with required leading zeros
-        let target = "0".repeat(self.difficulty as usize);
+        self.mine_block_with_prefix(DEFAULT_POW_PREFIX);
+    }
+
+    /// Mines the block against a target of leading `prefix` digits
+    pub fn mine_block_with_prefix(&mut self, prefix: char) {
+        self.mine_block_with_algorithm(prefix, PowAlgorithm::Sha256);
+    }
+
+    /// Mines the block until its `algorithm` digest has `prefix` leading digits
+    pub fn mine_block_with_algorithm(&mut self, prefix: char, algorithm: PowAlgorithm) {
+        // The transactions don't change while mining, so hash them once
+        self.merkle_root = self.compute_merkle_root();
 
         // Mining loop: increment nonce until we find a valid hash
         // This is the "burning electricity" part
-        while !self.hash.starts_with(&target) {
+        while !self.meets_pow(prefix, algorithm) {
             self.nonce += 1;
-            self.hash = self.calculate_hash();
+            self.hash = self.hash_with_merkle_root(&self.merkle_root);
         }
 
         // When we exit the loop, we've found a valid hash
         // The nonce proves we did the work
     }
 
+    /// Tries up to `max_iters` nonces, resuming from the current one
+    /// Returns true once the hash meets the difficulty, so callers can mine in slices
+    pub fn mine_step(&mut self, max_iters: u64) -> bool {
+        self.mine_step_with_prefix(max_iters, DEFAULT_POW_PREFIX)
+    }
+
+    /// `mine_step` against a target of leading `prefix` digits
+    pub fn mine_step_with_prefix(&mut self, max_iters: u64, prefix: char) -> bool {
+        self.mine_step_with_algorithm(max_iters, prefix, PowAlgorithm::Sha256)
+    }
+
+    /// `mine_step` against the `algorithm` digest and leading `prefix` digits
+    pub fn mine_step_with_algorithm(&mut self, max_iters: u64, prefix: char, algorithm: PowAlgorithm) -> bool {
+        self.merkle_root = self.compute_merkle_root();
+
+        for _ in 0..max_iters {
+            if self.meets_pow(prefix, algorithm) {
+                return true;
+            }
+            self.nonce += 1;
+            self.hash = self.hash_with_merkle_root(&self.merkle_root);
+        }
+
+        self.meets_pow(prefix, algorithm)
+    }
+
     /// Creates the genesis block (first block in the chain)
+    /// It stays at version 0, so every chain shares one genesis hash
     pub fn genesis() -> Self {
-        Block::new(
+        let mut genesis = Block::new(
             0,
             0,
             Vec::new(), // Empty transactions for genesis block
             String::from("0"),
             0, // Genesis block has no difficulty requirement
-        )
+        );
+        genesis.version = 0;
+        genesis.hash = genesis.calculate_hash();
+        genesis
+    }
+
+    /// The block's reward transaction, if any; a coinbase always comes first
+    pub fn coinbase(&self) -> Option<&Transaction> {
+        self.transactions.first().filter(|tx| tx.is_coinbase())
+    }
+
+    /// New coins the block creates: its coinbases minus the fees they pass on,
+    /// which already existed. 0 without a coinbase
+    /// Every coinbase counts, even a misplaced one, so the supply cap holds
+    /// whatever else is wrong with the block
+    pub fn issuance(&self) -> u64 {
+        let minted: u64 = self.transactions.iter().filter(|tx| tx.is_coinbase()).map(|tx| tx.amount).fold(0, u64::saturating_add);
+        minted.saturating_sub(self.total_fees())
+    }
+
+    /// Sum of the fees paid by the block's transfers
+    pub fn total_fees(&self) -> u64 {
+        self.transactions.iter().filter(|tx| !tx.is_coinbase()).map(|tx| tx.fee).sum()
     }
 
     /// Returns the number of transactions in this block
@@ -110,15 +359,20 @@ impl Block {
             println!("    (No transactions)");
         }
         println!("  Previous Hash: {}", self.previous_hash);
+        println!("  Merkle Root:   {}", self.merkle_root);
         println!("  Difficulty:    {}", self.difficulty);
         println!("  Nonce:         {}", self.nonce);
         println!("  Hash:          {}", self.hash);
+        if let Some(producer) = &self.producer {
+            println!("  Producer:      {}", producer);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transaction::COIN;
 
     #[test]
     fn test_block_creation_empty() {
@@ -144,12 +398,12 @@ mod tests {
         let tx1 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
         let tx2 = Transaction::new_unvalidated(
             String::from("Bob"),
             String::from("Charlie"),
-            5.0,
+            5 * COIN,
         );
 
         let block = Block::new(
@@ -181,12 +435,12 @@ mod tests {
         let tx1 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
         let tx2 = Transaction::new_unvalidated(
             String::from("Bob"),
             String::from("Charlie"),
-            5.0,
+            5 * COIN,
         );
 
         let block1 = Block::new(
@@ -212,12 +466,12 @@ mod tests {
         let tx1 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
         let tx2 = Transaction::new_unvalidated(
             String::from("Bob"),
             String::from("Charlie"),
-            5.0,
+            5 * COIN,
         );
 
         let block1 = Block::new(
@@ -243,7 +497,7 @@ mod tests {
         let tx1 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
 
         let block1 = Block::new(
@@ -257,7 +511,7 @@ mod tests {
         let tx2 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.1, // Different amount
+            10 * COIN + 1, // Different amount
         );
 
         let block2 = Block::new(
@@ -287,7 +541,7 @@ mod tests {
         let tx = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
 
         let mut block = Block::new_unmined(
@@ -315,7 +569,7 @@ mod tests {
         let tx = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
 
         // Mine with difficulty 1
@@ -349,7 +603,7 @@ mod tests {
         let tx = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
 
         // Mine two identical blocks
@@ -381,7 +635,7 @@ mod tests {
         let tx = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
 
         let mut block = Block::new_unmined(
@@ -410,12 +664,12 @@ mod tests {
         let tx1 = Transaction::new_unvalidated(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
         let tx2 = Transaction::new_unvalidated(
             String::from("Bob"),
             String::from("Charlie"),
-            5.0,
+            5 * COIN,
         );
 
         let mut block = Block::new_unmined(
@@ -432,4 +686,79 @@ mod tests {
         assert_ne!(block.nonce, 0);
         assert_eq!(block.transaction_count(), 2);
     }
+
+    #[test]
+    fn test_mine_step_matches_mine_block() {
+        let tx = Transaction::new_unvalidated(
+            String::from("Alice"),
+            String::from("Bob"),
+            10 * COIN,
+        );
+
+        let mut stepped = Block::new(1, 1234567890, vec![tx], String::from("prev"), 2);
+        let mut mined = stepped.clone();
+        mined.mine_block();
+
+        // Mine in small slices until a valid hash turns up
+        let mut slices = 0;
+        while !stepped.mine_step(16) {
+            slices += 1;
+        }
+
+        assert!(slices > 0);
+        assert_eq!(stepped.nonce, mined.nonce);
+        assert_eq!(stepped.hash, mined.hash);
+    }
+
+    #[test]
+    fn test_memo_changes_block_hash() {
+        let base = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        let with_memo = |memo: &str| base.clone().with_memo(memo).unwrap();
+
+        let block_a = Block::new(1, 1234567890, vec![with_memo("rent")], String::from("prev"), 1);
+        let block_b = Block::new(1, 1234567890, vec![with_memo("gift")], String::from("prev"), 1);
+        let block_plain = Block::new(1, 1234567890, vec![base.clone()], String::from("prev"), 1);
+
+        assert_ne!(block_a.hash, block_b.hash);
+        assert_ne!(block_a.hash, block_plain.hash);
+
+        // Editing a memo after the fact breaks the block hash
+        let mut tampered = block_a.clone();
+        tampered.transactions[0].memo = Some(String::from("gift"));
+        assert_ne!(tampered.calculate_hash(), block_a.hash);
+    }
+
+    #[test]
+    fn test_header_hash_matches_block_hash() {
+        let tx = Transaction::new_unvalidated(String::from("Alice"), String::from("Bob"), 10 * COIN);
+        let mut block = Block::new(1, 1234567890, vec![tx], String::from("prev"), 1);
+        block.mine_block();
+
+        let header = block.header();
+        assert_eq!(header.merkle_root, block.merkle_root);
+        assert_eq!(header.calculate_hash(), block.hash);
+    }
+
+    #[test]
+    fn test_verify_integrity_after_load() {
+        let tx = Transaction::new_unvalidated(String::from("Alice"), String::from("Bob"), 10 * COIN);
+        let mut block = Block::new(1, 1234567890, vec![tx], String::from("prev"), 1);
+        block.mine_block();
+        let loaded: Block = serde_json::from_str(&serde_json::to_string(&block).unwrap()).unwrap();
+        assert_eq!(loaded.verify_integrity(), Ok(()));
+
+        // A hand-edited hash that still looks mined
+        let forged = format!("0{}", &block.hash[1..].chars().rev().collect::<String>());
+        let json = serde_json::to_string(&block).unwrap().replace(&block.hash, &forged);
+        let loaded: Block = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.verify_integrity(),
+            Err(ValidationError::InvalidHash { index: 1, stored: forged, computed: block.hash.clone() })
+        );
+
+        // An edited Merkle root is caught even though the hash is untouched
+        let json = serde_json::to_string(&block).unwrap().replace(&block.merkle_root, &"ab".repeat(32));
+        let loaded: Block = serde_json::from_str(&json).unwrap();
+        assert!(matches!(loaded.verify_integrity(), Err(ValidationError::InvalidMerkleRoot { index: 1, .. })));
+    }
 }
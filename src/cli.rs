@@ -1,13 +1,26 @@
 //! CLI Module for RustChain
 //! Provides command-line interface for interacting with the blockchain
 
-use crate::blockchain::Blockchain;
-use crate::attacks::{AttackSimulator, AttackType};
-use crate::experiments::SecurityExperiments;
-use crate::visualization::BlockchainVisualizer;
-use std::io::{self, Write};
+use crate::alias::Aliases;
+use crate::block::Block;
+use crate::blockchain::{Blockchain, Direction};
+use crate::crypto::calculate_hash;
+#[cfg(feature = "http")]
+use crate::http::ApiServer;
+use crate::merkle::MerkleProof;
+use crate::stepper::{ChainStepper, StepUndo};
+use crate::storage::NodeState;
+use crate::transaction::{self, Transaction, TransactionError, COIN, COINBASE_SENDER};
+use crate::validation::{self, ValidationError, ValidationResult};
+use crate::attacks::{AttackSimulator, AttackType, SummaryOrder};
+use crate::experiments::{self, SecurityCostResult, SecurityExperiments};
+use crate::visualization::{self, BlockchainVisualizer};
+use std::io::{self, BufRead, Write};
 use std::process;
-use std::time::Instant;
+use std::sync::{Mutex, Once};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// CLI-specific errors
 #[derive(Debug)]
@@ -17,6 +30,32 @@ pub enum CliError {
     InvalidArgument(String),
     FileError(String),
     BlockchainError(String),
+    /// A transaction the chain refused, kept structured so callers can match on it
+    Transaction(TransactionError),
+    /// A block or chain that failed validation, naming the block and the rule
+    Validation(ValidationError),
+}
+
+impl CliError {
+    /// Process exit status for single-command mode: 2 for a rejected transaction, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Transaction(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl From<TransactionError> for CliError {
+    fn from(error: TransactionError) -> Self {
+        CliError::Transaction(error)
+    }
+}
+
+impl From<ValidationError> for CliError {
+    fn from(error: ValidationError) -> Self {
+        CliError::Validation(error)
+    }
 }
 
 impl std::fmt::Display for CliError {
@@ -27,6 +66,8 @@ impl std::fmt::Display for CliError {
             CliError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
             CliError::FileError(msg) => write!(f, "File error: {}", msg),
             CliError::BlockchainError(msg) => write!(f, "Blockchain error: {}", msg),
+            CliError::Transaction(error) => write!(f, "Transaction rejected: {}", error),
+            CliError::Validation(error) => write!(f, "Invalid chain: {}", error),
         }
     }
 }
@@ -34,35 +75,133 @@ impl std::fmt::Display for CliError {
 /// CLI commands
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
-    /// Add a new transaction: add <sender> <receiver> <amount>
-    AddTransaction { sender: String, receiver: String, amount: f64 },
+    /// Add a new transaction: add <sender> <receiver> <amount> [--memo <text>]
+    /// The amount is in base units, parsed exactly from the decimal input
+    AddTransaction { sender: String, receiver: String, amount: u64, memo: Option<String> },
+
+    /// Add several transactions, all or none: add <sender> <receiver> <amount> <sender> ...
+    AddBatch { transfers: Vec<(String, String, u64)> },
+
+    /// Mine a new block with pending transactions: mine [--all]
+    /// With --all, keep mining until the mempool is empty
+    MineBlock { all: bool },
 
-    /// Mine a new block with pending transactions
-    MineBlock,
+    /// Show the block `mine` would build, without mining it: mine --dry-run
+    PreviewBlock,
 
-    /// Display the blockchain
-    ShowChain { full: bool, last_n: Option<usize>, block_n: Option<usize> },
+    /// Mine exactly one block, remembering it so it can be rewound: step
+    Step,
 
-    /// Validate blockchain integrity
-    ValidateChain,
+    /// Undo the last step, returning its transactions to the mempool: rewind
+    Rewind,
+
+    /// Set the address that receives block rewards: setminer <address>
+    SetMiner { address: String },
+
+    /// Set how many hash characters are displayed: set hashlen <N>
+    SetHashLen { len: usize },
+
+    /// Auto-mine until Ctrl-C: watch [--threshold N] [--interval MS]
+    Watch { threshold: usize, interval_ms: u64 },
+
+    /// Serve the JSON HTTP API on localhost until Ctrl-C: serve [--port N]
+    Serve { port: u16 },
+
+    /// Full-screen live dashboard (needs the `tui` feature): tui
+    Tui,
+
+    /// Display the blockchain: chain [--full] [--last N] [--block <index|hash|prefix>]
+    ShowChain { full: bool, last_n: Option<usize>, block: Option<String> },
+
+    /// Display one page of the chain: chain --page N [--per-page M]
+    ShowChainPage { page: usize, per_page: usize },
+
+    /// Validate blockchain integrity: validate [--json] [--max-errors N]
+    ValidateChain { json: bool, max_errors: Option<usize> },
 
     /// Show pending transactions
     ShowPending,
 
+    /// Remove a pending transaction by its listed number: pending remove <n>
+    RemovePending { position: usize },
+
+    /// Remove the most recently added pending transaction: pending undo
+    UndoPending,
+
+    /// Drop pending transactions older than the mempool TTL: mempool prune
+    MempoolPrune,
+
     /// Show balance for an address
     ShowBalance { address: String },
 
+    /// Name an address, for input and display: alias <name> <address>
+    SetAlias { name: String, address: String },
+
+    /// List the aliases: alias
+    ShowAliases,
+
+    /// Show every transaction touching an address: history <address>
+    ShowHistory { address: String },
+
     /// Set mining difficulty
     SetDifficulty { difficulty: u32 },
 
     /// Show blockchain statistics
     ShowStats,
 
-    /// Save blockchain to file
-    Save { path: String },
+    /// Show coins issued, total work and the reward schedule: supply
+    ShowSupply,
+
+    /// Show every mutating operation on the chain, oldest first: audit
+    ShowAudit,
+
+    /// Start or stop recording the audit log: audit on|off
+    SetAudit { on: bool },
+
+    /// Per-block metrics as CSV, or written to a file: metrics [--out <path>]
+    ExportMetrics { out: Option<String> },
+
+    /// Measure this machine's mining hashrate
+    Hashrate,
+
+    /// Pick the difficulty that mines a block in about `target_ms` here: autodiff <ms>
+    AutoDifficulty { target_ms: u64 },
+
+    /// Reject transactions to or from an address: block <address>
+    BlockAddress { address: String },
+
+    /// Take an address off the blocklist: unblock <address>
+    UnblockAddress { address: String },
+
+    /// Re-mine from the first invalid block to the tip, then re-validate: repair
+    Repair,
+
+    /// Pin a block's current hash so reorgs can't replace it: checkpoint add <index>
+    AddCheckpoint { index: usize },
+
+    /// Save blockchain to file; with `state`, the whole session (mempool, miner address, ...)
+    Save { path: String, state: bool },
+
+    /// Save the chain to `path` after every new block, on exit, and on Ctrl-C:
+    /// autosave <path>|off (None turns it off)
+    AutoSave { path: Option<String> },
 
-    /// Load blockchain from file
-    Load { path: String },
+    /// Load blockchain from file; with `state`, a session written by `save --state`
+    /// Replaces the current chain; `force` skips the confirmation prompt, and
+    /// `allow_invalid` keeps a chain that fails validation (to `repair` it)
+    Load { path: String, state: bool, force: bool, allow_invalid: bool },
+
+    /// Discard the chain and start again from genesis: reset [--force]
+    Reset { force: bool },
+
+    /// List blocks added, removed or changed between two saved chains: diff <file_a> <file_b>
+    DiffFiles { file_a: String, file_b: String },
+
+    /// Print a Merkle inclusion proof: prove <tx_id>
+    Prove { tx_id: String },
+
+    /// Check a Merkle inclusion proof: verify <tx_id> [proof_json]
+    Verify { tx_id: String, proof: Option<String> },
 
     // Day 7: Attack Simulation Commands
     /// List available attacks
@@ -71,14 +210,20 @@ pub enum Command {
     /// Run a specific attack
     AttackRun { attack_name: String },
 
+    /// Replay an attack and show the chain before and after: attack inspect <name>
+    AttackInspect { attack_name: String },
+
     /// Run all attacks
     AttackAll,
 
-    /// Show attack results summary
-    AttackReport,
+    /// Show attack results summary: attack report [--by-severity] [--json]
+    AttackReport { by_severity: bool, json: bool },
+
+    /// Run security experiment: experiment <type> [--out <path>]
+    Experiment { experiment_type: String, out: Option<String> },
 
-    /// Run security experiment
-    Experiment { experiment_type: String },
+    /// Race a private attacker chain against honest miners: experiment 51 [attacker_fraction]
+    FiftyOnePercent { attacker_fraction: f64 },
 
     /// Display blockchain visualization
     Visualize,
@@ -96,42 +241,182 @@ pub enum Command {
 /// Command result
 pub type CommandResult = Result<Option<String>, CliError>;
 
+/// Nonces tried between checks for Ctrl-C while `watch` mines, and between
+/// status line updates while `mine` runs
+const MINE_SLICE: u64 = 10_000;
+
+/// Least time between redraws of the `mine` status line
+const MINE_STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often `watch` looks at the mempool while idle
+const WATCH_POLL: Duration = Duration::from_millis(50);
+
+/// Longest block time `autodiff` will aim for; its search overshoots by ~16x
+const MAX_AUTODIFF_TARGET_MS: u64 = 60_000;
+
+/// Attacker's share of the hashrate when `experiment 51` isn't given one
+const DEFAULT_ATTACKER_FRACTION: f64 = 0.3;
+
+/// Confirmations the merchant waits for in `experiment 51`
+const FIFTY_ONE_PERCENT_CONFIRMATIONS: usize = 6;
+
+/// Default port for `serve`
+const DEFAULT_SERVE_PORT: u16 = 8080;
+
+/// Set by the Ctrl-C handler while `watch` is running
+static WATCHING: AtomicBool = AtomicBool::new(false);
+static WATCH_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Set by the Ctrl-C handler while `serve` is running
+static SERVING: AtomicBool = AtomicBool::new(false);
+static SERVE_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// With autosave on, the path and the chain as JSON after the last command,
+/// for the Ctrl-C handler to write out
+static INTERRUPT_SNAPSHOT: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+/// When `watch` mines a block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchPolicy {
+    /// Mine as soon as this many transactions are pending
+    pub threshold: usize,
+    /// Mine whatever is pending once this long has passed since the last block
+    pub interval: Duration,
+}
+
+impl Default for WatchPolicy {
+    /// What `watch` uses without flags: 3 pending, or every 5 seconds
+    fn default() -> Self {
+        WatchPolicy { threshold: 3, interval: Duration::from_millis(5_000) }
+    }
+}
+
+impl WatchPolicy {
+    /// Whether to mine now, given the mempool size and time since the last block
+    pub fn should_mine(&self, pending: usize, since_last_block: Duration) -> bool {
+        pending >= self.threshold || (pending > 0 && since_last_block >= self.interval)
+    }
+}
+
+/// What the self-check found wrong with the chain, and what fixing it costs
+#[derive(Debug, Clone)]
+pub struct SelfCheckReport {
+    /// Index of the first block that fails validation
+    pub first_invalid: usize,
+    /// The first error found in that block
+    pub error: ValidationError,
+    /// Estimated work for `remine_from(first_invalid)`; None when the genesis
+    /// block is broken, since it can't be re-mined
+    pub repair_cost: Option<SecurityCostResult>,
+}
+
+impl std::fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Self-check failed: block #{} is the first broken block", self.first_invalid)?;
+        writeln!(f, "  {}", self.error)?;
+        match &self.repair_cost {
+            Some(cost) => write!(
+                f,
+                "  Suggested fix: 'repair' runs remine_from({}), re-mining {} block(s) at difficulty up to {}\n  \
+                 Estimated cost: {} hashes, ~{}, ~${:.4} of electricity",
+                self.first_invalid,
+                cost.blocks_to_rewrite,
+                cost.difficulty,
+                experiments::format_number(cost.total_hashes),
+                experiments::format_duration(cost.estimated_time),
+                cost.estimated_cost
+            ),
+            None => write!(f, "  The genesis block can't be re-mined; load a different chain"),
+        }
+    }
+}
+
+/// Routes Ctrl-C to `watch` or `serve` while one runs; otherwise it exits as usual
+fn install_interrupt_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let installed = ctrlc::set_handler(|| {
+            if WATCHING.load(Ordering::SeqCst) {
+                WATCH_INTERRUPTED.store(true, Ordering::SeqCst);
+            } else if SERVING.load(Ordering::SeqCst) {
+                SERVE_INTERRUPTED.store(true, Ordering::SeqCst);
+            } else {
+                save_interrupt_snapshot();
+                process::exit(130);
+            }
+        });
+        if let Err(e) = installed {
+            log::warn!("Could not install the Ctrl-C handler: {}", e);
+        }
+    });
+}
+
+/// Writes the autosave snapshot, if any; called on Ctrl-C before exiting
+fn save_interrupt_snapshot() {
+    let snapshot = INTERRUPT_SNAPSHOT.lock().map(|guard| guard.clone()).unwrap_or_default();
+    if let Some((path, json)) = snapshot {
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("\nBlockchain saved to '{}'", path),
+            Err(e) => eprintln!("\nWarning: autosave to '{}' failed: {}", path, e),
+        }
+    }
+}
+
 /// CLI interface
 pub struct Cli {
     blockchain: Blockchain,
+    /// Receives the reward for every block mined from this CLI
+    miner_address: String,
     command_history: Vec<String>,
-    auto_save_path: Option<String>,
     // Day 7: Attack simulation and experiments
     attack_simulator: AttackSimulator,
     experiments: SecurityExperiments,
     visualizer: BlockchainVisualizer,
+    /// Undo records for `step`, most recent last
+    step_history: Vec<StepUndo>,
+    /// Where `autosave` writes the chain, if it's on
+    auto_save_path: Option<String>,
+    /// Names typed and shown in place of the addresses they stand for
+    aliases: Aliases,
 }
 
 impl Cli {
     /// Create a new CLI instance
     pub fn new() -> Self {
+        let blockchain = Blockchain::new();
         Cli {
-            blockchain: Blockchain::new(),
+            miner_address: Self::generate_miner_address(&blockchain),
+            blockchain,
             command_history: Vec::new(),
-            auto_save_path: None,
             attack_simulator: AttackSimulator::new(),
             experiments: SecurityExperiments::new(),
             visualizer: BlockchainVisualizer::new(),
+            step_history: Vec::new(),
+            auto_save_path: None,
+            aliases: Aliases::new(),
         }
     }
 
     /// Create a new CLI instance with existing blockchain
     pub fn with_blockchain(blockchain: Blockchain) -> Self {
         Cli {
+            miner_address: Self::generate_miner_address(&blockchain),
             blockchain,
             command_history: Vec::new(),
-            auto_save_path: None,
             attack_simulator: AttackSimulator::new(),
             experiments: SecurityExperiments::new(),
             visualizer: BlockchainVisualizer::new(),
+            step_history: Vec::new(),
+            auto_save_path: None,
+            aliases: Aliases::new(),
         }
     }
 
+    /// A fresh `miner-xxxxxxxx` address, so rewards have somewhere to go before `setminer`
+    fn generate_miner_address(blockchain: &Blockchain) -> String {
+        format!("miner-{}", &calculate_hash(&blockchain.now().to_string())[..8])
+    }
+
     /// Parse command from string arguments
     pub fn parse_command(args: &[String]) -> Result<Command, CliError> {
         if args.is_empty() {
@@ -142,33 +427,102 @@ impl Cli {
 
         match command.as_str() {
             "add" | "a" => {
-                if args.len() < 4 {
+                // Everything after --memo is the memo, so it may contain spaces
+                let memo_at = args.iter().position(|arg| arg == "--memo").unwrap_or(args.len());
+                let fields = &args[1..memo_at];
+                if let Some(flag) = fields.iter().find(|field| field.starts_with("--")) {
+                    return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag)));
+                }
+                if fields.len() < 3 {
                     return Err(CliError::MissingArgument(
-                        "Usage: add <sender> <receiver> <amount>".to_string()
+                        "Usage: add <sender> <receiver> <amount> [...] [--memo <text>]".to_string()
                     ));
                 }
-                let sender = args[1].clone();
-                let receiver = args[2].clone();
-                let amount = args[3].parse::<f64>()
-                    .map_err(|_| CliError::InvalidArgument(
-                        format!("Amount must be a valid number: {}", args[3])
-                    ))?;
+                if !fields.len().is_multiple_of(3) {
+                    return Err(CliError::InvalidArgument(format!(
+                        "add takes <sender> <receiver> <amount> triples; got {} argument(s)",
+                        fields.len()
+                    )));
+                }
+
+                let mut transfers = Vec::with_capacity(fields.len() / 3);
+                for triple in fields.chunks(3) {
+                    let amount = transaction::parse_amount(&triple[2])
+                        .map_err(CliError::InvalidArgument)?;
+                    if amount == 0 {
+                        return Err(CliError::InvalidArgument(
+                            "Amount must be greater than zero".to_string()
+                        ));
+                    }
+                    transfers.push((triple[0].clone(), triple[1].clone(), amount));
+                }
+
+                let memo = match args.get(memo_at + 1..) {
+                    Some([]) => return Err(CliError::MissingArgument("--memo requires text".to_string())),
+                    Some(words) => Some(words.join(" ")),
+                    None => None,
+                };
 
-                if amount <= 0.0 {
+                if transfers.len() > 1 {
+                    if memo.is_some() {
+                        return Err(CliError::InvalidArgument(
+                            "--memo only works when adding one transaction".to_string()
+                        ));
+                    }
+                    return Ok(Command::AddBatch { transfers });
+                }
+                let (sender, receiver, amount) = transfers.remove(0);
+                Ok(Command::AddTransaction { sender, receiver, amount, memo })
+            }
+
+            "mine" | "m" => {
+                match args.get(1).map(String::as_str) {
+                    None => Ok(Command::MineBlock { all: false }),
+                    Some("--all") => Ok(Command::MineBlock { all: true }),
+                    Some("--dry-run") => Ok(Command::PreviewBlock),
+                    Some(flag) => Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                }
+            }
+
+            "step" => Ok(Command::Step),
+
+            "rewind" => Ok(Command::Rewind),
+
+            "setminer" => {
+                if args.len() < 2 {
+                    return Err(CliError::MissingArgument("Usage: setminer <address>".to_string()));
+                }
+                if args[1] == COINBASE_SENDER {
                     return Err(CliError::InvalidArgument(
-                        "Amount must be greater than zero".to_string()
+                        format!("{} is reserved for block rewards", COINBASE_SENDER)
                     ));
                 }
-
-                Ok(Command::AddTransaction { sender, receiver, amount })
+                Ok(Command::SetMiner { address: args[1].clone() })
             }
 
-            "mine" | "m" => Ok(Command::MineBlock),
+            "set" => {
+                match args.get(1).map(String::as_str) {
+                    Some("hashlen") => {
+                        let len = args.get(2)
+                            .ok_or_else(|| CliError::MissingArgument("Usage: set hashlen <N>".to_string()))?
+                            .parse::<usize>()
+                            .map_err(|_| CliError::InvalidArgument("Hash length must be a number".to_string()))?;
+                        if !(1..=64).contains(&len) {
+                            return Err(CliError::InvalidArgument("Hash length must be between 1 and 64".to_string()));
+                        }
+                        Ok(Command::SetHashLen { len })
+                    }
+                    Some(setting) => Err(CliError::InvalidArgument(format!("Unknown setting: {}", setting))),
+                    None => Err(CliError::MissingArgument("Usage: set hashlen <N>".to_string())),
+                }
+            }
 
             "chain" | "c" => {
                 let mut full = false;
                 let mut last_n = None;
-                let mut block_n = None;
+                let mut block = None;
+                let mut page = None;
+                let mut per_page = None;
 
                 let mut i = 1;
                 while i < args.len() {
@@ -189,13 +543,26 @@ impl Cli {
                         "--block" => {
                             if i + 1 >= args.len() {
                                 return Err(CliError::MissingArgument(
-                                    "--block requires a number".to_string()
+                                    "--block requires an index, hash, or hash prefix".to_string()
                                 ));
                             }
-                            block_n = Some(args[i + 1].parse::<usize>()
-                                .map_err(|_| CliError::InvalidArgument(
-                                    format!("Invalid number for --block: {}", args[i + 1])
-                                ))?);
+                            block = Some(args[i + 1].clone());
+                            i += 1;
+                        }
+                        flag @ ("--page" | "--per-page") => {
+                            let value = args.get(i + 1)
+                                .ok_or_else(|| CliError::MissingArgument(format!("{} requires a number", flag)))?
+                                .parse::<usize>()
+                                .ok()
+                                .filter(|&n| n > 0)
+                                .ok_or_else(|| CliError::InvalidArgument(
+                                    format!("{} takes a number of at least 1: {}", flag, args[i + 1])
+                                ))?;
+                            if flag == "--page" {
+                                page = Some(value);
+                            } else {
+                                per_page = Some(value);
+                            }
                             i += 1;
                         }
                         _ => {
@@ -207,12 +574,77 @@ impl Cli {
                     i += 1;
                 }
 
-                Ok(Command::ShowChain { full, last_n, block_n })
+                if page.is_none() && per_page.is_none() {
+                    return Ok(Command::ShowChain { full, last_n, block });
+                }
+                if full || last_n.is_some() || block.is_some() {
+                    return Err(CliError::InvalidArgument(
+                        "--page and --per-page don't combine with --full, --last or --block".to_string()
+                    ));
+                }
+                Ok(Command::ShowChainPage {
+                    page: page.unwrap_or(1),
+                    per_page: per_page.unwrap_or(visualization::DEFAULT_PAGE_SIZE),
+                })
+            }
+
+            "validate" | "v" => {
+                let mut json = false;
+                let mut max_errors = None;
+
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--json" => json = true,
+                        "--max-errors" => {
+                            if i + 1 >= args.len() {
+                                return Err(CliError::MissingArgument(
+                                    "--max-errors requires a number".to_string()
+                                ));
+                            }
+                            max_errors = Some(args[i + 1].parse::<usize>()
+                                .ok()
+                                .filter(|&n| n > 0)
+                                .ok_or_else(|| CliError::InvalidArgument(
+                                    format!("--max-errors needs a positive number, got {}", args[i + 1])
+                                ))?);
+                            i += 1;
+                        }
+                        flag => return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                    }
+                    i += 1;
+                }
+
+                Ok(Command::ValidateChain { json, max_errors })
             }
 
-            "validate" | "v" => Ok(Command::ValidateChain),
+            "pending" | "p" => {
+                match args.get(1).map(String::as_str) {
+                    None => Ok(Command::ShowPending),
+                    Some("undo") => Ok(Command::UndoPending),
+                    Some("remove" | "rm") => {
+                        let n = args.get(2).ok_or_else(|| CliError::MissingArgument(
+                            "Usage: pending remove <n>".to_string()
+                        ))?;
+                        let position = n.parse::<usize>()
+                            .ok()
+                            .filter(|&position| position >= 1)
+                            .ok_or_else(|| CliError::InvalidArgument(
+                                format!("Transaction number must be a positive integer: {}", n)
+                            ))?;
+                        Ok(Command::RemovePending { position })
+                    }
+                    Some(other) => Err(CliError::InvalidArgument(format!("Unknown pending command: {}", other))),
+                }
+            }
 
-            "pending" | "p" => Ok(Command::ShowPending),
+            "mempool" => {
+                match args.get(1).map(String::as_str) {
+                    Some("prune") => Ok(Command::MempoolPrune),
+                    Some(other) => Err(CliError::InvalidArgument(format!("Unknown mempool command: {}", other))),
+                    None => Err(CliError::MissingArgument("Usage: mempool prune".to_string())),
+                }
+            }
 
             "balance" | "b" => {
                 if args.len() < 2 {
@@ -223,6 +655,24 @@ impl Cli {
                 Ok(Command::ShowBalance { address: args[1].clone() })
             }
 
+            "alias" => match (args.get(1), args.get(2)) {
+                (None, _) => Ok(Command::ShowAliases),
+                (Some(name), Some(address)) => Ok(Command::SetAlias { name: name.clone(), address: address.clone() }),
+                (Some(_), None) => Err(CliError::MissingArgument("Usage: alias <name> <address>".to_string())),
+            },
+
+            "history" => {
+                if args.len() < 2 {
+                    return Err(CliError::MissingArgument(
+                        "Usage: history <address>".to_string()
+                    ));
+                }
+                Ok(Command::ShowHistory { address: args[1].clone() })
+            }
+
+            // `diff <N>` sets the difficulty; with two paths it compares chain files
+            "diff" if args.len() == 3 => Ok(Command::DiffFiles { file_a: args[1].clone(), file_b: args[2].clone() }),
+
             "difficulty" | "diff" => {
                 if args.len() < 2 {
                     return Err(CliError::MissingArgument(
@@ -234,7 +684,7 @@ impl Cli {
                         format!("Difficulty must be a number between 1-6: {}", args[1])
                     ))?;
 
-                if difficulty < 1 || difficulty > 6 {
+                if !(1..=6).contains(&difficulty) {
                     return Err(CliError::InvalidArgument(
                         "Difficulty must be between 1 and 6".to_string()
                     ));
@@ -245,22 +695,172 @@ impl Cli {
 
             "stats" => Ok(Command::ShowStats),
 
-            "save" => {
+            "supply" => Ok(Command::ShowSupply),
+            "audit" => match args.get(1).map(String::as_str) {
+                None => Ok(Command::ShowAudit),
+                Some("on") => Ok(Command::SetAudit { on: true }),
+                Some("off") => Ok(Command::SetAudit { on: false }),
+                Some(other) => Err(CliError::InvalidArgument(format!("Usage: audit [on|off], not '{}'", other))),
+            },
+
+            "metrics" => {
+                let out = match args.get(1).map(String::as_str) {
+                    None => None,
+                    Some("--out") => Some(args.get(2).cloned().ok_or_else(|| {
+                        CliError::MissingArgument("--out requires a file path".to_string())
+                    })?),
+                    Some(flag) => return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                };
+                Ok(Command::ExportMetrics { out })
+            }
+
+            "hashrate" => Ok(Command::Hashrate),
+
+            "autodiff" => {
+                let Some(ms) = args.get(1) else {
+                    return Err(CliError::MissingArgument("Usage: autodiff <ms>".to_string()));
+                };
+                let target_ms = ms.parse::<u64>()
+                    .ok()
+                    .filter(|ms| (1..=MAX_AUTODIFF_TARGET_MS).contains(ms))
+                    .ok_or_else(|| CliError::InvalidArgument(format!(
+                        "Target must be between 1 and {} ms: {}", MAX_AUTODIFF_TARGET_MS, ms
+                    )))?;
+                Ok(Command::AutoDifficulty { target_ms })
+            }
+
+            "watch" => {
+                let mut threshold = WatchPolicy::default().threshold;
+                let mut interval_ms = WatchPolicy::default().interval.as_millis() as u64;
+
+                let mut i = 1;
+                while i < args.len() {
+                    let value = args.get(i + 1).ok_or_else(|| {
+                        CliError::MissingArgument(format!("{} requires a number", args[i]))
+                    })?;
+                    match args[i].as_str() {
+                        "--threshold" => {
+                            threshold = value.parse::<usize>()
+                                .ok()
+                                .filter(|n| *n > 0)
+                                .ok_or_else(|| CliError::InvalidArgument(
+                                    format!("Threshold must be a positive number: {}", value)
+                                ))?;
+                        }
+                        "--interval" => {
+                            interval_ms = value.parse::<u64>()
+                                .map_err(|_| CliError::InvalidArgument(
+                                    format!("Interval must be a number of milliseconds: {}", value)
+                                ))?;
+                        }
+                        flag => return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                    }
+                    i += 2;
+                }
+
+                Ok(Command::Watch { threshold, interval_ms })
+            }
+
+            "serve" => {
+                match (args.get(1).map(String::as_str), args.get(2)) {
+                    (None, _) => Ok(Command::Serve { port: DEFAULT_SERVE_PORT }),
+                    (Some("--port"), Some(port)) => {
+                        let port = port.parse::<u16>()
+                            .map_err(|_| CliError::InvalidArgument(format!("Port must be 0-65535: {}", port)))?;
+                        Ok(Command::Serve { port })
+                    }
+                    (Some("--port"), None) => Err(CliError::MissingArgument("--port requires a number".to_string())),
+                    (Some(flag), _) => Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                }
+            }
+
+            "tui" => Ok(Command::Tui),
+
+            "repair" => Ok(Command::Repair),
+
+            "checkpoint" => {
+                match (args.get(1).map(String::as_str), args.get(2)) {
+                    (Some("add"), Some(index)) => {
+                        let index = index.parse::<usize>()
+                            .map_err(|_| CliError::InvalidArgument(format!("Block index must be a number: {}", index)))?;
+                        Ok(Command::AddCheckpoint { index })
+                    }
+                    (Some("add") | None, _) => Err(CliError::MissingArgument("Usage: checkpoint add <index>".to_string())),
+                    (Some(other), _) => Err(CliError::InvalidArgument(format!("Unknown checkpoint command: {}", other))),
+                }
+            }
+
+            "block" | "unblock" => {
+                let Some(address) = args.get(1) else {
+                    return Err(CliError::MissingArgument(format!("Usage: {} <address>", command)));
+                };
+                let address = address.clone();
+                if command == "block" {
+                    Ok(Command::BlockAddress { address })
+                } else {
+                    Ok(Command::UnblockAddress { address })
+                }
+            }
+
+            "autosave" => match args.get(1).map(String::as_str) {
+                Some("off") => Ok(Command::AutoSave { path: None }),
+                Some(path) => Ok(Command::AutoSave { path: Some(path.to_string()) }),
+                None => Err(CliError::MissingArgument(String::from("Usage: autosave <path>|off"))),
+            },
+
+            "save" | "load" => {
+                let mut state = false;
+                let mut force = false;
+                let mut allow_invalid = false;
+                let mut path = None;
+                for arg in &args[1..] {
+                    match arg.as_str() {
+                        "--state" => state = true,
+                        "--force" if command == "load" => force = true,
+                        "--allow-invalid" if command == "load" => allow_invalid = true,
+                        flag if flag.starts_with("--") => {
+                            return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag)));
+                        }
+                        _ => path = path.or(Some(arg.clone())),
+                    }
+                }
+                let Some(path) = path else {
+                    let flags = if command == "load" { "[--state] [--force] [--allow-invalid]" } else { "[--state]" };
+                    return Err(CliError::MissingArgument(
+                        format!("Usage: {} {} <path>", command, flags)
+                    ));
+                };
+                if command == "save" {
+                    Ok(Command::Save { path, state })
+                } else {
+                    Ok(Command::Load { path, state, force, allow_invalid })
+                }
+            }
+
+            "reset" => match args.get(1).map(String::as_str) {
+                None => Ok(Command::Reset { force: false }),
+                Some("--force") => Ok(Command::Reset { force: true }),
+                Some(flag) => Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+            },
+
+            "prove" => {
                 if args.len() < 2 {
                     return Err(CliError::MissingArgument(
-                        "Usage: save <path>".to_string()
+                        "Usage: prove <tx_id>".to_string()
                     ));
                 }
-                Ok(Command::Save { path: args[1].clone() })
+                Ok(Command::Prove { tx_id: args[1].clone() })
             }
 
-            "load" => {
+            "verify" => {
                 if args.len() < 2 {
                     return Err(CliError::MissingArgument(
-                        "Usage: load <path>".to_string()
+                        "Usage: verify <tx_id> [proof_json]".to_string()
                     ));
                 }
-                Ok(Command::Load { path: args[1].clone() })
+                // The proof may have been split on whitespace by the shell or REPL
+                let proof = if args.len() > 2 { Some(args[2..].join(" ")) } else { None };
+                Ok(Command::Verify { tx_id: args[1].clone(), proof })
             }
 
             // Day 7: Attack simulation commands
@@ -276,17 +876,58 @@ impl Cli {
                         }
                         Ok(Command::AttackRun { attack_name: args[2].clone() })
                     }
+                    "inspect" => {
+                        if args.len() < 3 {
+                            return Err(CliError::MissingArgument("Usage: attack inspect <attack_name>".to_string()));
+                        }
+                        Ok(Command::AttackInspect { attack_name: args[2].clone() })
+                    }
                     "all" => Ok(Command::AttackAll),
-                    "report" => Ok(Command::AttackReport),
+                    "report" => {
+                        let mut by_severity = false;
+                        let mut json = false;
+                        for flag in &args[2..] {
+                            match flag.as_str() {
+                                "--by-severity" => by_severity = true,
+                                "--json" => json = true,
+                                _ => {
+                                    return Err(CliError::InvalidArgument(
+                                        format!("Unknown flag: {}", flag)
+                                    ));
+                                }
+                            }
+                        }
+                        Ok(Command::AttackReport { by_severity, json })
+                    }
                     _ => Err(CliError::InvalidArgument(format!("Unknown attack command: {}", args[1]))),
                 }
             }
 
             "experiment" | "exp" => {
                 if args.len() < 2 {
-                    return Err(CliError::MissingArgument("Usage: experiment <type>".to_string()));
+                    return Err(CliError::MissingArgument("Usage: experiment <type> [--out <path>]".to_string()));
                 }
-                Ok(Command::Experiment { experiment_type: args[1].clone() })
+                if args[1] == "51" {
+                    let attacker_fraction = match args.get(2) {
+                        None => DEFAULT_ATTACKER_FRACTION,
+                        Some(fraction) => fraction
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|fraction| (0.0..=1.0).contains(fraction))
+                            .ok_or_else(|| CliError::InvalidArgument(format!(
+                                "Attacker fraction must be between 0 and 1, got {}", fraction
+                            )))?,
+                    };
+                    return Ok(Command::FiftyOnePercent { attacker_fraction });
+                }
+                let out = match args.get(2).map(String::as_str) {
+                    None => None,
+                    Some("--out") => Some(args.get(3).cloned().ok_or_else(|| {
+                        CliError::MissingArgument("--out requires a file path".to_string())
+                    })?),
+                    Some(flag) => return Err(CliError::InvalidArgument(format!("Unknown flag: {}", flag))),
+                };
+                Ok(Command::Experiment { experiment_type: args[1].clone(), out })
             }
 
             "visualize" | "viz" => Ok(Command::Visualize),
@@ -304,33 +945,126 @@ impl Cli {
         }
     }
 
-    /// Execute a command
+    /// Execute a command; with autosave on, a command that moved the tip saves the chain
     pub fn execute_command(&mut self, command: Command) -> CommandResult {
+        let tip = self.blockchain.tip_hash().to_string();
+        let result = self.dispatch_command(command);
+        if self.blockchain.tip_hash() != tip {
+            self.autosave();
+        }
+        result
+    }
+
+    fn dispatch_command(&mut self, command: Command) -> CommandResult {
         match command {
-            Command::AddTransaction { sender, receiver, amount } => {
-                self.execute_add_transaction(sender, receiver, amount)
+            Command::AddTransaction { sender, receiver, amount, memo } => {
+                self.execute_add_transaction(sender, receiver, amount, memo)
+            }
+
+            Command::AddBatch { transfers } => {
+                self.execute_add_batch(transfers)
             }
 
-            Command::MineBlock => {
+            Command::MineBlock { all: false } => {
                 self.execute_mine_block()
             }
 
-            Command::ShowChain { full, last_n, block_n } => {
-                self.execute_show_chain(full, last_n, block_n)
+            Command::MineBlock { all: true } => {
+                self.execute_mine_all()
+            }
+
+            Command::PreviewBlock => {
+                self.execute_preview_block()
+            }
+
+            Command::Step => {
+                self.execute_step()
+            }
+
+            Command::Rewind => {
+                let rewound = self.with_stepper(ChainStepper::rewind)
+                    .map_err(CliError::BlockchainError)?;
+                Ok(Some(format!(
+                    "Rewound block #{} ({}...); {} transaction(s) pending",
+                    rewound.index,
+                    self.visualizer.short(&rewound.hash),
+                    self.blockchain.pending_transaction_count()
+                )))
+            }
+
+            Command::SetMiner { address } => {
+                self.miner_address = self.resolve_address(address);
+                Ok(Some(format!("Block rewards will be paid to {}", self.miner_address)))
+            }
+
+            Command::SetHashLen { len } => {
+                self.visualizer.display.hash_len = len;
+                Ok(Some(format!("Hashes will be shown as {} characters", len)))
+            }
+
+            Command::ShowChain { full, last_n, block } => {
+                self.execute_show_chain(full, last_n, block)
+            }
+
+            Command::ShowChainPage { page, per_page } => {
+                self.visualizer
+                    .format_page(&self.blockchain, page, per_page)
+                    .map(Some)
+                    .map_err(CliError::InvalidArgument)
             }
 
-            Command::ValidateChain => {
-                self.execute_validate_chain()
+            Command::ValidateChain { json, max_errors } => {
+                self.execute_validate_chain(json, max_errors)
             }
 
             Command::ShowPending => {
                 self.execute_show_pending()
             }
 
+            Command::RemovePending { position } => {
+                self.execute_remove_pending(position)
+            }
+
+            Command::UndoPending => {
+                self.execute_undo_pending()
+            }
+
+            Command::MempoolPrune => {
+                self.execute_mempool_prune()
+            }
+
             Command::ShowBalance { address } => {
                 self.execute_show_balance(address)
             }
 
+            Command::ShowHistory { address } => {
+                self.execute_show_history(address)
+            }
+
+            Command::SetAlias { name, address } => {
+                let address = self.resolve_address(address);
+                let (blockchain, miner_address) = (&self.blockchain, &self.miner_address);
+                let is_address = |name: &str| name == miner_address || blockchain.knows_address(name);
+                let previous = self.aliases.set(&name, &address, is_address)
+                    .map_err(CliError::InvalidArgument)?;
+                Ok(Some(match previous {
+                    Some(previous) if previous != address => format!("{} now stands for {} (was {})", name, address, previous),
+                    _ => format!("{} now stands for {}", name, address),
+                }))
+            }
+
+            Command::ShowAliases => {
+                let aliases = self.aliases.entries();
+                if aliases.is_empty() {
+                    return Ok(Some("No aliases (add one with 'alias <name> <address>')".to_string()));
+                }
+                let mut output = format!("\n=== Aliases ({}) ===\n", aliases.len());
+                for (name, address) in aliases {
+                    output.push_str(&format!("  {:<16} {}\n", name, address));
+                }
+                Ok(Some(output))
+            }
+
             Command::SetDifficulty { difficulty } => {
                 self.execute_set_difficulty(difficulty)
             }
@@ -339,15 +1073,127 @@ impl Cli {
                 self.execute_show_stats()
             }
 
-            Command::Save { path } => {
+            Command::ShowSupply => {
+                self.execute_show_supply()
+            }
+
+            Command::ExportMetrics { out } => {
+                self.execute_export_metrics(out)
+            }
+
+            Command::ShowAudit => {
+                self.execute_show_audit()
+            }
+
+            Command::SetAudit { on } => {
+                self.blockchain.set_audit(on);
+                Ok(Some(String::from(if on {
+                    "Audit log on: changes to the chain are recorded from now ('audit' shows them)"
+                } else {
+                    "Audit log off (recorded events discarded)"
+                })))
+            }
+
+            Command::AutoDifficulty { target_ms } => {
+                println!("Timing blocks at increasing difficulty (target {} ms)...", target_ms);
+                let difficulty = self.blockchain.difficulty_for_target_time(target_ms as u128);
+                self.blockchain.set_difficulty(difficulty);
+                Ok(Some(format!(
+                    "Mining difficulty set to {}: about one block per {} ms or less on this machine",
+                    self.blockchain.get_difficulty(),
+                    target_ms
+                )))
+            }
+
+            Command::Watch { threshold, interval_ms } => {
+                let policy = WatchPolicy { threshold, interval: Duration::from_millis(interval_ms) };
+                self.execute_watch(policy)
+            }
+
+            Command::Serve { port } => {
+                self.execute_serve(port)
+            }
+
+            Command::Tui => {
+                self.execute_tui()
+            }
+
+            Command::Hashrate => {
+                self.execute_hashrate()
+            }
+
+            Command::Repair => {
+                self.execute_repair()
+            }
+
+            Command::AddCheckpoint { index } => {
+                self.execute_add_checkpoint(index)
+            }
+
+            Command::BlockAddress { address } => {
+                let address = self.resolve_address(address);
+                let dropped = self.blockchain.block_address(&address)
+                    .map_err(CliError::BlockchainError)?;
+                Ok(Some(format!(
+                    "Blocked {}: its transactions are now invalid ({} pending dropped)",
+                    address, dropped
+                )))
+            }
+
+            Command::UnblockAddress { address } => {
+                let address = self.resolve_address(address);
+                if self.blockchain.unblock_address(&address) {
+                    Ok(Some(format!("Unblocked {}", address)))
+                } else {
+                    Err(CliError::InvalidArgument(format!("{} is not blocked", address)))
+                }
+            }
+
+            Command::Save { path, state: false } => {
                 self.execute_save(path)
             }
 
-            Command::Load { path } => {
-                self.execute_load(path)
+            Command::Save { path, state: true } => {
+                NodeState::capture(&self.blockchain, Some(&self.miner_address))
+                    .save_state(&path)
+                    .map_err(CliError::FileError)?;
+                Ok(Some(format!("Node state saved to '{}'", path)))
+            }
+
+            Command::AutoSave { path } => {
+                self.execute_autosave(path)
+            }
+
+            Command::Load { path, state: false, allow_invalid, .. } => {
+                self.execute_load(path, allow_invalid)
+            }
+
+            Command::Load { path, state: true, allow_invalid, .. } => {
+                self.execute_load_state(path, allow_invalid)
+            }
+
+            Command::DiffFiles { file_a, file_b } => {
+                self.execute_diff_files(file_a, file_b)
+            }
+
+            Command::Reset { .. } => {
+                let discarded = self.blockchain.height();
+                let audited = self.blockchain.is_audited();
+                self.blockchain = Blockchain::new();
+                self.blockchain.set_audit(audited);
+                self.step_history.clear();
+                Ok(Some(format!("Started a new chain from genesis ({} block(s) discarded)", discarded)))
             }
 
             // Day 7: Attack simulation commands
+            Command::Prove { tx_id } => {
+                self.execute_prove(tx_id)
+            }
+
+            Command::Verify { tx_id, proof } => {
+                self.execute_verify(tx_id, proof)
+            }
+
             Command::AttackList => {
                 self.execute_attack_list()
             }
@@ -356,16 +1202,28 @@ impl Cli {
                 self.execute_attack_run(attack_name)
             }
 
+            Command::AttackInspect { attack_name } => {
+                self.execute_attack_inspect(attack_name)
+            }
+
             Command::AttackAll => {
                 self.execute_attack_all()
             }
 
-            Command::AttackReport => {
-                self.execute_attack_report()
+            Command::AttackReport { by_severity, json } => {
+                self.execute_attack_report(by_severity, json)
             }
 
-            Command::Experiment { experiment_type } => {
-                self.execute_experiment(experiment_type)
+            Command::Experiment { experiment_type, out } => {
+                self.execute_experiment(experiment_type, out)
+            }
+
+            Command::FiftyOnePercent { attacker_fraction } => {
+                let result = self.experiments.experiment_fifty_one_percent(FIFTY_ONE_PERCENT_CONFIRMATIONS, attacker_fraction);
+                Ok(Some(format!(
+                    "51% attack experiment complete! Attacker won {} of {} races",
+                    result.successes, result.trials
+                )))
             }
 
             Command::Visualize => {
@@ -386,8 +1244,61 @@ impl Cli {
         }
     }
 
+    /// The address `input` names: an alias's address, or `input` itself
+    fn resolve_address(&self, input: String) -> String {
+        match self.aliases.get(&input) {
+            Some(address) => address.to_string(),
+            None => input,
+        }
+    }
+
+    /// Runs full validation; if the chain is invalid, reports the first broken
+    /// block and what `repair` would cost at this machine's hashrate
+    pub fn self_check(&mut self) -> Option<SelfCheckReport> {
+        let result = validation::validate_chain(&self.blockchain);
+        let first_invalid = result.summary().first_invalid_block?;
+        let error = result.errors.into_iter().find(|error| error.block_index() == first_invalid)?;
+
+        let repair_cost = (first_invalid > 0).then(|| {
+            let difficulty = self.blockchain.chain[first_invalid..].iter().map(|block| block.difficulty).max().unwrap_or(0);
+            let hashrate = self.current_hashrate();
+            experiments::estimate_rewrite_cost(
+                self.blockchain.len() - first_invalid,
+                difficulty,
+                (hashrate as u64).max(1),
+                experiments::DEFAULT_ELECTRICITY_RATE_PER_KWH,
+                experiments::DEFAULT_POWER_WATTS,
+            )
+        });
+        Some(SelfCheckReport { first_invalid, error, repair_cost })
+    }
+
+    /// Execute repair command
+    fn execute_repair(&mut self) -> CommandResult {
+        let Some(first_invalid) = validation::validate_chain(&self.blockchain).summary().first_invalid_block else {
+            return Ok(Some("Chain is valid; nothing to repair".to_string()));
+        };
+
+        println!("Re-mining from block #{}...", first_invalid);
+        let start = Instant::now();
+        let remined = self.blockchain.remine_from(first_invalid)
+            .map_err(CliError::BlockchainError)?;
+        let duration = start.elapsed();
+
+        match self.self_check() {
+            None => Ok(Some(format!(
+                "Re-mined {} block(s) from #{} in {:?}; chain is valid",
+                remined, first_invalid, duration
+            ))),
+            Some(report) => Err(CliError::BlockchainError(format!(
+                "Re-mined {} block(s) from #{} but the chain is still invalid (re-mining can't fix this)\n{}",
+                remined, first_invalid, report
+            ))),
+        }
+    }
+
     /// Execute add transaction command
-    fn execute_add_transaction(&mut self, sender: String, receiver: String, amount: f64) -> CommandResult {
+    fn execute_add_transaction(&mut self, sender: String, receiver: String, amount: u64, memo: Option<String>) -> CommandResult {
         // Validate inputs
         if sender.trim().is_empty() {
             return Err(CliError::InvalidArgument("Sender cannot be empty".to_string()));
@@ -396,21 +1307,53 @@ impl Cli {
             return Err(CliError::InvalidArgument("Receiver cannot be empty".to_string()));
         }
 
+        // Aliases stand in for addresses on input only; the chain stores the address
+        let sender = self.resolve_address(sender);
+        let receiver = self.resolve_address(receiver);
+
+        // Build the transaction, attaching the memo if one was given
+        let mut transaction = Transaction::new(sender.clone(), receiver.clone(), amount)?;
+        if let Some(memo) = memo {
+            transaction = transaction.with_memo(memo)?;
+        }
+        let memo_note = transaction.memo.as_ref()
+            .map(|memo| format!(" \"{}\"", memo))
+            .unwrap_or_default();
+
         // Add transaction to blockchain
-        self.blockchain.add_transaction(sender.clone(), receiver.clone(), amount)
-            .map_err(|e| CliError::BlockchainError(e))?;
+        self.blockchain.add_pending_transaction(transaction)?;
 
         let message = format!(
-            "Transaction added: {} -> {} ({:.4})\nPending transactions: {}",
-            sender,
-            receiver,
-            amount,
+            "Transaction added: {} -> {} ({}){}\nPending transactions: {}",
+            self.aliases.label(&sender),
+            self.aliases.label(&receiver),
+            transaction::format_amount(amount),
+            memo_note,
             self.blockchain.pending_transaction_count()
         );
 
         Ok(Some(message))
     }
 
+    /// Execute add command with several transactions; none are queued if any is rejected
+    fn execute_add_batch(&mut self, transfers: Vec<(String, String, u64)>) -> CommandResult {
+        let transactions = transfers
+            .into_iter()
+            .map(|(sender, receiver, amount)| {
+                Transaction::new(self.resolve_address(sender), self.resolve_address(receiver), amount)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut message = format!("Queued {} transactions:\n", transactions.len());
+        for (i, tx) in transactions.iter().enumerate() {
+            message.push_str(&format!("  {}. {}\n", i + 1, self.aliases.transaction(tx)));
+        }
+        self.blockchain.add_pending_transactions(transactions)?;
+
+        message.push_str(&format!("Pending transactions: {}", self.blockchain.pending_transaction_count()));
+        Ok(Some(message))
+    }
+
     /// Execute mine block command
     fn execute_mine_block(&mut self) -> CommandResult {
         let pending_count = self.blockchain.pending_transaction_count();
@@ -425,63 +1368,202 @@ impl Cli {
         );
 
         let start = Instant::now();
-        self.blockchain.mine_block();
+        let block = if self.blockchain.consensus.is_proof_of_work() {
+            self.mine_with_status()?;
+            self.blockchain.get_latest_block()
+        } else {
+            self.blockchain.mine_block_to(&self.miner_address)
+        };
         let duration = start.elapsed();
 
-        let block = self.blockchain.get_latest_block();
-
         let message = format!(
-            "Block #{} mined successfully!\n  Hash: {}...\n  Nonce: {}\n  Transactions: {}\n  Time: {:?}",
+            "Block #{} mined successfully!\n  Hash: {}...\n  Nonce: {}\n  Transactions: {}\n  Reward: {} to {}\n  Time: {:?}",
             block.index,
-            &block.hash[..16.min(block.hash.len())],
+            self.visualizer.short(&block.hash),
             block.nonce,
             block.transaction_count(),
+            transaction::format_amount(block.coinbase().map_or(0, |tx| tx.amount)),
+            self.aliases.label(&self.miner_address),
             duration
         );
 
         Ok(Some(message))
     }
 
+    /// Mines the next block to the miner address in slices, redrawing a status
+    /// line (elapsed time, hashrate, ETA) in place while the search runs
+    fn mine_with_status(&mut self) -> Result<(), CliError> {
+        let timestamp = self.blockchain.now();
+        self.blockchain.prune_expired_transactions(timestamp);
+        let mut block = self.blockchain.create_reward_candidate_block(timestamp, &self.miner_address);
+
+        let start = Instant::now();
+        let mut last_draw = start;
+        let mut drawn = 0;
+        let (prefix, algorithm) = (self.blockchain.pow_prefix_char(), self.blockchain.pow_algorithm);
+        while !block.mine_step_with_algorithm(MINE_SLICE, prefix, algorithm) {
+            if last_draw.elapsed() >= MINE_STATUS_INTERVAL {
+                let status = mining_status(start.elapsed(), block.nonce, block.difficulty);
+                // Pad over any longer line drawn before
+                print!("\r{:<width$}", status, width = drawn);
+                let _ = io::stdout().flush();
+                drawn = status.len();
+                last_draw = Instant::now();
+            }
+        }
+        if drawn > 0 {
+            print!("\r{}\r", " ".repeat(drawn));
+            let _ = io::stdout().flush();
+        }
+
+        Ok(self.blockchain.try_append_mined_block(block)?)
+    }
+
+    /// Execute mine --dry-run command
+    fn execute_preview_block(&self) -> CommandResult {
+        let block = self.blockchain.build_reward_candidate_block(&self.miner_address);
+        // Everything after the coinbase comes from the mempool
+        let waiting = self.blockchain.pending_transaction_count().saturating_sub(block.transaction_count() - 1);
+
+        let mut output = format!(
+            "Candidate block #{} (not mined)\n  Previous: {}...\n  Difficulty: {}\n  Merkle root: {}...\n  Pre-mining hash: {}...\n  Size: {} bytes\n  Transactions: {} ({} pending left waiting)\n",
+            block.index,
+            self.visualizer.short(&block.previous_hash),
+            block.difficulty,
+            self.visualizer.short(&block.merkle_root),
+            self.visualizer.short(&block.hash),
+            block.serialized_size(),
+            block.transaction_count(),
+            waiting
+        );
+        for (i, tx) in block.transactions.iter().enumerate() {
+            output.push_str(&format!("    {}. {}\n", i + 1, self.aliases.transaction(tx)));
+        }
+        Ok(Some(output))
+    }
+
+    /// Execute step command
+    fn execute_step(&mut self) -> CommandResult {
+        let Some(report) = self.with_stepper(ChainStepper::step) else {
+            return Ok(Some(String::from("No pending transactions to step")));
+        };
+
+        Ok(Some(format!(
+            "Step {}: block #{}\n  Hash: {}...\n  Previous: {}...\n  Nonce: {}\n  Timestamp: {}\n  Transactions: {}\n  Reward: {} to {}\n  Time: {:?}\n  Pending: {}",
+            self.step_history.len(),
+            report.index,
+            self.visualizer.short(&report.hash),
+            self.visualizer.short(&report.previous_hash),
+            report.nonce,
+            report.timestamp,
+            report.transactions,
+            transaction::format_amount(report.reward),
+            self.aliases.label(&self.miner_address),
+            report.elapsed,
+            self.blockchain.pending_transaction_count()
+        )))
+    }
+
+    /// Runs `f` on a `ChainStepper` over this CLI's chain and step history
+    fn with_stepper<T>(&mut self, f: impl FnOnce(&mut ChainStepper) -> T) -> T {
+        let blockchain = std::mem::take(&mut self.blockchain);
+        let history = std::mem::take(&mut self.step_history);
+        let mut stepper = ChainStepper::resume(blockchain, history).with_miner(self.miner_address.clone());
+        let result = f(&mut stepper);
+        (self.blockchain, self.step_history) = stepper.into_parts();
+        result
+    }
+
+    /// Execute mine --all command
+    fn execute_mine_all(&mut self) -> CommandResult {
+        let pending_count = self.blockchain.pending_transaction_count();
+        if pending_count == 0 {
+            return Ok(Some(String::from("No pending transactions to mine")));
+        }
+
+        println!("Mining {} pending transaction(s)...", pending_count);
+
+        let start = Instant::now();
+        let blocks = self.blockchain.mine_all_pending_to(&self.miner_address);
+        let duration = start.elapsed();
+        let earned: u64 = self.blockchain.blocks()
+            .rev()
+            .take(blocks)
+            .filter_map(|block| block.coinbase().map(|tx| tx.amount))
+            .sum();
+
+        let mut message = format!(
+            "Mined {} block(s), now at height {}\n  Reward: {} to {}\n  Time: {:?}",
+            blocks,
+            self.blockchain.height(),
+            transaction::format_amount(earned),
+            self.aliases.label(&self.miner_address),
+            duration
+        );
+        let left = self.blockchain.pending_transaction_count();
+        if left > 0 {
+            message.push_str(&format!("\n  {} transaction(s) too large for a block remain pending", left));
+        }
+
+        Ok(Some(message))
+    }
+
+    /// Resolves a `--block` argument: a plain number is an index, anything else
+    /// (including digits with a leading zero, as PoW hashes have) a hash or prefix
+    fn find_block(&self, key: &str) -> Result<&Block, CliError> {
+        let is_index = key == "0" || !key.starts_with('0');
+        if is_index && let Ok(index) = key.parse::<usize>() {
+            return self.blockchain
+                .get_block(index)
+                .ok_or_else(|| CliError::InvalidArgument(format!("Block #{} does not exist", index)));
+        }
+
+        match self.blockchain.block_by_hash(key) {
+            Some(block) => Ok(block),
+            None => self.blockchain
+                .block_by_prefix(key)
+                .map_err(|e| CliError::InvalidArgument(e.to_string())),
+        }
+    }
+
     /// Execute show chain command
-    fn execute_show_chain(&self, full: bool, last_n: Option<usize>, block_n: Option<usize>) -> CommandResult {
-        if let Some(n) = block_n {
+    fn execute_show_chain(&self, full: bool, last_n: Option<usize>, block: Option<String>) -> CommandResult {
+        if let Some(key) = block {
             // Show specific block
-            if let Some(block) = self.blockchain.get_block(n) {
-                let output = if full {
-                    format!(
-                        "Block #{}\n  Index: {}\n  Hash: {}\n  Previous: {}\n  Nonce: {}\n  Transactions: {}",
-                        block.index,
-                        block.index,
-                        block.hash,
-                        block.previous_hash,
-                        block.nonce,
-                        block.transaction_count()
-                    )
-                } else {
-                    format!(
-                        "Block #{} | Hash: {}... | Txs: {}",
-                        block.index,
-                        &block.hash[..16.min(block.hash.len())],
-                        block.transaction_count()
-                    )
-                };
-                return Ok(Some(output));
+            let block = self.find_block(&key)?;
+            let output = if full {
+                format!(
+                    "Block #{}\n  Index: {}\n  Hash: {}\n  Previous: {}\n  Nonce: {}\n  Transactions: {}",
+                    block.index,
+                    block.index,
+                    block.hash,
+                    block.previous_hash,
+                    block.nonce,
+                    block.transaction_count()
+                )
             } else {
-                return Err(CliError::InvalidArgument(format!("Block #{} does not exist", n)));
-            }
+                format!(
+                    "Block #{} | Hash: {}... | Txs: {}",
+                    block.index,
+                    self.visualizer.short(&block.hash),
+                    block.transaction_count()
+                )
+            };
+            return Ok(Some(output));
         }
 
         let blocks_to_show: Vec<_> = if let Some(n) = last_n {
-            self.blockchain.chain.iter()
+            self.blockchain.blocks()
                 .rev()
                 .take(n)
                 .collect()
         } else {
-            self.blockchain.chain.iter().collect()
+            self.blockchain.blocks().collect()
         };
 
-        let mut output = format!("\n=== Blockchain ===\nTotal blocks: {}\nDifficulty: {}\nChain valid: {}\n\n",
+        let mut output = format!("\n=== Blockchain ===\nTotal blocks: {} (height {})\nDifficulty: {}\nChain valid: {}\n\n",
             self.blockchain.len(),
+            self.blockchain.height(),
             self.blockchain.get_difficulty(),
             self.blockchain.is_valid()
         );
@@ -489,23 +1571,24 @@ impl Cli {
         for block in blocks_to_show.into_iter().rev() {
             if full {
                 output.push_str(&format!(
-                    "Block #{}\n  Hash: {}\n  Previous: {}\n  Nonce: {}\n  Transactions: {}\n",
+                    "Block #{}\n  Hash: {}\n  Previous: {}\n  Merkle root: {}\n  Nonce: {}\n  Transactions: {}\n",
                     block.index,
                     block.hash,
                     block.previous_hash,
+                    block.merkle_root,
                     block.nonce,
                     block.transaction_count()
                 ));
 
                 for tx in &block.transactions {
-                    output.push_str(&format!("    {}\n", tx));
+                    output.push_str(&format!("    {}\n      id: {}\n", self.aliases.transaction(tx), tx.id()));
                 }
                 output.push('\n');
             } else {
                 output.push_str(&format!(
                     "Block #{} | Hash: {}... | Txs: {}\n",
                     block.index,
-                    &block.hash[..16.min(block.hash.len())],
+                    self.visualizer.short(&block.hash),
                     block.transaction_count()
                 ));
             }
@@ -515,7 +1598,30 @@ impl Cli {
     }
 
     /// Execute validate chain command
-    fn execute_validate_chain(&self) -> CommandResult {
+    fn execute_validate_chain(&self, json: bool, max_errors: Option<usize>) -> CommandResult {
+        // Stop validating once enough errors have been found
+        if let Some(max_errors) = max_errors {
+            let errors: Vec<ValidationError> = self.blockchain.iter_validation_errors().take(max_errors).collect();
+            let result = if errors.is_empty() { ValidationResult::valid() } else { ValidationResult::invalid(errors) };
+            if json {
+                return Ok(Some(result.summary().to_json()));
+            }
+            if result.is_valid {
+                return Ok(Some("Chain is VALID ✓".to_string()));
+            }
+
+            let mut output = format!("Chain is INVALID ✗\nFirst {} error(s):\n", result.errors.len());
+            for error in &result.errors {
+                output.push_str(&format!("  - {}\n", error));
+            }
+            return Ok(Some(output));
+        }
+
+        if json {
+            let summary = validation::validate_chain(&self.blockchain).summary();
+            return Ok(Some(summary.to_json()));
+        }
+
         let is_valid = self.blockchain.is_valid();
 
         if is_valid {
@@ -534,23 +1640,101 @@ impl Cli {
         } else {
             let mut output = format!("\n=== Pending Transactions ({}) ===\n", pending.len());
             for (i, tx) in pending.iter().enumerate() {
-                output.push_str(&format!("  {}. {}\n", i + 1, tx));
+                output.push_str(&format!("  {}. {}\n", i + 1, self.aliases.transaction(tx)));
             }
             Ok(Some(output))
         }
     }
 
+    /// Execute pending remove command (`position` is 1-based, as listed by `pending`)
+    fn execute_remove_pending(&mut self, position: usize) -> CommandResult {
+        let pending = self.blockchain.get_pending_transactions().len();
+        if pending > 0 && position > pending {
+            return Err(CliError::InvalidArgument(format!(
+                "No pending transaction {}; there are {}", position, pending
+            )));
+        }
+
+        let removed = self.blockchain.remove_pending(position - 1)
+            .map_err(CliError::BlockchainError)?;
+
+        Ok(Some(format!(
+            "Removed pending transaction {}: {}\nPending transactions: {}",
+            position,
+            removed,
+            self.blockchain.get_pending_transactions().len()
+        )))
+    }
+
+    /// Execute pending undo command
+    fn execute_undo_pending(&mut self) -> CommandResult {
+        let removed = self.blockchain.undo_last_pending()
+            .ok_or_else(|| CliError::BlockchainError("Mempool is empty".to_string()))?;
+
+        Ok(Some(format!(
+            "Removed last added transaction: {}\nPending transactions: {}",
+            removed,
+            self.blockchain.get_pending_transactions().len()
+        )))
+    }
+
+    /// Execute mempool prune command
+    fn execute_mempool_prune(&mut self) -> CommandResult {
+        let now = self.blockchain.now();
+        let removed = self.blockchain.prune_expired_transactions(now);
+
+        Ok(Some(format!(
+            "Pruned {} expired transaction(s) (TTL {}s)\nPending transactions: {}",
+            removed,
+            self.blockchain.mempool_ttl_ms / 1000,
+            self.blockchain.get_pending_transactions().len()
+        )))
+    }
+
     /// Execute show balance command
     fn execute_show_balance(&self, address: String) -> CommandResult {
-        let balance = self.calculate_balance(&address);
+        let address = self.resolve_address(address);
+        let balance = self.blockchain.balance(&address);
 
         Ok(Some(format!(
-            "Balance for '{}': {:.4}",
+            "Balance for '{}': {}",
             address,
-            balance
+            transaction::format_balance(balance)
         )))
     }
 
+    /// Execute history command
+    fn execute_show_history(&self, address: String) -> CommandResult {
+        let address = self.resolve_address(address);
+        let history = self.blockchain.transaction_history(&address);
+
+        if history.is_empty() {
+            return Ok(Some(format!("No confirmed transactions for '{}'", address)));
+        }
+
+        let mut output = format!("\n=== History for '{}' ===\n", address);
+        output.push_str(&format!(
+            "  {:>5}  {:<8}  {:<16}  {:>12}  {:>12}\n",
+            "Block", "Type", "Counterparty", "Amount", "Balance"
+        ));
+        for record in &history {
+            let sign = match record.direction {
+                Direction::Sent => "-",
+                Direction::Received => "+",
+            };
+            output.push_str(&format!(
+                "  {:>5}  {:<8}  {:<16}  {:>12}  {:>12}\n",
+                format!("#{}", record.block_index),
+                record.direction,
+                self.aliases.label(&record.counterparty),
+                format!("{}{}", sign, transaction::format_amount(record.amount)),
+                transaction::format_balance(record.balance)
+            ));
+        }
+
+        Ok(Some(output))
+    }
+
     /// Execute set difficulty command
     fn execute_set_difficulty(&mut self, difficulty: u32) -> CommandResult {
         self.blockchain.set_difficulty(difficulty);
@@ -563,26 +1747,248 @@ impl Cli {
     }
 
     /// Execute show stats command
-    fn execute_show_stats(&self) -> CommandResult {
+    fn execute_show_stats(&mut self) -> CommandResult {
+        let hashrate = self.current_hashrate();
+
         let stats = format!(
             "\n=== Blockchain Statistics ===\n\
-             Total blocks:           {}\n\
-             Latest block:           #{}\n\
+             Total blocks:           {} (including genesis)\n\
+             Height:                 {}\n\
              Latest hash:            {}...\n\
              Pending transactions:   {}\n\
              Current difficulty:     {}\n\
+             Measured hashrate:      {}\n\
+             Miner address:          {}\n\
+             Miner balance:          {}\n\
              Chain valid:            {}",
             self.blockchain.len(),
-            self.blockchain.get_latest_block().index,
-            &self.blockchain.get_latest_block().hash[..16.min(self.blockchain.get_latest_block().hash.len())],
+            self.blockchain.height(),
+            self.visualizer.short(self.blockchain.tip_hash()),
             self.blockchain.pending_transaction_count(),
             self.blockchain.get_difficulty(),
+            experiments::format_hashrate(hashrate),
+            self.miner_address,
+            transaction::format_balance(self.blockchain.balance(&self.miner_address)),
             if self.blockchain.is_valid() { "Yes ✓" } else { "No ✗" }
         );
 
         Ok(Some(stats))
     }
 
+    /// Execute metrics command
+    /// A .json path gets JSON; anything else, or no path, gets CSV
+    fn execute_export_metrics(&self, out: Option<String>) -> CommandResult {
+        let Some(path) = out else {
+            return Ok(Some(self.blockchain.block_metrics_csv()));
+        };
+        let contents = if path.ends_with(".json") {
+            self.blockchain.block_metrics_json()
+        } else {
+            self.blockchain.block_metrics_csv()
+        };
+        std::fs::write(&path, contents)
+            .map_err(|e| CliError::FileError(format!("Failed to write to '{}': {}", path, e)))?;
+        Ok(Some(format!("Metrics for {} block(s) written to '{}'", self.blockchain.len(), path)))
+    }
+
+    /// Execute audit command
+    fn execute_show_audit(&self) -> CommandResult {
+        let log = self.blockchain.audit_log();
+        if !self.blockchain.is_audited() {
+            return Ok(Some(String::from("Audit log is off (turn it on with 'audit on')")));
+        }
+        let Some(first) = log.first() else {
+            return Ok(Some(String::from("Audit log is empty (it starts at 'audit on', or at the last load)")));
+        };
+
+        let mut output = format!("Audit log: {} event(s)\n", log.len());
+        for (n, event) in log.iter().enumerate() {
+            output.push_str(&format!("  {:>3}. +{} ms  {}\n", n + 1, event.timestamp.saturating_sub(first.timestamp), event.kind));
+        }
+        Ok(Some(output.trim_end().to_string()))
+    }
+
+    /// Execute supply command
+    fn execute_show_supply(&self) -> CommandResult {
+        let next_height = self.blockchain.len() as u64;
+        let next_halving = match self.blockchain.blocks_until_halving() {
+            Some(blocks) => format!("in {} block(s), at block #{}", blocks, next_height + blocks),
+            None => "never".to_string(),
+        };
+
+        Ok(Some(format!(
+            "\n=== Supply ===\n\
+             Total issued:           {} coins over {} block(s)\n\
+             Total work:             {} expected hashes\n\
+             Current block reward:   {}\n\
+             Next halving:           {}\n\
+             Max supply:             {}",
+            transaction::format_amount(self.blockchain.total_issuance()),
+            self.blockchain.height(),
+            self.blockchain.total_work(),
+            transaction::format_amount(self.blockchain.next_block_reward()),
+            next_halving,
+            self.blockchain.max_supply.map_or_else(|| String::from("uncapped"), transaction::format_amount)
+        )))
+    }
+
+    /// Execute checkpoint add command
+    fn execute_add_checkpoint(&mut self, index: usize) -> CommandResult {
+        let hash = self.blockchain.add_checkpoint(index)
+            .map_err(CliError::InvalidArgument)?;
+
+        Ok(Some(format!(
+            "Checkpoint added at block #{} ({})\nReorgs that would replace block #{} or earlier are now rejected",
+            index, hash, index
+        )))
+    }
+
+    /// Execute hashrate command
+    fn execute_hashrate(&mut self) -> CommandResult {
+        println!("Mining {} throwaway blocks at difficulty {}...",
+            experiments::HASHRATE_SAMPLES,
+            experiments::HASHRATE_SAMPLE_DIFFICULTY
+        );
+
+        let hashrate = self.measure_hashrate();
+
+        Ok(Some(format!(
+            "Measured hashrate: {} ({:.0} hashes/second)",
+            experiments::format_hashrate(hashrate),
+            hashrate
+        )))
+    }
+
+    /// Measure the hashrate and share it with the experiments
+    fn measure_hashrate(&mut self) -> f64 {
+        let hashrate = self.blockchain.estimate_hashrate(
+            experiments::HASHRATE_SAMPLE_DIFFICULTY,
+            experiments::HASHRATE_SAMPLES,
+        );
+        self.experiments.set_hashrate(hashrate);
+        hashrate
+    }
+
+    /// Execute watch command: mine whenever the policy says so, until Ctrl-C
+    fn execute_watch(&mut self, policy: WatchPolicy) -> CommandResult {
+        install_interrupt_handler();
+        WATCH_INTERRUPTED.store(false, Ordering::SeqCst);
+        WATCHING.store(true, Ordering::SeqCst);
+
+        println!(
+            "Watching the mempool: mining at {} pending, or every {}ms while any are pending. Press Ctrl-C to stop.",
+            policy.threshold,
+            policy.interval.as_millis()
+        );
+
+        let mut mined = 0;
+        let mut last_block = Instant::now();
+        while !WATCH_INTERRUPTED.load(Ordering::SeqCst) {
+            let pending = self.blockchain.pending_transaction_count();
+            if !policy.should_mine(pending, last_block.elapsed()) {
+                thread::sleep(WATCH_POLL);
+                continue;
+            }
+
+            match self.mine_until_interrupted() {
+                Some(message) => {
+                    println!("{}", message);
+                    mined += 1;
+                    last_block = Instant::now();
+                }
+                None => println!("Mining aborted; its transactions are back in the mempool"),
+            }
+        }
+
+        WATCHING.store(false, Ordering::SeqCst);
+        Ok(Some(format!("Stopped watching after mining {} block(s)", mined)))
+    }
+
+    /// Execute tui command: the dashboard takes over the terminal until `q`
+    #[cfg(feature = "tui")]
+    fn execute_tui(&mut self) -> CommandResult {
+        crate::tui::run(&mut self.blockchain, &self.miner_address, WatchPolicy::default())
+            .map(Some)
+            .map_err(|e| CliError::BlockchainError(format!("Dashboard failed: {}", e)))
+    }
+
+    #[cfg(not(feature = "tui"))]
+    fn execute_tui(&mut self) -> CommandResult {
+        Err(CliError::InvalidArgument(String::from("This build has no dashboard; rebuild with --features tui")))
+    }
+
+    /// Execute serve command: answer HTTP API requests until Ctrl-C
+    #[cfg(feature = "http")]
+    fn execute_serve(&mut self, port: u16) -> CommandResult {
+        let server = ApiServer::bind(&format!("127.0.0.1:{}", port))
+            .map_err(CliError::BlockchainError)?
+            .with_miner(self.miner_address.clone());
+
+        install_interrupt_handler();
+        SERVE_INTERRUPTED.store(false, Ordering::SeqCst);
+        SERVING.store(true, Ordering::SeqCst);
+
+        println!(
+            "Serving the API on http://127.0.0.1:{} (rewards to {}). Press Ctrl-C to stop.",
+            server.port().unwrap_or(port),
+            self.miner_address
+        );
+
+        let mut handled = 0;
+        while !SERVE_INTERRUPTED.load(Ordering::SeqCst) {
+            match server.handle_next(&mut self.blockchain, WATCH_POLL) {
+                Ok(true) => handled += 1,
+                Ok(false) => {}
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+
+        SERVING.store(false, Ordering::SeqCst);
+        Ok(Some(format!("Stopped serving after {} request(s)", handled)))
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn execute_serve(&mut self, _port: u16) -> CommandResult {
+        Err(CliError::InvalidArgument(String::from("This build has no HTTP API; rebuild with --features http")))
+    }
+
+    /// Mines the next block to the miner address in slices, checking for Ctrl-C
+    /// between them. Returns None if interrupted, with the transactions requeued
+    fn mine_until_interrupted(&mut self) -> Option<String> {
+        let timestamp = self.blockchain.now();
+        self.blockchain.prune_expired_transactions(timestamp);
+        let mut block = self.blockchain.create_reward_candidate_block(timestamp, &self.miner_address);
+
+        let start = Instant::now();
+        let (prefix, algorithm) = (self.blockchain.pow_prefix_char(), self.blockchain.pow_algorithm);
+        while !block.mine_step_with_algorithm(MINE_SLICE, prefix, algorithm) {
+            if WATCH_INTERRUPTED.load(Ordering::SeqCst) {
+                self.blockchain.requeue_candidate(block);
+                return None;
+            }
+        }
+
+        let message = format!(
+            "Block #{} mined: {} transaction(s), nonce {}, {:?}",
+            block.index,
+            block.transaction_count(),
+            block.nonce,
+            start.elapsed()
+        );
+        match self.blockchain.try_append_mined_block(block) {
+            Ok(()) => Some(message),
+            Err(e) => Some(format!("Mined block was rejected: {}", e)),
+        }
+    }
+
+    /// Last measured hashrate, measuring now if there isn't one yet
+    fn current_hashrate(&mut self) -> f64 {
+        match self.blockchain.measured_hashrate() {
+            Some(hashrate) => hashrate,
+            None => self.measure_hashrate(),
+        }
+    }
+
     /// Execute save command
     fn execute_save(&self, path: String) -> CommandResult {
         // Serialize blockchain to JSON
@@ -596,44 +2002,177 @@ impl Cli {
         Ok(Some(format!("Blockchain saved to '{}'", path)))
     }
 
+    /// Execute autosave command: saves to `path` right away, so a bad path
+    /// is reported now rather than at the next block
+    fn execute_autosave(&mut self, path: Option<String>) -> CommandResult {
+        let Some(path) = path else {
+            self.auto_save_path = None;
+            self.stash_for_interrupt();
+            return Ok(Some(String::from("Autosave is off")));
+        };
+        self.execute_save(path.clone())?;
+        self.auto_save_path = Some(path.clone());
+        self.stash_for_interrupt();
+        install_interrupt_handler();
+        Ok(Some(format!("Autosaving to '{}' after every new block, on exit, and on Ctrl-C", path)))
+    }
+
+    /// Saves to the autosave path, if it's on; a failed save is only a warning
+    fn autosave(&self) {
+        if let Some(path) = &self.auto_save_path
+            && let Err(e) = self.execute_save(path.clone())
+        {
+            eprintln!("Warning: autosave failed: {}", e);
+        }
+    }
+
+    /// Keeps the chain as it stands for the Ctrl-C handler to save
+    fn stash_for_interrupt(&self) {
+        let snapshot = self.auto_save_path.as_ref().and_then(|path| {
+            serde_json::to_string_pretty(&self.blockchain).ok().map(|json| (path.clone(), json))
+        });
+        if let Ok(mut stashed) = INTERRUPT_SNAPSHOT.lock() {
+            *stashed = snapshot;
+        }
+    }
+
     /// Execute load command
-    fn execute_load(&mut self, path: String) -> CommandResult {
-        // Read from file
-        let json = std::fs::read_to_string(&path)
+    /// The file must use this node's consensus rules; a session saved with
+    /// `save --state` restores its own through `load --state` instead
+    fn execute_load(&mut self, path: String, allow_invalid: bool) -> CommandResult {
+        let blockchain = Self::read_chain_file(&path)?;
+        let mismatches = self.blockchain.consensus_mismatches(&blockchain);
+        if !mismatches.is_empty() {
+            return Err(CliError::BlockchainError(format!(
+                "'{}' uses different consensus rules ({}); set them on this node first, or restore a saved session with 'load --state'",
+                path,
+                mismatches.join(", ")
+            )));
+        }
+        let kept_invalid = Self::check_loaded(&blockchain, allow_invalid)?;
+        let audited = self.blockchain.is_audited();
+        self.blockchain = blockchain;
+        self.blockchain.set_audit(audited);
+        let dropped = self.blockchain.reconcile_pending();
+
+        let message = format!(
+            "Blockchain loaded from '{}' ({} stale pending transaction(s) dropped)",
+            path,
+            dropped.len()
+        );
+        Ok(Some(self.with_self_check(message, kept_invalid)))
+    }
+
+    /// Reads a chain saved with `save`, checking each block's hash as it loads
+    fn read_chain_file(path: &str) -> Result<Blockchain, CliError> {
+        let json = std::fs::read_to_string(path)
             .map_err(|e| CliError::FileError(format!("Failed to read from '{}': {}", path, e)))?;
+        Blockchain::from_json(&json)
+            .map_err(|e| CliError::FileError(format!("'{}': {}", path, e)))
+    }
 
-        // Deserialize blockchain
-        let blockchain: Blockchain = serde_json::from_str(&json)
-            .map_err(|e| CliError::FileError(format!("Deserialization failed: {}", e)))?;
+    /// Execute diff command on two chain files
+    fn execute_diff_files(&self, file_a: String, file_b: String) -> CommandResult {
+        let a = Self::read_chain_file(&file_a)?;
+        let b = Self::read_chain_file(&file_b)?;
+        Ok(Some(self.visualizer.format_chain_patch(&a, &b, [&file_a, &file_b]).trim_end().to_string()))
+    }
 
-        // Validate loaded chain
-        if !blockchain.is_valid() {
-            return Err(CliError::FileError(
-                "Loaded blockchain is invalid and cannot be used".to_string()
-            ));
+    /// The first validation error of a loaded chain, unless `allow_invalid`
+    /// accepts it anyway; Ok(true) means an invalid chain is being kept
+    fn check_loaded(blockchain: &Blockchain, allow_invalid: bool) -> Result<bool, CliError> {
+        match blockchain.iter_validation_errors().next() {
+            Some(error) if !allow_invalid => Err(error.into()),
+            Some(_) => Ok(true),
+            None => Ok(false),
         }
+    }
+
+    /// `message`, followed by the self-check report (and the `repair` offer)
+    /// when an invalid chain was kept on request
+    fn with_self_check(&mut self, message: String, kept_invalid: bool) -> String {
+        match kept_invalid.then(|| self.self_check()).flatten() {
+            Some(report) => format!("{}\n{}", message, report),
+            None => message,
+        }
+    }
 
+    /// Execute load --state command
+    fn execute_load_state(&mut self, path: String, allow_invalid: bool) -> CommandResult {
+        let state = NodeState::load_state(&path)
+            .map_err(|e| CliError::FileError(format!("'{}': {}", path, e)))?;
+        let (blockchain, miner_address) = state.restore()
+            .map_err(|e| CliError::FileError(format!("'{}': {}", path, e)))?;
+        let kept_invalid = Self::check_loaded(&blockchain, allow_invalid)?;
+        let audited = self.blockchain.is_audited();
         self.blockchain = blockchain;
+        self.blockchain.set_audit(audited);
+        if let Some(address) = miner_address {
+            self.miner_address = address;
+        }
+        let dropped = self.blockchain.reconcile_pending();
 
-        Ok(Some(format!("Blockchain loaded from '{}'", path)))
+        let message = format!(
+            "Node state loaded from '{}': height {}, {} pending transaction(s), miner {} ({} stale dropped)",
+            path,
+            self.blockchain.height(),
+            self.blockchain.pending_transaction_count(),
+            self.miner_address,
+            dropped.len()
+        );
+        Ok(Some(self.with_self_check(message, kept_invalid)))
     }
 
-    /// Calculate balance for an address
-    fn calculate_balance(&self, address: &str) -> f64 {
-        let mut balance = 0.0;
+    /// Execute prove command
+    fn execute_prove(&self, tx_id: String) -> CommandResult {
+        let proof = self.blockchain.prove_transaction(&tx_id)
+            .ok_or_else(|| CliError::InvalidArgument(match self.blockchain.find_transaction(&tx_id) {
+                Some((block, _)) => format!(
+                    "Block #{} predates provable Merkle trees (version {}); no proof can be built",
+                    block.index, block.version
+                ),
+                None => format!("Transaction {} is not in any mined block", tx_id),
+            }))?;
 
-        for block in &self.blockchain.chain {
-            for tx in &block.transactions {
-                if tx.sender == address {
-                    balance -= tx.amount;
-                }
-                if tx.receiver == address {
-                    balance += tx.amount;
-                }
-            }
+        let json = serde_json::to_string(&proof)
+            .map_err(|e| CliError::BlockchainError(format!("Failed to serialize proof: {}", e)))?;
+
+        Ok(Some(format!(
+            "Transaction found in block #{} (position {})\n  Merkle root: {}\n  Path length: {}\n\nProof:\n{}",
+            proof.block_index,
+            proof.leaf_index,
+            proof.merkle_root,
+            proof.path.len(),
+            json
+        )))
+    }
+
+    /// Execute verify command
+    /// With a proof argument, checks that proof; otherwise rebuilds one from the chain
+    fn execute_verify(&self, tx_id: String, proof: Option<String>) -> CommandResult {
+        let proof = match proof {
+            Some(json) => serde_json::from_str::<MerkleProof>(&json)
+                .map_err(|e| CliError::InvalidArgument(format!("Invalid proof JSON: {}", e)))?,
+            None => self.blockchain.prove_transaction(&tx_id)
+                .ok_or_else(|| CliError::InvalidArgument(
+                    format!("Transaction {} is not in any mined block", tx_id)
+                ))?,
+        };
+
+        if proof.tx_id != tx_id {
+            return Ok(Some(format!(
+                "Proof INVALID ✗\nProof is for transaction {}, not {}",
+                proof.tx_id, tx_id
+            )));
         }
 
-        balance
+        match self.blockchain.verify_transaction_proof(&proof) {
+            Ok(()) => Ok(Some(format!(
+                "Proof VALID ✓\nTransaction {} is included in block #{} (Merkle root {})",
+                tx_id, proof.block_index, proof.merkle_root
+            ))),
+            Err(reason) => Ok(Some(format!("Proof INVALID ✗\n{}", reason))),
+        }
     }
 
     // =========================================================================
@@ -642,15 +2181,15 @@ impl Cli {
 
     /// Execute attack list command
     fn execute_attack_list(&self) -> CommandResult {
-        let mut output = format!("\n=== Available Attack Simulations ===\n\n");
+        let mut output = String::from("\n=== Available Attack Simulations ===\n\n");
 
         for (i, attack_type) in AttackType::all().iter().enumerate() {
-            output.push_str(&format!("  {}. {}\n", i + 1, attack_type));
+            output.push_str(&format!("  {}. {} ({})\n", i + 1, attack_type, attack_type.short_name()));
             output.push_str(&format!("     {}\n\n", attack_type.description()));
         }
 
         output.push_str("Usage:\n");
-        output.push_str("  attack run <name>     Run a specific attack\n");
+        output.push_str("  attack run <name>     Run a specific attack, e.g. attack run tx-tamper\n");
         output.push_str("  attack all            Run all attacks\n");
         output.push_str("  attack report         Show attack results summary\n");
 
@@ -659,14 +2198,7 @@ impl Cli {
 
     /// Execute attack run command
     fn execute_attack_run(&mut self, attack_name: String) -> CommandResult {
-        // Find matching attack type
-        let attack_type = AttackType::all().into_iter()
-            .find(|t| t.to_string().to_lowercase().contains(&attack_name.to_lowercase()));
-
-        let attack_type = match attack_type {
-            Some(t) => t,
-            None => return Err(CliError::InvalidArgument(format!("Unknown attack: {}", attack_name))),
-        };
+        let attack_type = Self::find_attack_type(&attack_name)?;
 
         // Run the attack
         let result = self.attack_simulator.run_attack(attack_type, &self.blockchain);
@@ -674,6 +2206,36 @@ impl Cli {
         Ok(Some(result.to_string()))
     }
 
+    /// Execute attack inspect command: replay one attack with chain capture
+    /// and show the chain before and after it
+    fn execute_attack_inspect(&mut self, attack_name: String) -> CommandResult {
+        let attack_type = Self::find_attack_type(&attack_name)?;
+
+        // A separate simulator, so inspecting doesn't add to the attack report
+        let mut simulator = AttackSimulator::new();
+        simulator.set_capture_chains(true);
+        let result = simulator.run_attack(attack_type, &self.blockchain);
+
+        if let Some(attacked) = &result.attacked_chain {
+            self.visualizer.display_attack_comparison(&self.blockchain, attacked, &result.attack_name);
+        }
+
+        let mut output = result.to_string();
+        if !result.validation_errors.is_empty() {
+            output.push_str("\nValidation errors on the attacked chain:\n");
+            for (i, error) in result.validation_errors.iter().enumerate() {
+                output.push_str(&format!("  {}. {}\n", i + 1, error));
+            }
+        }
+
+        Ok(Some(output))
+    }
+
+    /// Looks up an attack by its short name (see `attack list`)
+    fn find_attack_type(attack_name: &str) -> Result<AttackType, CliError> {
+        AttackType::try_from(attack_name).map_err(CliError::InvalidArgument)
+    }
+
     /// Execute attack all command
     fn execute_attack_all(&mut self) -> CommandResult {
         println!("\n=== Running All Attack Simulations ===\n");
@@ -682,36 +2244,62 @@ impl Cli {
         if self.blockchain.len() < 2 {
             // Create some blocks for testing
             println!("Creating test blockchain...");
-            self.blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10.0).unwrap();
+            self.blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
             self.blockchain.mine_block();
-            self.blockchain.add_transaction("Bob".to_string(), "Charlie".to_string(), 5.0).unwrap();
+            self.blockchain.add_transaction("Bob".to_string(), "Charlie".to_string(), 5 * COIN).unwrap();
             self.blockchain.mine_block();
         }
 
-        let results = self.attack_simulator.run_all_attacks(&self.blockchain);
+        self.attack_simulator.run_all_attacks(&self.blockchain);
 
         let summary = self.attack_simulator.generate_summary();
         Ok(Some(summary))
     }
 
     /// Execute attack report command
-    fn execute_attack_report(&self) -> CommandResult {
+    fn execute_attack_report(&self, by_severity: bool, json: bool) -> CommandResult {
         if self.attack_simulator.results.is_empty() {
             Ok(Some("No attack results available. Run 'attack all' first.".to_string()))
         } else {
-            Ok(Some(self.attack_simulator.generate_summary()))
+            let order = if by_severity { SummaryOrder::Severity } else { SummaryOrder::RunOrder };
+            if json {
+                Ok(Some(self.attack_simulator.results_as_json_ordered(order)))
+            } else {
+                Ok(Some(self.attack_simulator.generate_summary_ordered(order)))
+            }
         }
     }
 
     /// Execute experiment command
-    fn execute_experiment(&mut self, experiment_type: String) -> CommandResult {
+    fn execute_experiment(&mut self, experiment_type: String, out: Option<String>) -> CommandResult {
+        if out.is_some() && !matches!(experiment_type.as_str(), "difficulty" | "diff" | "chain-history") {
+            return Err(CliError::InvalidArgument(
+                "--out is only supported by the difficulty and chain-history experiments".to_string()
+            ));
+        }
+
         match experiment_type.as_str() {
             "difficulty" | "diff" => {
-                self.experiments.experiment_difficulty_vs_time(4, 3);
-                Ok(Some("Difficulty experiment complete!".to_string()))
+                let result = self.experiments.experiment_difficulty_vs_time(4, 3);
+                let Some(path) = out else {
+                    return Ok(Some("Difficulty experiment complete!".to_string()));
+                };
+
+                // A .json path gets JSON; anything else gets CSV
+                let contents = if path.ends_with(".json") { result.to_json() } else { result.to_csv() };
+                std::fs::write(&path, contents)
+                    .map_err(|e| CliError::FileError(format!("Failed to write to '{}': {}", path, e)))?;
+                Ok(Some(format!("Difficulty experiment complete! Results written to '{}'", path)))
             }
             "cost" => {
-                self.experiments.calculate_attack_cost(6, 4, 1_000_000_000, 0.10, 1000.0);
+                let hashrate = self.current_hashrate();
+                self.experiments.calculate_attack_cost(
+                    6,
+                    4,
+                    (hashrate as u64).max(1),
+                    experiments::DEFAULT_ELECTRICITY_RATE_PER_KWH,
+                    experiments::DEFAULT_POWER_WATTS,
+                );
                 Ok(Some("Attack cost calculation complete!".to_string()))
             }
             "cascade" | "cascading" => {
@@ -719,6 +2307,8 @@ impl Cli {
                 Ok(Some("Cascading failure demonstration complete!".to_string()))
             }
             "finality" => {
+                let hashrate = self.current_hashrate();
+                self.experiments.set_hashrate(hashrate);
                 self.experiments.demonstrate_finality(6);
                 Ok(Some("Finality demonstration complete!".to_string()))
             }
@@ -726,12 +2316,28 @@ impl Cli {
                 self.experiments.demonstrate_longest_chain_rule();
                 Ok(Some("Longest chain rule demonstration complete!".to_string()))
             }
+            "chain-history" => {
+                // This chain's own difficulty and block times, as CSV
+                let csv = experiments::chain_history_csv(&self.blockchain);
+                let Some(path) = out else {
+                    return Ok(Some(csv));
+                };
+                std::fs::write(&path, csv)
+                    .map_err(|e| CliError::FileError(format!("Failed to write to '{}': {}", path, e)))?;
+                Ok(Some(format!("Chain history written to '{}'", path)))
+            }
+            "propagation" | "prop" => {
+                let result = self.experiments.experiment_propagation(4, 2_000, 30);
+                Ok(Some(format!("Propagation experiment complete! Orphan rate: {:.1}%", result.orphan_rate * 100.0)))
+            }
             "all" => {
+                let hashrate = self.current_hashrate();
+                self.experiments.set_hashrate(hashrate);
                 self.experiments.run_all_experiments();
                 Ok(Some("All experiments complete!".to_string()))
             }
             _ => Err(CliError::InvalidArgument(format!(
-                "Unknown experiment: {}. Available: difficulty, cost, cascade, finality, longest, all",
+                "Unknown experiment: {}. Available: difficulty, cost, cascade, finality, longest, propagation, 51, chain-history, all",
                 experiment_type
             ))),
         }
@@ -739,7 +2345,7 @@ impl Cli {
 
     /// Execute visualize command
     fn execute_visualize(&self) -> CommandResult {
-        self.visualizer.display_chain(&self.blockchain);
+        self.visualizer.display_chain(&self.blockchain, &self.aliases);
         Ok(None)
     }
 
@@ -781,35 +2387,74 @@ impl Cli {
 
     /// Display help information
     fn display_help() -> String {
-        format!(
+        String::from(
             "\n=== RustChain CLI Help ===\n\n\
              Commands:\n\
              \n  Transaction Commands:\n\
                 add <sender> <receiver> <amount>   Add a new transaction\n\
+                    [<sender> <receiver> <amount>...]  Add several at once, all or none\n\
+                    [--memo <text>]                  Attach a memo (max 80 bytes)\n\
                 pending                            Show pending transactions\n\
+                pending remove <n>                 Remove pending transaction number n\n\
+                pending undo                       Remove the last added transaction\n\
+                mempool prune                      Drop expired pending transactions\n\
                 balance <address>                  Show balance for address\n\
+                history <address>                  Show address ledger with running balance\n\
+                alias [<name> <address>]           Name an address (any command takes the name)\n\
              \n  Mining Commands:\n\
-                mine                               Mine a new block\n\
-                difficulty <N>                     Set mining difficulty (1-6)\n\
+                mine                               Mine a new block (reward to the miner address)\n\
+                mine --all                         Mine blocks until the mempool is empty\n\
+                mine --dry-run                     Show the next block's contents without mining\n\
+                step                               Mine one block and show what changed\n\
+               rewind                             Undo the last step\n\
+               setminer <address>                 Set where block rewards are paid\n\
+                watch [--threshold N]              Auto-mine at N pending (default 3)\n\
+                      [--interval MS]                or every MS ms with any pending\n\
+                serve [--port N]                   Serve the JSON HTTP API (default 8080)\n\
+                tui                                Live dashboard: m mine, v validate, w watch\n\
+               difficulty <N>                     Set mining difficulty (1-6)\n\
+                hashrate                           Benchmark this machine's hashrate\n\
+                autodiff <ms>                      Pick the difficulty that mines in ~ms here\n\
+                checkpoint add <index>             Pin a block so reorgs can't replace it\n\
+                repair                             Re-mine from the first invalid block\n\
+                block <address>                    Reject transactions to or from an address\n\
+                unblock <address>                  Take an address off the blocklist\n\
              \n  Display Commands:\n\
                 chain [--full] [--last N]          Display blockchain\n\
-                          [--block N]                \n\
+                          [--block N|hash|prefix]    \n\
+                chain --page N [--per-page M]      One page of the chain (10 blocks a page)\n\
                 stats                              Show blockchain statistics\n\
-                validate                           Validate chain integrity\n\
+                supply                             Show coins issued, total work, reward and halving\n\
+                audit [on|off]                     Show the audit log, or start or stop recording it\n\
+                metrics [--out <path>]             Per-block metrics as CSV (.json path: JSON)\n\
+                set hashlen <N>                    Hash characters to display (default 16)\n\
+                validate [--json] [--max-errors N] Validate chain integrity\n\
                 visualize                           Display blockchain visualization\n\
              \n  Day 7: Attack Simulation:\n\
                 attack list                        List available attacks\n\
-                attack run <name>                  Run a specific attack\n\
+                attack run <name>                  Run an attack by short name (attack list)\n\
+                attack inspect <name>              Replay an attack, show before/after\n\
                 attack all                         Run all attack simulations\n\
-                attack report                      Show attack results\n\
+                attack report [--by-severity]      Show attack results\n\
+                        [--json]                     (sorted / machine-readable)\n\
              \n  Day 7: Security Experiments:\n\
                 experiment <type>                  Run security experiment\n\
-                  Types: difficulty, cost, cascade, finality, longest, all\n\
+                  Types: difficulty, cost, cascade, finality, longest, propagation, chain-history, all\n\
+                experiment difficulty --out <path> Also write results as CSV (.json: JSON)\n\
+                experiment 51 [fraction]           Race an attacker with that hashrate share (default 0.3)\n\
                 learn [topic]                      Educational content\n\
                   Topics: difficulty, double-spend, lifecycle, pow\n\
+             \n  Merkle Proofs:\n\
+                prove <tx_id>                      Print an inclusion proof (JSON)\n\
+                verify <tx_id> [proof_json]        Check a proof against the block's root\n\
              \n  Storage Commands:\n\
-                save <path>                        Save blockchain to file\n\
-                load <path>                        Load blockchain from file\n\
+                save [--state] <path>              Save blockchain (or the whole session) to file\n\
+                autosave <path>|off                Save after every new block, on exit, and on Ctrl-C\n\
+                load [--state] [--force] [--allow-invalid] <path>\n\
+                                                   Load blockchain (or a saved session) from file;\n\
+                                                   --force skips the prompt, --allow-invalid keeps an invalid chain\n\
+                reset [--force]                    Discard the chain and start from genesis\n\
+                diff <file_a> <file_b>             List blocks added/removed/changed between saved chains\n\
              \n  Other:\n\
                 help                               Show this help message\n\
                 exit                               Exit interactive mode\n\
@@ -819,6 +2464,7 @@ impl Cli {
                 atk = attack   exp = experiment   viz = visualize\n\
              \nExamples:\n\
                 add Alice Bob 10.5\n\
+                add Alice Bob 2 --memo lunch money\n\
                 mine\n\
                 chain --full\n\
                 attack all\n\
@@ -831,7 +2477,11 @@ impl Cli {
     /// Run interactive mode
     pub fn run_interactive(&mut self) {
         println!("\n=== RustChain Day 7: Attack Simulation & Security ===");
+        println!("Mining rewards go to {} (change with 'setminer <address>')", self.miner_address);
         println!("Type 'help' for available commands\n");
+        if let Some(report) = self.self_check() {
+            println!("{}\n", report);
+        }
 
         loop {
             print!("rustchain> ");
@@ -841,6 +2491,7 @@ impl Cli {
             match io::stdin().read_line(&mut input) {
                 Ok(0) => {
                     // EOF (Ctrl+D)
+                    self.autosave();
                     println!("\nGoodbye!");
                     break;
                 }
@@ -853,24 +2504,26 @@ impl Cli {
                     // Add to history
                     self.command_history.push(input.to_string());
 
-                    // Parse command
-                    let args: Vec<String> = input
-                        .split_whitespace()
-                        .map(|s| s.to_string())
-                        .collect();
-
-                    match Self::parse_command(&args) {
+                    match Command::try_from(input) {
                         Ok(command) => {
                             if command == Command::Exit {
+                                self.autosave();
                                 println!("Goodbye!");
                                 break;
                             }
+                            if !self.confirm(&command, &mut io::stdin().lock()) {
+                                println!("Cancelled");
+                                continue;
+                            }
 
                             match self.execute_command(command) {
                                 Ok(Some(message)) => println!("{}", message),
                                 Ok(None) => {}
                                 Err(e) => eprintln!("Error: {}", e),
                             }
+                            if self.auto_save_path.is_some() {
+                                self.stash_for_interrupt();
+                            }
                         }
                         Err(e) => eprintln!("Error: {}", e),
                     }
@@ -883,6 +2536,28 @@ impl Cli {
         }
     }
 
+    /// Whether to go ahead with `command`: yes, unless it needs confirmation
+    /// and there is work to lose (blocks past genesis, or pending transactions);
+    /// then only a "y" read from `input` goes ahead
+    fn confirm(&self, command: &Command, input: &mut impl BufRead) -> bool {
+        let height = self.blockchain.height();
+        let pending = self.blockchain.pending_transaction_count();
+        if !command.needs_confirmation() || (height == 0 && pending == 0) {
+            return true;
+        }
+
+        print!(
+            "This discards the current chain ({} block(s), {} pending transaction(s)). Continue? [y/N] ",
+            height, pending
+        );
+        let _ = io::stdout().flush();
+        let mut answer = String::new();
+        if input.read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    }
+
     /// Run single command mode
     pub fn run_single_command(&mut self, args: &[String]) {
         match Self::parse_command(args) {
@@ -892,13 +2567,13 @@ impl Cli {
                     Ok(None) => {}
                     Err(e) => {
                         eprintln!("Error: {}", e);
-                        process::exit(1);
+                        process::exit(e.exit_code());
                     }
                 }
             }
             Err(e) => {
                 eprintln!("Error: {}", e);
-                process::exit(1);
+                process::exit(e.exit_code());
             }
         }
     }
@@ -928,6 +2603,32 @@ impl Default for Cli {
     }
 }
 
+impl Command {
+    /// Whether the command discards the current chain (`load`, `reset`)
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Command::Load { .. } | Command::Reset { .. })
+    }
+
+    /// Whether interactive mode should ask before running it: destructive
+    /// and not given `--force`
+    pub fn needs_confirmation(&self) -> bool {
+        match self {
+            Command::Load { force, .. } | Command::Reset { force } => !force,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<&str> for Command {
+    type Error = CliError;
+
+    /// Parses one interactive-mode line, split on whitespace
+    fn try_from(line: &str) -> Result<Self, Self::Error> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        Cli::parse_command(&args)
+    }
+}
+
 /// Parse command from arguments (skipping program name)
 pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
     if args.len() <= 1 {
@@ -936,3 +2637,578 @@ pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
     }
     Cli::parse_command(&args[1..])
 }
+
+/// Strips `--verbose` / `--quiet` from the arguments and returns the log level they select
+/// Info by default, Debug with `--verbose`, errors only with `--quiet`
+pub fn split_log_flags(args: &[String]) -> (log::LevelFilter, Vec<String>) {
+    let mut level = log::LevelFilter::Info;
+    let mut rest = Vec::with_capacity(args.len());
+
+    for arg in args {
+        match arg.as_str() {
+            "--verbose" => level = log::LevelFilter::Debug,
+            "--quiet" => level = log::LevelFilter::Error,
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    (level, rest)
+}
+
+/// Installs the `env_logger` backend; `RUST_LOG` still overrides the level
+pub fn init_logging(level: log::LevelFilter) {
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
+}
+
+/// One `mine` status line: time so far, the hashrate over it, and the
+/// expected time to a block at that rate
+fn mining_status(elapsed: Duration, hashes: u64, difficulty: u32) -> String {
+    let hashrate = hashes as f64 / elapsed.as_secs_f64().max(1e-9);
+    let eta = experiments::expected_mining_time(difficulty, hashrate)
+        .map_or_else(|| String::from("unknown"), experiments::format_duration);
+    format!(
+        "  Mining... {} elapsed, {}, ETA ~{}",
+        experiments::format_duration(elapsed),
+        experiments::format_hashrate(hashrate),
+        eta
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_policy_decides_when_to_mine() {
+        let policy = WatchPolicy { threshold: 3, interval: Duration::from_millis(500) };
+
+        // Nothing pending never mines, however long it has been
+        assert!(!policy.should_mine(0, Duration::from_secs(60)));
+        // Below the threshold, wait for the interval
+        assert!(!policy.should_mine(2, Duration::from_millis(100)));
+        assert!(policy.should_mine(1, Duration::from_millis(500)));
+        // At or over the threshold, mine right away
+        assert!(policy.should_mine(3, Duration::ZERO));
+        assert!(policy.should_mine(10, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_set_hashlen() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(Cli::parse_command(&args("set hashlen 8")).unwrap(), Command::SetHashLen { len: 8 });
+        assert!(Cli::parse_command(&args("set hashlen 0")).is_err());
+        assert!(Cli::parse_command(&args("set hashlen")).is_err());
+        assert!(Cli::parse_command(&args("set colour red")).is_err());
+
+        let mut cli = Cli::new();
+        let tip = cli.blockchain.tip_hash().to_string();
+        cli.execute_command(Command::SetHashLen { len: 8 }).unwrap();
+        let shown = cli.execute_command(Command::ShowChain { full: false, last_n: None, block: Some(String::from("0")) })
+            .unwrap()
+            .unwrap();
+        assert!(shown.contains(&format!("{}...", &tip[..8])));
+        assert!(!shown.contains(&tip[..9]));
+    }
+
+    #[test]
+    fn test_rejected_transaction_keeps_its_cause() {
+        let mut cli = Cli::new();
+        let add = |sender: &str, receiver: &str, memo: Option<String>| Command::AddTransaction {
+            sender: String::from(sender),
+            receiver: String::from(receiver),
+            amount: COIN,
+            memo,
+        };
+
+        let error = cli.execute_command(add("Alice", "Alice", None)).unwrap_err();
+        assert!(matches!(error, CliError::Transaction(TransactionError::SameParty)));
+        assert_eq!(error.exit_code(), 2);
+
+        let error = cli.execute_command(add("Alice", "Bob", Some("x".repeat(81)))).unwrap_err();
+        assert!(matches!(error, CliError::Transaction(TransactionError::MemoTooLong { len: 81, .. })));
+        assert_eq!(CliError::InvalidArgument(String::from("x")).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_step_and_rewind() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.blockchain.add_transaction(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+        let genesis = cli.blockchain.tip_hash().to_string();
+
+        cli.execute_command(Command::Step).unwrap();
+        assert_eq!(cli.blockchain.len(), 2);
+        assert_eq!(cli.blockchain.pending_transaction_count(), 0);
+
+        cli.execute_command(Command::Rewind).unwrap();
+        assert_eq!(cli.blockchain.tip_hash(), genesis);
+        assert_eq!(cli.blockchain.pending_transaction_count(), 1);
+
+        // A block mined outside `step` can't be rewound
+        cli.execute_command(Command::Step).unwrap();
+        cli.blockchain.mine_block();
+        assert!(cli.execute_command(Command::Rewind).is_err());
+        assert_eq!(cli.blockchain.len(), 3);
+    }
+
+    #[test]
+    fn test_chain_block_accepts_hash_prefix() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.blockchain.mine_block();
+        let hash = cli.blockchain.chain[1].hash.clone();
+
+        // A prefix starting with '0' is a hash, not an index
+        assert_eq!(cli.find_block(&hash).unwrap().index, 1);
+        assert_eq!(cli.find_block(&hash[..12]).unwrap().index, 1);
+        assert_eq!(cli.find_block("1").unwrap().index, 1);
+        assert_eq!(cli.find_block("0").unwrap().index, 0);
+        assert!(cli.find_block("7").is_err());
+        assert!(cli.find_block("zzz").is_err());
+    }
+
+    #[test]
+    fn test_validate_max_errors() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            Cli::parse_command(&args("validate --max-errors 2 --json")).unwrap(),
+            Command::ValidateChain { json: true, max_errors: Some(2) }
+        );
+        assert!(Cli::parse_command(&args("validate --max-errors 0")).is_err());
+        assert!(Cli::parse_command(&args("validate --max-errors")).is_err());
+
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        for _ in 0..3 {
+            cli.blockchain.mine_block();
+        }
+        for block in &mut cli.blockchain.chain[1..] {
+            block.hash = String::from("0000forged");
+        }
+
+        let output = cli.execute_command(Command::ValidateChain { json: false, max_errors: Some(1) })
+            .unwrap()
+            .unwrap();
+        assert!(output.contains("First 1 error(s)"));
+        assert_eq!(output.matches("\n  - ").count(), 1);
+    }
+
+    #[test]
+    fn test_parse_add_amounts() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        for amount in ["inf", "NaN", "-5"] {
+            let error = Cli::parse_command(&args(&format!("add Alice Bob {}", amount))).unwrap_err();
+            assert!(matches!(error, CliError::InvalidArgument(_)), "{} gave {:?}", amount, error);
+        }
+        assert_eq!(
+            Cli::parse_command(&args("add Alice Bob 1,000.50")).unwrap(),
+            Command::AddTransaction {
+                sender: String::from("Alice"),
+                receiver: String::from("Bob"),
+                amount: 1_000 * COIN + COIN / 2,
+                memo: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_alias_resolves_on_input_and_shows_on_output() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let address = "0x9f3a7c21e4b8d6f05a1c3e7b9d2f4a6c8e0b1d3f";
+        let mut cli = Cli::new();
+        let alias = Cli::parse_command(&args(&format!("alias alice {}", address))).unwrap();
+        cli.execute_command(alias).unwrap();
+
+        cli.execute_command(Cli::parse_command(&args("add alice Bob 5")).unwrap()).unwrap();
+        let pending = cli.blockchain.get_pending_transactions();
+        assert_eq!(pending[0].sender, address);
+        assert!(!serde_json::to_string(&cli.blockchain).unwrap().contains("alice"));
+
+        let shown = cli.execute_command(Command::ShowPending).unwrap().unwrap();
+        assert!(shown.contains("alice -> Bob : 5.00"), "{}", shown);
+        assert!(!shown.contains(address));
+    }
+
+    #[test]
+    fn test_audit_log_is_opt_in() {
+        let mut cli = Cli::new();
+        assert!(!cli.blockchain.is_audited());
+        assert_eq!(Cli::parse_command(&[String::from("audit"), String::from("on")]).unwrap(), Command::SetAudit { on: true });
+        assert!(Cli::parse_command(&[String::from("audit"), String::from("maybe")]).is_err());
+
+        cli.execute_command(Command::SetAudit { on: true }).unwrap();
+        cli.blockchain.add_transaction(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+        cli.execute_command(Command::Reset { force: true }).unwrap();
+        assert!(cli.blockchain.is_audited() && cli.blockchain.audit_log().is_empty());
+    }
+
+    #[test]
+    fn test_alias_cannot_redirect_a_known_address() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let mut cli = Cli::new();
+        cli.blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 5 * COIN).unwrap();
+
+        let alias = Cli::parse_command(&args("alias Bob Mallory")).unwrap();
+        assert!(matches!(cli.execute_command(alias), Err(CliError::InvalidArgument(_))));
+        let miner = Cli::parse_command(&args(&format!("alias {} Mallory", cli.miner_address))).unwrap();
+        assert!(cli.execute_command(miner).is_err());
+
+        cli.execute_command(Cli::parse_command(&args("add Alice Bob 1")).unwrap()).unwrap();
+        assert_eq!(cli.blockchain.get_pending_transactions()[1].receiver, "Bob");
+    }
+
+    #[test]
+    fn test_self_check_finds_first_broken_block_of_loaded_chain() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        for _ in 0..4 {
+            cli.blockchain.mine_block();
+        }
+
+        // Relink block 2 and rehash it: it loads with --allow-invalid, but no longer chains
+        let mut tampered = cli.blockchain.clone();
+        tampered.chain[2].previous_hash = "f".repeat(64);
+        tampered.chain[2].hash = tampered.chain[2].calculate_hash();
+        let path = std::env::temp_dir().join(format!("rustchain-selfcheck-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+        let loaded = cli.execute_command(Command::Load { path: path.display().to_string(), state: false, force: false, allow_invalid: true });
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.unwrap().unwrap().contains("block #2 is the first broken block"));
+
+        let report = cli.self_check().unwrap();
+        assert_eq!(report.first_invalid, 2);
+        assert_eq!(report.repair_cost.unwrap().blocks_to_rewrite, 3);
+
+        cli.execute_command(Cli::parse_command(&[String::from("repair")]).unwrap()).unwrap();
+        assert!(cli.self_check().is_none());
+        assert!(cli.blockchain.is_valid());
+    }
+
+    #[test]
+    fn test_invalid_chain_is_refused_unless_asked_for() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.blockchain.mine_block();
+        let mut tampered = cli.blockchain.clone();
+        tampered.mine_block();
+        tampered.mine_block();
+        tampered.chain[2].previous_hash = "f".repeat(64);
+        tampered.chain[2].hash = tampered.chain[2].calculate_hash();
+        let path = std::env::temp_dir().join(format!("rustchain-refused-{}.json", std::process::id()));
+        let path = path.display().to_string();
+        std::fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+
+        // Refused by default: the current chain stays active and nothing offers a repair
+        let refused = cli.execute_command(Command::Load { path: path.clone(), state: false, force: true, allow_invalid: false });
+        assert!(!refused.unwrap_err().to_string().contains("repair"));
+        assert_eq!(cli.blockchain.height(), 1);
+        assert!(cli.self_check().is_none());
+
+        let kept = cli.execute_command(Command::Load { path: path.clone(), state: false, force: true, allow_invalid: true });
+        std::fs::remove_file(&path).unwrap();
+        assert!(kept.unwrap().unwrap().contains("Suggested fix: 'repair'"));
+        assert_eq!(cli.blockchain.height(), 3);
+    }
+
+    #[test]
+    fn test_load_reports_broken_link_as_validation_error() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        for _ in 0..3 {
+            cli.blockchain.mine_block();
+        }
+        let mut tampered = cli.blockchain.clone();
+        tampered.chain[2].previous_hash = "f".repeat(64);
+        tampered.chain[2].hash = tampered.chain[2].calculate_hash();
+        let path = std::env::temp_dir().join(format!("rustchain-brokenlink-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&tampered).unwrap()).unwrap();
+
+        let error = cli.execute_command(Command::Load { path: path.display().to_string(), state: false, force: true, allow_invalid: false });
+        std::fs::remove_file(&path).unwrap();
+        let error = error.unwrap_err();
+        assert!(matches!(error, CliError::Validation(ValidationError::BrokenLink { index: 2, .. })), "{:?}", error);
+        let message = error.to_string();
+        assert!(message.contains("Block #2") && message.contains("Broken chain link"), "{}", message);
+
+        // The current chain is left alone
+        assert_eq!(cli.blockchain.height(), 3);
+    }
+
+    #[test]
+    fn test_load_refuses_chain_with_other_consensus_rules() {
+        let mut cli = Cli::new();
+        let mut generous = Blockchain::new();
+        generous.block_reward = 1_000 * COIN;
+        generous.min_amount = 0;
+        let path = std::env::temp_dir().join(format!("rustchain-rules-{}.json", std::process::id()));
+        let path = path.display().to_string();
+        std::fs::write(&path, serde_json::to_string(&generous).unwrap()).unwrap();
+
+        let error = cli.execute_command(Command::Load { path: path.clone(), state: false, force: true, allow_invalid: true });
+        let message = error.unwrap_err().to_string();
+        assert!(message.contains("min_amount, block_reward"), "{}", message);
+        assert_eq!(cli.blockchain.block_reward, crate::blockchain::DEFAULT_BLOCK_REWARD);
+
+        // Once the node runs the same rules, the file loads
+        cli.blockchain.block_reward = 1_000 * COIN;
+        cli.blockchain.min_amount = 0;
+        let loaded = cli.execute_command(Command::Load { path: path.clone(), state: false, force: false, allow_invalid: false });
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_ok(), "{:?}", loaded);
+    }
+
+    #[test]
+    fn test_diff_files_shows_one_added_block() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+
+        let dir = std::env::temp_dir();
+        let path_a = dir.join(format!("rustchain-diff-a-{}.json", std::process::id())).display().to_string();
+        let path_b = dir.join(format!("rustchain-diff-b-{}.json", std::process::id())).display().to_string();
+        cli.execute_command(Command::Save { path: path_a.clone(), state: false }).unwrap();
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+        cli.execute_command(Command::Save { path: path_b.clone(), state: false }).unwrap();
+
+        let command = Cli::parse_command(&[String::from("diff"), path_a.clone(), path_b.clone()]).unwrap();
+        let shown = cli.execute_command(command).unwrap().unwrap();
+        std::fs::remove_file(&path_a).unwrap();
+        std::fs::remove_file(&path_b).unwrap();
+
+        let tip = cli.blockchain.get_block(2).unwrap();
+        let changes: Vec<&str> = shown.lines().filter(|line| line.starts_with(['+', '-', '~'])).skip(2).collect();
+        assert_eq!(changes, vec![format!("+ #2 {}", cli.visualizer.short(&tip.hash))]);
+        assert!(shown.ends_with("1 added, 0 removed, 0 changed"), "{}", shown);
+
+        // `diff <N>` still sets the difficulty
+        assert_eq!(Cli::parse_command(&[String::from("diff"), String::from("3")]).unwrap(), Command::SetDifficulty { difficulty: 3 });
+    }
+
+    #[test]
+    fn test_autosave_writes_the_chain_after_each_mined_block() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        let path = std::env::temp_dir().join(format!("rustchain-autosave-{}.json", std::process::id())).display().to_string();
+
+        let command = Cli::parse_command(&args(&format!("autosave {}", path))).unwrap();
+        assert_eq!(command, Command::AutoSave { path: Some(path.clone()) });
+        cli.execute_command(command).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        cli.execute_command(Command::AddTransaction {
+            sender: String::from("Alice"),
+            receiver: String::from("Bob"),
+            amount: COIN,
+            memo: None,
+        }).unwrap();
+        assert!(std::fs::metadata(&path).is_err(), "only a new block triggers a save");
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+
+        let saved = Cli::read_chain_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let hashes = |chain: &Blockchain| chain.blocks().map(|block| block.hash.clone()).collect::<Vec<_>>();
+        assert_eq!(hashes(&saved), hashes(&cli.blockchain));
+        assert_eq!(saved.height(), 1);
+
+        // Turned off, mining writes nothing; a path that can't be written is refused up front
+        cli.execute_command(Cli::parse_command(&args("autosave off")).unwrap()).unwrap();
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+        assert!(std::fs::metadata(&path).is_err());
+        let unwritable = std::env::temp_dir().display().to_string();
+        assert!(cli.execute_command(Command::AutoSave { path: Some(unwritable) }).is_err());
+        assert_eq!(cli.auto_save_path, None);
+    }
+
+    #[test]
+    fn test_add_batch_queues_all_or_none() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let mut cli = Cli::new();
+
+        let batch = Cli::parse_command(&args("add Alice Bob 10 Bob Carol 5 Carol Dave 2")).unwrap();
+        let shown = cli.execute_command(batch).unwrap().unwrap();
+        assert!(shown.starts_with("Queued 3 transactions"), "{}", shown);
+        let receivers: Vec<_> = cli.blockchain.get_pending_transactions().iter().map(|tx| tx.receiver.as_str()).collect();
+        assert_eq!(receivers, ["Bob", "Carol", "Dave"]);
+
+        // The second transfer is refused by the mempool, so the first is rolled back
+        cli.execute_command(Command::BlockAddress { address: String::from("Mallory") }).unwrap();
+        let batch = Cli::parse_command(&args("add Erin Frank 1 Mallory Carol 5")).unwrap();
+        let error = cli.execute_command(batch).unwrap_err();
+        assert!(matches!(error, CliError::Transaction(TransactionError::BlockedAddress { .. })));
+        assert_eq!(cli.blockchain.pending_transaction_count(), 3);
+        assert_eq!(cli.blockchain.next_nonce("Erin"), 1);
+    }
+
+    #[test]
+    fn test_parse_add_batch_arity() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            Cli::parse_command(&args("add Alice Bob 10 Bob Carol 5")).unwrap(),
+            Command::AddBatch { transfers: vec![
+                (String::from("Alice"), String::from("Bob"), 10 * COIN),
+                (String::from("Bob"), String::from("Carol"), 5 * COIN),
+            ] }
+        );
+        let error = Cli::parse_command(&args("add Alice Bob 10 Bob Carol")).unwrap_err();
+        assert!(error.to_string().contains("triples; got 5 argument(s)"), "{}", error);
+        assert!(Cli::parse_command(&args("add Alice Bob 10 Bob Carol 5 --memo rent")).is_err());
+        assert!(Cli::parse_command(&args("add Alice Bob 10 --memo")).is_err());
+    }
+
+    #[test]
+    fn test_supply_reports_issuance_and_halving() {
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.blockchain.halving_interval = 3;
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+
+        let shown = cli.execute_command(Cli::parse_command(&[String::from("supply")]).unwrap()).unwrap().unwrap();
+        assert!(shown.contains("Total issued:           50.00 coins over 1 block(s)"), "{}", shown);
+        assert!(shown.contains("Current block reward:   50.00"), "{}", shown);
+        assert!(shown.contains("in 1 block(s), at block #3"), "{}", shown);
+    }
+
+    #[test]
+    fn test_is_destructive_per_command() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let parse = |line: &str| Cli::parse_command(&args(line)).unwrap();
+        assert!(parse("load chain.json").is_destructive());
+        assert!(parse("load --state --force session.json").is_destructive());
+        assert!(parse("reset").is_destructive());
+        assert!(!parse("save --state session.json").is_destructive());
+        assert!(!parse("mine").is_destructive());
+        assert!(!parse("repair").is_destructive());
+
+        assert!(parse("load chain.json").needs_confirmation());
+        assert!(!parse("load --force chain.json").needs_confirmation());
+        // Accepting an invalid chain is its own flag; it still asks first
+        assert!(parse("load --allow-invalid chain.json").needs_confirmation());
+        assert_eq!(
+            parse("load --force chain.json"),
+            Command::Load { path: String::from("chain.json"), state: false, force: true, allow_invalid: false }
+        );
+        assert_eq!(parse("reset --force"), Command::Reset { force: true });
+        assert!(Cli::parse_command(&args("save --force chain.json")).is_err());
+    }
+
+    #[test]
+    fn test_force_skips_confirmation() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let mut cli = Cli::new();
+        cli.blockchain.set_difficulty(1);
+        cli.execute_command(Command::MineBlock { all: false }).unwrap();
+
+        let reset = Cli::parse_command(&args("reset")).unwrap();
+        assert!(!cli.confirm(&reset, &mut &b""[..]));
+        assert!(!cli.confirm(&reset, &mut &b"n\n"[..]));
+        assert!(cli.confirm(&reset, &mut &b"y\n"[..]));
+
+        let forced = Cli::parse_command(&args("reset --force")).unwrap();
+        assert!(cli.confirm(&forced, &mut &b""[..]));
+        cli.execute_command(forced).unwrap();
+        assert_eq!(cli.blockchain.height(), 0);
+
+        // Nothing to lose on a fresh chain, so no prompt either
+        assert!(cli.confirm(&reset, &mut &b""[..]));
+    }
+
+    #[test]
+    fn test_parse_chain_pages() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            Cli::parse_command(&args("chain --page 2 --per-page 5")).unwrap(),
+            Command::ShowChainPage { page: 2, per_page: 5 }
+        );
+        assert_eq!(
+            Cli::parse_command(&args("chain --per-page 20")).unwrap(),
+            Command::ShowChainPage { page: 1, per_page: 20 }
+        );
+        assert!(Cli::parse_command(&args("chain --page 0")).is_err());
+        assert!(Cli::parse_command(&args("chain --page")).is_err());
+        assert!(Cli::parse_command(&args("chain --page 2 --full")).is_err());
+
+        let mut cli = Cli::new();
+        let shown = cli.execute_command(Command::ShowChainPage { page: 1, per_page: 10 }).unwrap().unwrap();
+        assert!(shown.ends_with("Page 1/1 (blocks #0-#0)"), "{}", shown);
+        assert!(cli.execute_command(Command::ShowChainPage { page: 2, per_page: 10 }).is_err());
+    }
+
+    #[test]
+    fn test_parse_mine_dry_run() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(Cli::parse_command(&args("mine --dry-run")).unwrap(), Command::PreviewBlock);
+        assert_eq!(Cli::parse_command(&args("m --all")).unwrap(), Command::MineBlock { all: true });
+    }
+
+    #[test]
+    fn test_command_try_from_line() {
+        assert_eq!(Command::try_from("  mine   --all ").unwrap(), Command::MineBlock { all: true });
+        assert!(matches!(Command::try_from("frobnicate"), Err(CliError::InvalidCommand(cmd)) if cmd == "frobnicate"));
+        assert!(matches!(Command::try_from("   "), Err(CliError::InvalidCommand(cmd)) if cmd.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_autodiff() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(Cli::parse_command(&args("autodiff 500")).unwrap(), Command::AutoDifficulty { target_ms: 500 });
+        assert!(Cli::parse_command(&args("autodiff")).is_err());
+        assert!(Cli::parse_command(&args("autodiff 0")).is_err());
+        assert!(Cli::parse_command(&args("autodiff 600000")).is_err());
+    }
+
+    #[test]
+    fn test_parse_save_and_load_state() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            Cli::parse_command(&args("save chain.json")).unwrap(),
+            Command::Save { path: String::from("chain.json"), state: false }
+        );
+        assert_eq!(
+            Cli::parse_command(&args("load --state session.json")).unwrap(),
+            Command::Load { path: String::from("session.json"), state: true, force: false, allow_invalid: false }
+        );
+        assert!(Cli::parse_command(&args("save --state")).is_err());
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(Cli::parse_command(&args("serve")).unwrap(), Command::Serve { port: DEFAULT_SERVE_PORT });
+        assert_eq!(Cli::parse_command(&args("serve --port 9000")).unwrap(), Command::Serve { port: 9000 });
+        assert!(Cli::parse_command(&args("serve --port 70000")).is_err());
+        assert!(Cli::parse_command(&args("serve --port")).is_err());
+        assert!(Cli::parse_command(&args("serve --host x")).is_err());
+    }
+
+    #[test]
+    fn test_parse_watch_flags() {
+        let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            Cli::parse_command(&args("watch")).unwrap(),
+            Command::Watch { threshold: 3, interval_ms: 5_000 }
+        );
+        assert_eq!(
+            Cli::parse_command(&args("watch --interval 250 --threshold 1")).unwrap(),
+            Command::Watch { threshold: 1, interval_ms: 250 }
+        );
+        assert!(Cli::parse_command(&args("watch --threshold 0")).is_err());
+        assert!(Cli::parse_command(&args("watch --threshold")).is_err());
+    }
+
+    #[test]
+    fn test_mining_status_line() {
+        // 2,048 hashes in 2s is 1,024 H/s; 16^4 hashes at that rate take 64s
+        assert_eq!(
+            mining_status(Duration::from_secs(2), 2048, 4),
+            "  Mining... 2.00 seconds elapsed, 1.02 kH/s, ETA ~1.07 minutes"
+        );
+        assert!(mining_status(Duration::from_secs(1), 0, 4).ends_with("ETA ~unknown"));
+    }
+}
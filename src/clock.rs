@@ -0,0 +1,97 @@
+//! Time source for block timestamps
+//!
+//! Blocks are stamped with milliseconds since the Unix epoch. By default that
+//! comes from the system clock; tests and experiments can swap in a fake,
+//! monotonic clock so that mined hashes are reproducible run to run.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Reads a timestamp; `true` advances a clock that moves on each read
+type ReadFn = dyn Fn(bool) -> u128 + Send + Sync;
+
+/// Shared, cloneable source of millisecond timestamps
+#[derive(Clone)]
+pub struct Clock(Arc<ReadFn>);
+
+impl Clock {
+    /// Wall-clock time in milliseconds since the Unix epoch
+    pub fn system() -> Self {
+        Clock::from_fn(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_millis()
+        })
+    }
+
+    /// Wraps any timestamp function; `peek` calls it too
+    pub fn from_fn(clock: impl Fn() -> u128 + Send + Sync + 'static) -> Self {
+        Clock(Arc::new(move |_| clock()))
+    }
+
+    /// Fake clock returning `start`, `start + step`, `start + 2*step`, ...
+    /// Clones share the same counter, so it stays monotonic across them
+    pub fn stepping(start: u128, step: u128) -> Self {
+        let next = Mutex::new(start);
+        Clock(Arc::new(move |advance| {
+            let mut next = next.lock().expect("clock mutex poisoned");
+            let now = *next;
+            if advance {
+                *next += step;
+            }
+            now
+        }))
+    }
+
+    /// Reads the current timestamp
+    pub fn now(&self) -> u128 {
+        (self.0)(true)
+    }
+
+    /// The timestamp `now` would return, without moving a stepping clock on
+    pub fn peek(&self) -> u128 {
+        (self.0)(false)
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::system()
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stepping_clock_is_monotonic() {
+        let clock = Clock::stepping(1_000, 250);
+        assert_eq!(clock.now(), 1_000);
+        assert_eq!(clock.now(), 1_250);
+
+        // Clones share the counter
+        let clone = clock.clone();
+        assert_eq!(clone.now(), 1_500);
+        assert_eq!(clock.now(), 1_750);
+
+        // Peeking doesn't advance it
+        assert_eq!(clock.peek(), 2_000);
+        assert_eq!(clock.now(), 2_000);
+    }
+
+    #[test]
+    fn test_from_fn_clock() {
+        let clock = Clock::from_fn(|| 42);
+        assert_eq!(clock.now(), 42);
+        assert_eq!(clock.now(), 42);
+    }
+}
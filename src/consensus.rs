@@ -0,0 +1,175 @@
+//! Consensus rules: who gets to add the next block
+//!
+//! The chain is proof-of-work by default. Proof-of-stake mode is here for
+//! contrast: there is no nonce search, and the producer of each block is
+//! picked from a stake table, weighted by stake and seeded by the previous
+//! block's hash, so every node computes the same choice.
+
+use crate::crypto::calculate_hash;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Highest proof-of-work difficulty (leading hex zeros) a block may claim
+/// Half the 64-digit digest: far beyond anything this chain can mine, but a
+/// block claiming more is rejected rather than validated against an absurd target
+pub const MAX_DIFFICULTY: u32 = 32;
+
+/// Hex digit a proof-of-work hash must start with, `difficulty` times over
+pub const DEFAULT_POW_PREFIX: char = '0';
+
+/// Checks `prefix` can start a hash: one lowercase hex digit, as digests are printed
+pub fn check_pow_prefix(prefix: char) -> Result<(), String> {
+    if prefix.is_ascii_digit() || ('a'..='f').contains(&prefix) {
+        Ok(())
+    } else {
+        Err(format!("Proof-of-work prefix must be a lowercase hex digit, got '{}'", prefix))
+    }
+}
+
+/// scrypt cost for `PowAlgorithm::ScryptLike`: N = 2^10 rounds of r = 8,
+/// so each hash works through 128 * r * N = 1 MiB of memory
+#[cfg(feature = "scrypt")]
+pub const SCRYPT_LOG_N: u8 = 10;
+#[cfg(feature = "scrypt")]
+pub const SCRYPT_R: u32 = 8;
+
+/// Hash function whose digest proof-of-work is checked against
+/// The block hash that links the chain is always SHA-256 of the header; the
+/// algorithm only decides what digest of it must meet the target, much as
+/// Litecoin identifies blocks by SHA-256d but mines them with scrypt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowAlgorithm {
+    /// The block hash itself
+    #[default]
+    Sha256,
+    /// SHA-256 of the block hash, as in Bitcoin
+    DoubleSha256,
+    /// scrypt of the block hash, a memory-hard KDF: every try fills a 1 MiB
+    /// buffer, so memory bandwidth rather than raw hashing speed bounds the
+    /// hashrate (`scrypt` feature)
+    #[cfg(feature = "scrypt")]
+    ScryptLike,
+}
+
+impl PowAlgorithm {
+    /// The digest to check against the target for a block hashing to `block_hash`
+    pub fn pow_hash<'a>(&self, block_hash: &'a str) -> Cow<'a, str> {
+        match self {
+            PowAlgorithm::Sha256 => Cow::Borrowed(block_hash),
+            PowAlgorithm::DoubleSha256 => Cow::Owned(calculate_hash(block_hash)),
+            #[cfg(feature = "scrypt")]
+            PowAlgorithm::ScryptLike => {
+                let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, 1, 32).expect("valid scrypt parameters");
+                let mut output = [0u8; 32];
+                scrypt::scrypt(block_hash.as_bytes(), b"rustchain", &params, &mut output)
+                    .expect("32-byte output is a valid length");
+                Cow::Owned(hex::encode(output))
+            }
+        }
+    }
+}
+
+/// How blocks are sealed and which producer a valid block must name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConsensusMode {
+    /// Blocks carry a nonce whose hash meets the difficulty
+    #[default]
+    ProofOfWork,
+    /// Blocks are produced by a stake-weighted validator: (address, stake)
+    ProofOfStake { validators: Vec<(String, u64)> },
+}
+
+impl ConsensusMode {
+    /// Proof-of-stake over `validators`; at least one must have a non-zero stake
+    pub fn proof_of_stake(validators: Vec<(String, u64)>) -> Result<Self, String> {
+        if validators.iter().all(|(_, stake)| *stake == 0) {
+            return Err(String::from("Proof-of-stake needs at least one validator with stake"));
+        }
+        Ok(ConsensusMode::ProofOfStake { validators })
+    }
+
+    /// Whether blocks are sealed by a nonce search
+    pub fn is_proof_of_work(&self) -> bool {
+        matches!(self, ConsensusMode::ProofOfWork)
+    }
+
+    /// The producer the block after `previous_hash` must name (None under proof-of-work)
+    pub fn expected_producer(&self, previous_hash: &str) -> Option<&str> {
+        match self {
+            ConsensusMode::ProofOfWork => None,
+            ConsensusMode::ProofOfStake { validators } => select_producer(validators, previous_hash),
+        }
+    }
+}
+
+/// Picks a validator with probability proportional to its stake
+/// The draw is seeded by `previous_hash`, so it is the same on every node.
+/// Returns None if no validator has any stake
+pub fn select_producer<'a>(validators: &'a [(String, u64)], previous_hash: &str) -> Option<&'a str> {
+    let total: u128 = validators.iter().map(|(_, stake)| *stake as u128).sum();
+    if total == 0 {
+        return None;
+    }
+
+    // Rehash so the seed is uniform even for short or non-hex previous hashes
+    let seed = u64::from_str_radix(&calculate_hash(previous_hash)[..16], 16)
+        .expect("SHA-256 hex digest");
+    let mut ticket = seed as u128 % total;
+
+    for (address, stake) in validators {
+        let stake = *stake as u128;
+        if ticket < stake {
+            return Some(address);
+        }
+        ticket -= stake;
+    }
+    unreachable!("ticket is below the total stake")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stake_table() -> Vec<(String, u64)> {
+        vec![
+            (String::from("Alice"), 60),
+            (String::from("Bob"), 30),
+            (String::from("Carol"), 10),
+            (String::from("Dave"), 0),
+        ]
+    }
+
+    #[test]
+    fn test_selection_is_deterministic() {
+        let validators = stake_table();
+        let previous_hash = calculate_hash("some block");
+
+        let first = select_producer(&validators, &previous_hash);
+        assert!(first.is_some());
+        for _ in 0..10 {
+            assert_eq!(select_producer(&validators, &previous_hash), first);
+        }
+    }
+
+    #[test]
+    fn test_selection_is_weighted_by_stake() {
+        let validators = stake_table();
+        let mut counts = std::collections::HashMap::new();
+        for i in 0..1_000 {
+            let producer = select_producer(&validators, &calculate_hash(&i.to_string())).unwrap();
+            *counts.entry(producer).or_insert(0) += 1;
+        }
+
+        assert!(counts["Alice"] > counts["Bob"]);
+        assert!(counts["Bob"] > counts["Carol"]);
+        assert!(!counts.contains_key("Dave"));
+    }
+
+    #[test]
+    fn test_proof_of_stake_needs_stake() {
+        assert!(ConsensusMode::proof_of_stake(Vec::new()).is_err());
+        assert!(ConsensusMode::proof_of_stake(vec![(String::from("Dave"), 0)]).is_err());
+        assert!(ConsensusMode::proof_of_stake(stake_table()).is_ok());
+        assert_eq!(ConsensusMode::ProofOfWork.expected_producer("abc"), None);
+    }
+}
@@ -1,5 +1,4 @@
 use sha2::{Digest, Sha256};
-use hex;
 
 /// Calculates SHA-256 hash of the given input string
 /// Returns hexadecimal encoded hash string
@@ -10,6 +9,18 @@ pub fn calculate_hash(input: &str) -> String {
     hex::encode(result)
 }
 
+/// Hash characters shown when nothing else is configured
+pub const DEFAULT_HASH_LEN: usize = 16;
+
+/// Returns at most the first `n` characters of a hash for display
+/// Never panics on short or tampered hashes (e.g. "wrong_hash" or multi-byte text)
+pub fn short_hash(hash: &str, n: usize) -> &str {
+    match hash.char_indices().nth(n) {
+        Some((end, _)) => &hash[..end],
+        None => hash,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +46,13 @@ mod tests {
         let hash2 = calculate_hash("test data.");
         assert_ne!(hash1, hash2);
     }
+
+    #[test]
+    fn test_short_hash_clamps() {
+        assert_eq!(short_hash("abcdef", 4), "abcd");
+        assert_eq!(short_hash("wrong_hash", 32), "wrong_hash");
+        assert_eq!(short_hash("", 12), "");
+        // Multi-byte characters are never split
+        assert_eq!(short_hash("ééé", 2), "éé");
+    }
 }
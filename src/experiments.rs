@@ -4,9 +4,13 @@
 //! difficulty relationships, and the computational cost of various attacks.
 
 use crate::blockchain::Blockchain;
-use crate::transaction::Transaction;
+use crate::clock::Clock;
+use crate::crypto::{calculate_hash, short_hash, DEFAULT_HASH_LEN};
+use crate::transaction::COIN;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::thread;
 
 /// Result of a mining experiment
 #[derive(Debug, Clone)]
@@ -59,30 +63,164 @@ pub struct DifficultyComparisonResult {
     pub security_increase_factor: f64,
 }
 
+/// One difficulty level of a `DifficultyComparisonResult`, as exported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DifficultyRow {
+    difficulty: u32,
+    avg_time_ms: f64,
+    avg_nonce: u64,
+}
+
+impl DifficultyComparisonResult {
+    /// One row per difficulty level tested
+    fn rows(&self) -> Vec<DifficultyRow> {
+        self.difficulties
+            .iter()
+            .zip(&self.avg_times)
+            .zip(&self.avg_nonces)
+            .map(|((&difficulty, avg_time), &avg_nonce)| DifficultyRow {
+                difficulty,
+                avg_time_ms: avg_time.as_secs_f64() * 1000.0,
+                avg_nonce,
+            })
+            .collect()
+    }
+
+    /// CSV with a `difficulty,avg_time_ms,avg_nonce` header; just the header if empty
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("difficulty,avg_time_ms,avg_nonce\n");
+        for row in self.rows() {
+            csv.push_str(&format!("{},{:.3},{}\n", row.difficulty, row.avg_time_ms, row.avg_nonce));
+        }
+        csv
+    }
+
+    /// Pretty-printed JSON array with the same fields as the CSV; `[]` if empty
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.rows()).expect("difficulty rows are always serializable")
+    }
+}
+
+/// Result of a block propagation experiment
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropagationResult {
+    /// Number of competing miners
+    pub miners: usize,
+    /// Delay before a block reaches the other miners
+    pub latency_ms: u64,
+    /// Blocks found by all miners together
+    pub blocks_found: usize,
+    /// Blocks that ended up off the winning chain
+    pub orphaned: usize,
+    /// Height of the winning chain
+    pub final_height: usize,
+    /// Orphaned blocks as a fraction of blocks found
+    pub orphan_rate: f64,
+}
+
+/// Average time between blocks across the whole simulated network
+pub const PROPAGATION_BLOCK_INTERVAL_MS: u64 = 10_000;
+
+/// Result of a simulated 51% attack, over several races
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiftyOnePercentResult {
+    /// Confirmations the honest chain reaches before the attacker publishes
+    pub honest_blocks: usize,
+    /// Attacker's share of the total hashrate
+    pub attacker_fraction: f64,
+    /// Races run
+    pub trials: usize,
+    /// Races in which the attacker's chain replaced the honest one
+    pub successes: usize,
+    /// Successes as a fraction of trials
+    pub success_rate: f64,
+}
+
+/// Races run by `experiment_fifty_one_percent`
+pub const FIFTY_ONE_PERCENT_TRIALS: usize = 20;
+
+/// CSV of `blockchain`'s difficulty and block times, one row per block
+/// Header `index,difficulty,block_time_ms`; genesis has no block time
+pub fn chain_history_csv(blockchain: &Blockchain) -> String {
+    let block_times: std::collections::HashMap<u64, u128> = blockchain.block_time_history().into_iter().collect();
+    let mut csv = String::from("index,difficulty,block_time_ms\n");
+    for (index, difficulty) in blockchain.difficulty_history() {
+        let block_time = block_times.get(&index).map(u128::to_string).unwrap_or_default();
+        csv.push_str(&format!("{},{},{}\n", index, difficulty, block_time));
+    }
+    csv
+}
+
+/// Difficulty of the throwaway blocks mined to measure hashrate
+pub const HASHRATE_SAMPLE_DIFFICULTY: u32 = 3;
+
+/// Number of throwaway blocks mined to measure hashrate
+pub const HASHRATE_SAMPLES: usize = 5;
+
+/// Electricity price assumed by the cost estimates, in dollars per kWh
+pub const DEFAULT_ELECTRICITY_RATE_PER_KWH: f64 = 0.10;
+
+/// Power draw assumed by the cost estimates, in watts
+pub const DEFAULT_POWER_WATTS: f64 = 1000.0;
+
 /// Security experiment runner
 pub struct SecurityExperiments {
     /// Test blockchain for experiments
     blockchain: Option<Blockchain>,
+    /// Timestamp source for every chain the experiments build
+    clock: Clock,
+    /// Measured hashrate (hashes/second) used for cost estimates
+    hashrate: Option<f64>,
 }
 
 impl SecurityExperiments {
     /// Create a new security experiment runner
     pub fn new() -> Self {
+        Self::with_clock(Clock::system())
+    }
+
+    /// Create an experiment runner whose chains are stamped by `clock`
+    /// Use `Clock::stepping` to make mined hashes reproducible across runs
+    pub fn with_clock(clock: Clock) -> Self {
         SecurityExperiments {
             blockchain: None,
+            clock,
+            hashrate: None,
+        }
+    }
+
+    /// Use an already-measured hashrate for cost estimates
+    pub fn set_hashrate(&mut self, hashes_per_second: f64) {
+        self.hashrate = Some(hashes_per_second);
+    }
+
+    /// This machine's hashrate, measured on first use
+    pub fn hashrate(&mut self) -> f64 {
+        if let Some(hashrate) = self.hashrate {
+            return hashrate;
         }
+
+        log::info!("Measuring this machine's hashrate...");
+        let hashrate = self.new_chain().estimate_hashrate(HASHRATE_SAMPLE_DIFFICULTY, HASHRATE_SAMPLES);
+        self.hashrate = Some(hashrate);
+        hashrate
+    }
+
+    /// Create an empty chain that uses the experiments' clock
+    fn new_chain(&self) -> Blockchain {
+        Blockchain::with_clock(self.clock.clone())
     }
 
     /// Create a test blockchain for experiments
     pub fn create_test_blockchain(&mut self, difficulty: u32, blocks: usize) -> &Blockchain {
-        let mut blockchain = Blockchain::new();
+        let mut blockchain = self.new_chain();
         blockchain.set_difficulty(difficulty);
 
         for i in 0..blocks {
             blockchain.add_transaction(
                 format!("User{}", i),
                 format!("User{}", i + 1),
-                10.0,
+                10 * COIN,
             ).unwrap();
             blockchain.mine_block();
         }
@@ -110,22 +248,21 @@ impl SecurityExperiments {
             let mut total_nonce = 0u64;
             let mut total_time = Duration::from_secs(0);
 
-            println!("Testing difficulty {}...", difficulty);
+            log::info!("Testing difficulty {}...", difficulty);
 
             for block_num in 0..blocks_per_difficulty {
-                let mut blockchain = Blockchain::new();
+                let mut blockchain = self.new_chain();
                 blockchain.set_difficulty(difficulty);
-                blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10.0).unwrap();
+                blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
 
                 let start = Instant::now();
-                blockchain.mine_block();
+                let block = blockchain.mine_block();
                 let duration = start.elapsed();
 
-                let block = blockchain.get_latest_block();
                 total_nonce += block.nonce;
                 total_time += duration;
 
-                println!("  Block {}: {}ms, nonce: {}",
+                log::debug!("  Block {}: {}ms, nonce: {}",
                     block_num + 1,
                     duration.as_millis(),
                     block.nonce
@@ -186,34 +323,26 @@ impl SecurityExperiments {
         println!("Parameters:");
         println!("  Blocks to rewrite:      {}", blocks_to_rewrite);
         println!("  Difficulty:             {} leading zeros", difficulty);
-        println!("  Attacker hashrate:      {} hashes/second", hashrate_hashes_per_second);
+        println!("  Attacker hashrate:      {}", format_hashrate(hashrate_hashes_per_second as f64));
         println!("  Electricity cost:       ${}/kWh", electricity_rate_per_kwh);
         println!("  Power consumption:      {} watts\n", power_consumption_watts);
 
-        // Estimate hashes needed per block
-        // On average, need to try 16^difficulty hashes
-        let estimated_hashes_per_block = 16_u64.pow(difficulty);
-
-        // For safety margin, multiply by 2 (could get lucky or unlucky)
-        let estimated_hashes_per_block = estimated_hashes_per_block * 2;
+        let result = estimate_rewrite_cost(
+            blocks_to_rewrite,
+            difficulty,
+            hashrate_hashes_per_second,
+            electricity_rate_per_kwh,
+            power_consumption_watts,
+        );
 
         println!("Calculations:");
-        println!("  Estimated hashes/block:  {}", format_number(estimated_hashes_per_block));
-        println!("  Total hashes needed:     {}", format_number(estimated_hashes_per_block * blocks_to_rewrite as u64));
-
-        // Calculate time
-        let total_hashes = estimated_hashes_per_block * blocks_to_rewrite as u64;
-        let estimated_seconds = total_hashes as f64 / hashrate_hashes_per_second as f64;
-        let estimated_time = Duration::from_secs_f64(estimated_seconds);
-
-        println!("  Estimated time:         {}", format_duration(estimated_time));
-
-        // Calculate electricity cost
-        let kilowatt_hours = (estimated_seconds / 3600.0) * (power_consumption_watts / 1000.0);
-        let estimated_cost = kilowatt_hours * electricity_rate_per_kwh;
+        println!("  Estimated hashes/block:  {}", format_number(result.estimated_hashes_per_block));
+        println!("  Total hashes needed:     {}", format_number(result.total_hashes));
+        println!("  Estimated time:         {}", format_duration(result.estimated_time));
 
+        let kilowatt_hours = (result.estimated_time.as_secs_f64() / 3600.0) * (power_consumption_watts / 1000.0);
         println!("  Energy consumption:     {:.2} kWh", kilowatt_hours);
-        println!("  Estimated cost:         ${:.2}\n", estimated_cost);
+        println!("  Estimated cost:         ${:.2}\n", result.estimated_cost);
 
         // Compare with Bitcoin network
         println!("Real-world Context:");
@@ -224,14 +353,7 @@ impl SecurityExperiments {
 
         println!("═════════════════════════════════════════════════════════\n");
 
-        SecurityCostResult {
-            blocks_to_rewrite,
-            difficulty,
-            estimated_hashes_per_block,
-            total_hashes,
-            estimated_time,
-            estimated_cost,
-        }
+        result
     }
 
     /// Experiment 3: Cascading Failure Demonstration
@@ -241,21 +363,21 @@ impl SecurityExperiments {
         println!("║     Experiment: Cascading Failure Demonstration       ║");
         println!("╚════════════════════════════════════════════════════════╝\n");
 
-        let mut blockchain = Blockchain::new();
+        let mut blockchain = self.new_chain();
         blockchain.set_difficulty(2); // Low difficulty for faster demo
 
         // Create a chain
-        println!("Creating blockchain with {} blocks...", chain_depth);
+        log::info!("Creating blockchain with {} blocks...", chain_depth);
         for i in 0..chain_depth {
             blockchain.add_transaction(
                 format!("User{}", i),
                 format!("User{}", i + 1),
-                10.0,
+                10 * COIN,
             ).unwrap();
             blockchain.mine_block();
         }
 
-        println!("Blockchain created with {} blocks\n", blockchain.len());
+        log::info!("Blockchain created with {} blocks after genesis", blockchain.height());
 
         // Show initial state
         println!("Initial validation:");
@@ -263,10 +385,10 @@ impl SecurityExperiments {
 
         // Modify block 1
         println!("Modifying block #1 (changing transaction amount from 10.0 to 999.0)...");
-        if let Some(block) = blockchain.get_block_mut(1) {
-            if !block.transactions.is_empty() {
-                block.transactions[0].amount = 999.0;
-            }
+        if let Some(block) = blockchain.get_block_mut(1)
+            && !block.transactions.is_empty()
+        {
+            block.transactions[0].amount = 999 * COIN;
         }
 
         // Check each block
@@ -304,6 +426,11 @@ impl SecurityExperiments {
         }
 
         println!("\nResult: {} out of {} blocks are invalid", invalid_count, blockchain.len());
+
+        // Prefix validity flips once, from valid to invalid, so binary search finds the break
+        let heights: Vec<usize> = (0..blockchain.len()).collect();
+        let first_invalid = heights.partition_point(|&height| blockchain.is_valid_up_to(height));
+        println!("Binary search over is_valid_up_to: chain first breaks at block #{}", first_invalid);
         println!("\nExplanation:");
         println!("  • Block #1: Invalid because data changed but hash wasn't recalculated");
         println!("  • Blocks #2-{}: Invalid because their previous_hash references old block #1 hash",
@@ -333,22 +460,22 @@ impl SecurityExperiments {
 
     /// Experiment 4: Finality and Confirmations
     /// Demonstrate why transactions become more secure over time
-    pub fn demonstrate_finality(&self, confirmations: usize) {
+    pub fn demonstrate_finality(&mut self, confirmations: usize) {
         println!("\n╔════════════════════════════════════════════════════════╗");
         println!("║     Experiment: Transaction Finality                 ║");
         println!("╚════════════════════════════════════════════════════════╝\n");
 
         println!("Understanding why Bitcoin waits for 6 confirmations...\n");
 
-        let mut blockchain = Blockchain::new();
+        let mut blockchain = self.new_chain();
         blockchain.set_difficulty(2);
 
         // Add a transaction
         println!("1. Adding transaction: Alice -> Bob (10.0)");
-        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10.0).unwrap();
+        blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
         blockchain.mine_block();
 
-        let tx_block = blockchain.get_latest_block().index;
+        let tx_block = blockchain.tip_index();
         println!("   Transaction included in block #{}\n", tx_block);
 
         // Add more blocks
@@ -357,18 +484,18 @@ impl SecurityExperiments {
             blockchain.add_transaction(
                 format!("Miner{}", i),
                 format!("Receiver{}", i),
-                1.0,
+                COIN,
             ).unwrap();
             blockchain.mine_block();
         }
 
-        println!("   Current chain height: #{}\n", blockchain.get_latest_block().index);
+        println!("   Current chain height: #{}\n", blockchain.tip_index());
 
         // Calculate attack cost at different depths
         println!("3. Attack cost analysis (rewriting blocks to double-spend):");
 
         let difficulty = blockchain.get_difficulty();
-        let hashrate = 1_000_000_000.0; // 1 GH/s for calculation
+        let hashrate = self.hashrate();
 
         for depth in [0, 1, 3, 6, 10].iter() {
             if *depth <= tx_block as usize {
@@ -377,10 +504,11 @@ impl SecurityExperiments {
                 let total_hashes = hashes_per_block * blocks_to_rewrite as f64;
                 let seconds = total_hashes / hashrate;
 
-                println!("   {} confirmation(s):  Rewrite {} blocks  (~{} with 1 GH/s)",
+                println!("   {} confirmation(s):  Rewrite {} blocks  (~{} at {})",
                     depth,
                     blocks_to_rewrite,
-                    format_duration(Duration::from_secs_f64(seconds))
+                    format_duration(Duration::from_secs_f64(seconds)),
+                    format_hashrate(hashrate)
                 );
             }
         }
@@ -409,7 +537,7 @@ impl SecurityExperiments {
         println!("Understanding blockchain consensus through chain reorganization...\n");
 
         // Create main chain
-        let mut main_chain = Blockchain::new();
+        let mut main_chain = self.new_chain();
         main_chain.set_difficulty(1);
 
         println!("Creating main chain:");
@@ -417,18 +545,18 @@ impl SecurityExperiments {
             main_chain.add_transaction(
                 format!("MainTx{}", i),
                 format!("MainRx{}", i),
-                10.0,
+                10 * COIN,
             ).unwrap();
             main_chain.mine_block();
             println!("  Mined block #{}: MainTx{}", i + 1, i);
         }
 
         println!("\nMain chain: {} blocks", main_chain.len());
-        println!("Latest hash: {}...\n", &main_chain.get_latest_block().hash[..16]);
+        println!("Latest hash: {}...\n", short_hash(main_chain.tip_hash(), DEFAULT_HASH_LEN));
 
         // Create competing fork
         println!("Creating competing fork (attacker's chain):");
-        let mut fork_chain = Blockchain::new();
+        let mut fork_chain = self.new_chain();
         fork_chain.set_difficulty(1);
 
         // Fork starts from same genesis
@@ -436,14 +564,14 @@ impl SecurityExperiments {
             fork_chain.add_transaction(
                 format!("ForkTx{}", i),
                 format!("ForkRx{}", i),
-                10.0,
+                10 * COIN,
             ).unwrap();
             fork_chain.mine_block();
             println!("  Mined block #{}: ForkTx{}", i + 1, i);
         }
 
         println!("\nFork chain: {} blocks", fork_chain.len());
-        println!("Latest hash: {}...\n", &fork_chain.get_latest_block().hash[..16]);
+        println!("Latest hash: {}...\n", short_hash(fork_chain.tip_hash(), DEFAULT_HASH_LEN));
 
         // Apply longest chain rule
         println!("Applying longest chain rule:");
@@ -451,12 +579,13 @@ impl SecurityExperiments {
         println!("  Fork chain length:  {}", fork_chain.len());
         println!("  Winner: Fork chain (longer)\n");
 
-        let before_replace = main_chain.get_latest_block().index;
+        let before_replace = main_chain.tip_index();
         match main_chain.replace_chain(fork_chain) {
-            Ok(_) => {
+            Ok(report) => {
                 println!("✓ Chain reorganized!");
                 println!("  Before: chain ending at block #{}", before_replace);
-                println!("  After:  chain ending at block #{}", main_chain.get_latest_block().index);
+                println!("  After:  chain ending at block #{}", main_chain.tip_index());
+                println!("  Orphaned transactions: {} back in the mempool, {} dropped", report.returned, report.dropped);
             }
             Err(e) => {
                 println!("✗ Reorganization failed: {}", e);
@@ -473,6 +602,137 @@ impl SecurityExperiments {
         println!("═════════════════════════════════════════════════════════\n");
     }
 
+    /// Experiment 6: Block Propagation
+    /// Simulate `miners` racing to find `blocks` blocks, each seeing the
+    /// others' blocks only `latency_ms` after they are found
+    pub fn experiment_propagation(&self, miners: usize, latency_ms: u64, blocks: usize) -> PropagationResult {
+        println!("\n╔════════════════════════════════════════════════════════╗");
+        println!("║     Experiment: Block Propagation Delay                ║");
+        println!("╚════════════════════════════════════════════════════════╝\n");
+
+        let miners = miners.max(1);
+        println!("{} miners, {}ms latency, one block every {}ms on average\n",
+            miners, latency_ms, PROPAGATION_BLOCK_INTERVAL_MS);
+
+        // Simulated time drives every node's clock, offset from the experiments' clock
+        let elapsed = Arc::new(AtomicU64::new(0));
+        let start = self.clock.now();
+        let clock = {
+            let elapsed = Arc::clone(&elapsed);
+            Clock::from_fn(move || start + elapsed.load(Ordering::SeqCst) as u128)
+        };
+        let mut genesis = Blockchain::with_clock(clock);
+        genesis.set_difficulty(1);
+        let mut nodes = vec![genesis; miners];
+
+        // Each miner's find times come from its own fixed sequence of draws, so
+        // runs that differ only in latency find blocks at the same moments
+        let mut draws = vec![1; miners];
+        let mut next_find: Vec<u64> = (0..miners).map(|miner| find_interval(miner, 0, miners)).collect();
+        let mut in_flight: Vec<(u64, usize, Blockchain)> = Vec::new();
+
+        for _ in 0..blocks {
+            let (miner, at) = next_find
+                .iter()
+                .copied()
+                .enumerate()
+                .min_by_key(|&(miner, at)| (at, miner))
+                .expect("at least one miner");
+
+            deliver(&mut nodes, &mut in_flight, at);
+            elapsed.store(at, Ordering::SeqCst);
+            nodes[miner].mine_block_to(&format!("Miner{}", miner));
+            log::debug!("t={}ms: Miner{} found block #{}", at, miner, nodes[miner].height());
+
+            for peer in (0..miners).filter(|&peer| peer != miner) {
+                in_flight.push((at + latency_ms, peer, nodes[miner].clone()));
+            }
+            next_find[miner] = at + find_interval(miner, draws[miner], miners);
+            draws[miner] += 1;
+        }
+        deliver(&mut nodes, &mut in_flight, u64::MAX);
+
+        // Every node has now heard every chain; the longest one wins
+        let final_height = nodes.iter().map(Blockchain::height).max().unwrap_or(0);
+        let orphaned = blocks - final_height;
+        let orphan_rate = if blocks == 0 { 0.0 } else { orphaned as f64 / blocks as f64 };
+
+        println!("Blocks found:   {}", blocks);
+        println!("Winning chain:  {} blocks after genesis", final_height);
+        println!("Orphaned:       {} ({:.1}%)", orphaned, orphan_rate * 100.0);
+        println!("\nWhile a block is in flight, other miners still work on the old tip;");
+        println!("if one of them finds a block too, only one of the two can survive.");
+        println!("═════════════════════════════════════════════════════════\n");
+
+        PropagationResult {
+            miners,
+            latency_ms,
+            blocks_found: blocks,
+            orphaned,
+            final_height,
+            orphan_rate,
+        }
+    }
+
+    /// Experiment 7: 51% Attack
+    /// Races an attacker mining in private against honest miners until the
+    /// honest chain has `honest_blocks` blocks, then publishes the attacker's
+    /// chain to an honest node. Each block goes to the attacker with
+    /// probability `attacker_fraction`, its share of the hashrate
+    pub fn experiment_fifty_one_percent(&self, honest_blocks: usize, attacker_fraction: f64) -> FiftyOnePercentResult {
+        println!("\n╔════════════════════════════════════════════════════════╗");
+        println!("║     Experiment: 51% Attack                             ║");
+        println!("╚════════════════════════════════════════════════════════╝\n");
+
+        let attacker_fraction = attacker_fraction.clamp(0.0, 1.0);
+        println!("Attacker has {:.0}% of the hashrate; the merchant waits for {} confirmation(s)",
+            attacker_fraction * 100.0, honest_blocks);
+        println!("Racing {} times...\n", FIFTY_ONE_PERCENT_TRIALS);
+
+        let mut successes = 0;
+        for trial in 0..FIFTY_ONE_PERCENT_TRIALS {
+            let mut honest = self.new_chain();
+            honest.set_difficulty(1);
+            let mut attacker = honest.clone();
+
+            // One block per turn, found by whoever the draw favors; once the
+            // attacker's chain is past `honest_blocks` the race is already won
+            let mut turn = 0;
+            while honest.height() < honest_blocks && attacker.height() <= honest_blocks {
+                if hash_uniform(&format!("fifty-one:{}:{}", trial, turn)) < attacker_fraction {
+                    attacker.mine_block_to("Attacker");
+                } else {
+                    honest.mine_block_to("Honest");
+                }
+                turn += 1;
+            }
+
+            // The honest node reorgs only onto a longer valid chain
+            let (honest_height, attacker_height) = (honest.height(), attacker.height());
+            let won = attacker_height > honest_height && honest.replace_chain(attacker).is_ok();
+            if won {
+                successes += 1;
+            }
+            log::debug!("Race {}: honest {} vs attacker {} blocks, attacker {}",
+                trial + 1, honest_height, attacker_height, if won { "wins" } else { "loses" });
+        }
+
+        let success_rate = successes as f64 / FIFTY_ONE_PERCENT_TRIALS as f64;
+        println!("Attacker reorged the honest chain in {} of {} races ({:.0}%)",
+            successes, FIFTY_ONE_PERCENT_TRIALS, success_rate * 100.0);
+        println!("\nBelow half the hashrate, falling behind is the likely outcome, and more");
+        println!("confirmations make it likelier; above half, the attacker usually wins.");
+        println!("═════════════════════════════════════════════════════════\n");
+
+        FiftyOnePercentResult {
+            honest_blocks,
+            attacker_fraction,
+            trials: FIFTY_ONE_PERCENT_TRIALS,
+            successes,
+            success_rate,
+        }
+    }
+
     /// Run all experiments
     pub fn run_all_experiments(&mut self) {
         println!("\n╔════════════════════════════════════════════════════════╗");
@@ -485,10 +745,11 @@ impl SecurityExperiments {
         self.experiment_difficulty_vs_time(4, 3);
 
         // Experiment 2: Attack Cost
+        let hashrate = self.hashrate();
         self.calculate_attack_cost(
             6,                           // Rewrite 6 blocks
             4,                           // Difficulty 4
-            1_000_000_000,               // 1 GH/s (fast for demo)
+            (hashrate as u64).max(1),    // This machine's measured hashrate
             0.10,                        // $0.10 per kWh
             1000.0,                      // 1000 watts
         );
@@ -502,6 +763,12 @@ impl SecurityExperiments {
         // Experiment 5: Longest Chain
         self.demonstrate_longest_chain_rule();
 
+        // Experiment 6: Propagation
+        self.experiment_propagation(4, 2_000, 30);
+
+        // Experiment 7: 51% Attack
+        self.experiment_fifty_one_percent(6, 0.3);
+
         println!("\n╔════════════════════════════════════════════════════════╗");
         println!("║     All Experiments Complete!                          ║");
         println!("╚════════════════════════════════════════════════════════╝\n");
@@ -512,6 +779,8 @@ impl SecurityExperiments {
         println!("  3. Tampering with any block breaks all subsequent blocks");
         println!("  4. Confirmations provide probabilistic finality");
         println!("  5. Longest chain rule enables consensus");
+        println!("  6. Slow propagation turns simultaneous blocks into orphans");
+        println!("  7. A hashrate majority can rewrite confirmed blocks");
         println!("\nBlockchain security comes from:");
         println!("  • Cryptographic linking (integrity)");
         println!("  • Proof-of-work (cost to rewrite)");
@@ -525,8 +794,37 @@ impl Default for SecurityExperiments {
     }
 }
 
+/// Milliseconds until `miner` finds its `draw`-th block
+/// Exponentially distributed, as for real proof-of-work, and derived from a
+/// hash so every run sees the same sequence
+fn find_interval(miner: usize, draw: usize, miners: usize) -> u64 {
+    let uniform = hash_uniform(&format!("propagation:{}:{}", miner, draw));
+    let mean = (PROPAGATION_BLOCK_INTERVAL_MS * miners as u64) as f64;
+    ((-(1.0 - uniform).ln() * mean) as u64).max(1)
+}
+
+/// A number in [0, 1) derived from the hash of `seed`, the same on every run
+fn hash_uniform(seed: &str) -> f64 {
+    let hash = calculate_hash(seed);
+    u64::from_str_radix(&hash[..13], 16).expect("hash is hex") as f64 / (1u64 << 52) as f64
+}
+
+/// Hands over every chain in flight that arrives by `until`, in arrival order
+/// A node switches only to a longer chain, as `replace_chain` requires
+fn deliver(nodes: &mut [Blockchain], in_flight: &mut Vec<(u64, usize, Blockchain)>, until: u64) {
+    in_flight.sort_by_key(|&(arrival, _, _)| arrival);
+    let arrived = in_flight.partition_point(|&(arrival, _, _)| arrival <= until);
+    for (_, peer, chain) in in_flight.drain(..arrived) {
+        if chain.len() > nodes[peer].len()
+            && let Err(e) = nodes[peer].replace_chain(chain)
+        {
+            log::warn!("Miner{} rejected a peer's chain: {}", peer, e);
+        }
+    }
+}
+
 /// Format a large number with commas
-fn format_number(n: u64) -> String {
+pub fn format_number(n: u64) -> String {
     if n >= 1_000_000_000 {
         format!("{:.2} billion", n as f64 / 1_000_000_000.0)
     } else if n >= 1_000_000 {
@@ -538,8 +836,61 @@ fn format_number(n: u64) -> String {
     }
 }
 
+/// Format a hashrate with H/s, kH/s, MH/s or GH/s units
+pub fn format_hashrate(hashes_per_second: f64) -> String {
+    if hashes_per_second >= 1_000_000_000.0 {
+        format!("{:.2} GH/s", hashes_per_second / 1_000_000_000.0)
+    } else if hashes_per_second >= 1_000_000.0 {
+        format!("{:.2} MH/s", hashes_per_second / 1_000_000.0)
+    } else if hashes_per_second >= 1_000.0 {
+        format!("{:.2} kH/s", hashes_per_second / 1_000.0)
+    } else {
+        format!("{:.2} H/s", hashes_per_second)
+    }
+}
+
+/// Expected time to mine one block at `difficulty`: `16^difficulty` hashes at
+/// `hashes_per_second`. Each nonce is an independent draw, so this is also the
+/// expected time still to go however long mining has already run.
+/// None without a usable hashrate
+pub fn expected_mining_time(difficulty: u32, hashes_per_second: f64) -> Option<Duration> {
+    if !hashes_per_second.is_finite() || hashes_per_second <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(16_f64.powi(difficulty as i32) / hashes_per_second).ok()
+}
+
+/// The attack cost model without the report: `2 * 16^difficulty` hashes per
+/// block (a safety margin over the average), timed at the given hashrate and
+/// priced at the given electricity rate and power draw
+pub fn estimate_rewrite_cost(
+    blocks_to_rewrite: usize,
+    difficulty: u32,
+    hashrate_hashes_per_second: u64,
+    electricity_rate_per_kwh: f64,
+    power_consumption_watts: f64,
+) -> SecurityCostResult {
+    let estimated_hashes_per_block = 16_u64.saturating_pow(difficulty).saturating_mul(2);
+    let total_hashes = estimated_hashes_per_block.saturating_mul(blocks_to_rewrite as u64);
+
+    let estimated_seconds = total_hashes as f64 / hashrate_hashes_per_second.max(1) as f64;
+    let estimated_time = Duration::from_secs_f64(estimated_seconds);
+
+    let kilowatt_hours = (estimated_seconds / 3600.0) * (power_consumption_watts / 1000.0);
+    let estimated_cost = kilowatt_hours * electricity_rate_per_kwh;
+
+    SecurityCostResult {
+        blocks_to_rewrite,
+        difficulty,
+        estimated_hashes_per_block,
+        total_hashes,
+        estimated_time,
+        estimated_cost,
+    }
+}
+
 /// Format a duration in human-readable form
-fn format_duration(duration: Duration) -> String {
+pub fn format_duration(duration: Duration) -> String {
     let secs = duration.as_secs_f64();
 
     if secs >= 86400.0 {
@@ -568,12 +919,93 @@ mod tests {
         assert!(format_number(1_000_000).contains("million"));
     }
 
+    #[test]
+    fn test_format_hashrate() {
+        assert_eq!(format_hashrate(512.0), "512.00 H/s");
+        assert_eq!(format_hashrate(1_500_000.0), "1.50 MH/s");
+        assert_eq!(format_hashrate(2_000_000_000.0), "2.00 GH/s");
+    }
+
+    #[test]
+    fn test_expected_mining_time() {
+        // 16^4 = 65,536 hashes at 1,024 H/s
+        assert_eq!(expected_mining_time(4, 1024.0), Some(Duration::from_secs(64)));
+        assert_eq!(expected_mining_time(0, 2.0), Some(Duration::from_millis(500)));
+        // Each extra digit is sixteen times the work
+        assert_eq!(expected_mining_time(5, 1024.0), Some(Duration::from_secs(1024)));
+        assert_eq!(expected_mining_time(4, 0.0), None);
+        assert_eq!(expected_mining_time(4, f64::NAN), None);
+    }
+
+    #[test]
+    fn test_hashrate_is_measured_once() {
+        let mut experiments = SecurityExperiments::new();
+        let first = experiments.hashrate();
+        assert!(first.is_finite() && first > 0.0);
+        assert_eq!(experiments.hashrate(), first);
+
+        experiments.set_hashrate(42.0);
+        assert_eq!(experiments.hashrate(), 42.0);
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_secs(90)), "1.50 minutes");
         assert_eq!(format_duration(Duration::from_secs(3600)), "1.00 hours");
     }
 
+    #[test]
+    fn test_difficulty_results_export() {
+        let experiments = SecurityExperiments::with_clock(Clock::stepping(1_700_000_000_000, 1_000));
+        let result = experiments.experiment_difficulty_vs_time(3, 1);
+
+        let csv = result.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 3);
+        assert_eq!(lines[0], "difficulty,avg_time_ms,avg_nonce");
+        for (line, difficulty) in lines[1..].iter().zip(1..) {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), 3);
+            assert_eq!(fields[0], difficulty.to_string());
+            assert_eq!(fields[2], result.avg_nonces[difficulty - 1].to_string());
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[2]["difficulty"], 3);
+    }
+
+    #[test]
+    fn test_chain_history_csv() {
+        let mut blockchain = Blockchain::with_clock(Clock::stepping(1_000, 100));
+        blockchain.set_difficulty(1);
+        blockchain.mine_block();
+        blockchain.set_difficulty(2);
+        blockchain.mine_block();
+
+        let csv = chain_history_csv(&blockchain);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "index,difficulty,block_time_ms");
+        assert!(lines[1].ends_with(','));
+        assert!(lines[2].starts_with("1,1,"));
+        assert!(lines[3].starts_with("2,2,"));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_empty_difficulty_results_export() {
+        let result = DifficultyComparisonResult {
+            difficulties: Vec::new(),
+            avg_times: Vec::new(),
+            avg_nonces: Vec::new(),
+            time_increase_factor: 1.0,
+            security_increase_factor: 1.0,
+        };
+
+        assert_eq!(result.to_csv(), "difficulty,avg_time_ms,avg_nonce\n");
+        assert_eq!(result.to_json(), "[]");
+    }
+
     #[test]
     fn test_create_test_blockchain() {
         let mut experiments = SecurityExperiments::new();
@@ -583,6 +1015,47 @@ mod tests {
         assert_eq!(blockchain.get_difficulty(), 2);
     }
 
+    #[test]
+    fn test_seeded_experiments_are_reproducible() {
+        let mut first = SecurityExperiments::with_clock(Clock::stepping(1_000, 500));
+        let mut second = SecurityExperiments::with_clock(Clock::stepping(1_000, 500));
+
+        let first_hash = first.create_test_blockchain(2, 3).tip_hash().to_string();
+        let second_hash = second.create_test_blockchain(2, 3).tip_hash().to_string();
+
+        assert_eq!(first_hash, second_hash);
+    }
+
+    #[test]
+    fn test_fifty_one_percent_attack_needs_a_majority() {
+        let experiments = SecurityExperiments::with_clock(Clock::stepping(1_700_000_000_000, 1));
+        let majority = experiments.experiment_fifty_one_percent(4, 0.9);
+        let minority = experiments.experiment_fifty_one_percent(4, 0.1);
+
+        assert_eq!(majority.trials, FIFTY_ONE_PERCENT_TRIALS);
+        assert!(majority.successes * 4 >= majority.trials * 3, "{:?}", majority);
+        assert!(minority.successes * 4 <= minority.trials, "{:?}", minority);
+        assert!(majority.success_rate > minority.success_rate);
+    }
+
+    #[test]
+    fn test_propagation_latency_causes_orphans() {
+        let experiments = SecurityExperiments::with_clock(Clock::stepping(1_700_000_000_000, 1));
+        let instant = experiments.experiment_propagation(4, 0, 30);
+        let slow = experiments.experiment_propagation(4, 2_000, 30);
+        let slower = experiments.experiment_propagation(4, 10_000, 30);
+
+        assert_eq!(instant.orphaned, 0);
+        assert_eq!(instant.final_height, 30);
+        assert!(slow.orphaned <= slower.orphaned);
+        assert!(slower.orphaned > 0);
+        assert_eq!(slower.final_height + slower.orphaned, slower.blocks_found);
+
+        // Same inputs, same race
+        let again = experiments.experiment_propagation(4, 2_000, 30);
+        assert_eq!(again, slow);
+    }
+
     #[test]
     fn test_difficulty_experiment() {
         let experiments = SecurityExperiments::new();
@@ -0,0 +1,269 @@
+//! JSON-over-HTTP API for a node
+//!
+//! A small blocking server (tiny_http) so other programs can query and drive
+//! a chain. Routes:
+//!
+//! - `GET /chain`: the whole chain, as saved by `save`
+//! - `GET /block/{index}`: one block
+//! - `GET /balance/{address}`: confirmed balance in base units
+//! - `POST /transaction`: queue a transfer; body `{"sender", "receiver", "amount", "fee"?, "memo"?}`
+//! - `POST /mine`: mine the pending transactions; optional body `{"miner"}`
+//!
+//! Amounts are base units (see `transaction::COIN`). Errors come back as
+//! `{"error": "..."}` with a 4xx status. Bodies over `MAX_BODY_BYTES` are
+//! refused with 413 without being buffered.
+
+use crate::blockchain::Blockchain;
+use crate::transaction::{format_balance, Transaction, TransactionError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Read;
+use std::time::Duration;
+
+/// Largest request body `ApiServer` reads by default, in bytes
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Body of `POST /transaction`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewTransaction {
+    pub sender: String,
+    pub receiver: String,
+    /// Amount in base units
+    pub amount: u64,
+    #[serde(default)]
+    pub fee: u64,
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+/// Body of `POST /mine`; without a miner the block pays no reward
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MineRequest {
+    #[serde(default)]
+    pub miner: Option<String>,
+}
+
+/// Status code and JSON body for one request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl ApiResponse {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => ApiResponse { status, body },
+            Err(e) => Self::error(500, format!("Serialization failed: {}", e)),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        ApiResponse { status, body: json!({ "error": message.into() }).to_string() }
+    }
+}
+
+/// Answers one API request against `blockchain`
+/// `default_miner` is paid for `POST /mine` when the body names no miner
+pub fn route(
+    blockchain: &mut Blockchain,
+    default_miner: Option<&str>,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> ApiResponse {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["chain"]) => ApiResponse::json(200, blockchain),
+
+        ("GET", ["block", index]) => {
+            let Ok(index) = index.parse::<usize>() else {
+                return ApiResponse::error(400, format!("Invalid block index '{}'", index));
+            };
+            match blockchain.get_block(index) {
+                Some(block) => ApiResponse::json(200, block),
+                None => ApiResponse::error(404, format!("Block {} does not exist", index)),
+            }
+        }
+
+        ("GET", ["balance", address]) => {
+            let balance = blockchain.balance(address);
+            ApiResponse::json(200, &json!({
+                "address": address,
+                "balance": balance,
+                "formatted": format_balance(balance),
+            }))
+        }
+
+        ("POST", ["transaction"]) => {
+            let request: NewTransaction = match serde_json::from_str(body) {
+                Ok(request) => request,
+                Err(e) => return ApiResponse::error(400, format!("Invalid transaction body: {}", e)),
+            };
+            match add_transaction(blockchain, request) {
+                Ok(id) => ApiResponse::json(201, &json!({
+                    "id": id,
+                    "pending": blockchain.pending_transaction_count(),
+                })),
+                Err(e) => ApiResponse::error(422, e.to_string()),
+            }
+        }
+
+        ("POST", ["mine"]) => {
+            let request: MineRequest = if body.trim().is_empty() {
+                MineRequest::default()
+            } else {
+                match serde_json::from_str(body) {
+                    Ok(request) => request,
+                    Err(e) => return ApiResponse::error(400, format!("Invalid mine body: {}", e)),
+                }
+            };
+            let block = match request.miner.as_deref().or(default_miner) {
+                Some(miner) => blockchain.mine_block_to(miner),
+                None => blockchain.mine_block(),
+            };
+            ApiResponse::json(201, block)
+        }
+
+        (_, ["chain"] | ["block", _] | ["balance", _] | ["transaction"] | ["mine"]) => {
+            ApiResponse::error(405, format!("{} is not supported on {}", method, path))
+        }
+        _ => ApiResponse::error(404, format!("No route for {}", path)),
+    }
+}
+
+/// Builds, validates and queues a transfer; returns its id once queued
+fn add_transaction(blockchain: &mut Blockchain, request: NewTransaction) -> Result<String, TransactionError> {
+    // Take the sender's next account nonce up front, since it is part of the id
+    let nonce = blockchain.next_nonce(&request.sender);
+    let mut transaction = Transaction::new(request.sender, request.receiver, request.amount)?
+        .with_fee(request.fee)
+        .with_nonce(nonce);
+    if let Some(memo) = request.memo {
+        transaction = transaction.with_memo(memo)?;
+    }
+    let id = transaction.id();
+    blockchain.add_pending_transaction(transaction)?;
+    Ok(id)
+}
+
+/// A bound HTTP listener serving the API
+pub struct ApiServer {
+    server: tiny_http::Server,
+    miner_address: Option<String>,
+    /// Longer bodies are answered with 413
+    max_body_bytes: usize,
+}
+
+impl ApiServer {
+    /// Listens on `addr`, e.g. "127.0.0.1:8080" (port 0 picks a free one)
+    pub fn bind(addr: &str) -> Result<Self, String> {
+        let server = tiny_http::Server::http(addr)
+            .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+        Ok(ApiServer { server, miner_address: None, max_body_bytes: MAX_BODY_BYTES })
+    }
+
+    /// Pays `POST /mine` rewards to `address` unless the request names a miner
+    pub fn with_miner(mut self, address: impl Into<String>) -> Self {
+        self.miner_address = Some(address.into());
+        self
+    }
+
+    /// Refuses request bodies longer than `bytes` (default `MAX_BODY_BYTES`)
+    pub fn with_max_body_bytes(mut self, bytes: usize) -> Self {
+        self.max_body_bytes = bytes;
+        self
+    }
+
+    /// The port actually bound (useful after binding port 0)
+    pub fn port(&self) -> Option<u16> {
+        self.server.server_addr().to_ip().map(|addr| addr.port())
+    }
+
+    /// Answers the next request, waiting at most `timeout` for one
+    /// Returns whether a request was handled
+    pub fn handle_next(&self, blockchain: &mut Blockchain, timeout: Duration) -> Result<bool, String> {
+        let Some(mut request) = self.server.recv_timeout(timeout).map_err(|e| e.to_string())? else {
+            return Ok(false);
+        };
+
+        // Read at most one byte past the limit, so a longer body is caught
+        // whatever its Content-Length claims
+        let limit = self.max_body_bytes;
+        let too_large = || ApiResponse::error(413, format!("Request body is over {} bytes", limit));
+        let mut body = String::new();
+        let response = if request.body_length().is_some_and(|length| length > limit) {
+            too_large()
+        } else {
+            match Read::take(request.as_reader(), limit as u64 + 1).read_to_string(&mut body) {
+                Ok(read) if read > limit => too_large(),
+                Ok(_) => {
+                    let method = request.method().as_str().to_ascii_uppercase();
+                    let path = request.url().split('?').next().unwrap_or("").to_string();
+                    route(blockchain, self.miner_address.as_deref(), &method, &path, &body)
+                }
+                Err(e) => ApiResponse::error(400, format!("Failed to read body: {}", e)),
+            }
+        };
+        log::debug!("{} {} -> {}", request.method(), request.url(), response.status);
+
+        let header = tiny_http::Header::from_bytes("Content-Type", "application/json")
+            .expect("static header is valid");
+        let reply = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(header);
+        request.respond(reply).map_err(|e| format!("Failed to send response: {}", e))?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::COIN;
+
+    fn chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let mut blockchain = chain();
+        let status = |response: ApiResponse| response.status;
+
+        assert_eq!(status(route(&mut blockchain, None, "GET", "/block/0", "")), 200);
+        assert_eq!(status(route(&mut blockchain, None, "GET", "/block/9", "")), 404);
+        assert_eq!(status(route(&mut blockchain, None, "GET", "/block/x", "")), 400);
+        assert_eq!(status(route(&mut blockchain, None, "DELETE", "/chain", "")), 405);
+        assert_eq!(status(route(&mut blockchain, None, "GET", "/nowhere", "")), 404);
+        assert_eq!(status(route(&mut blockchain, None, "POST", "/transaction", "{}")), 400);
+
+        let same_party = json!({ "sender": "Alice", "receiver": "Alice", "amount": COIN }).to_string();
+        let response = route(&mut blockchain, None, "POST", "/transaction", &same_party);
+        assert_eq!(response.status, 422);
+        assert!(response.body.contains("cannot be the same"));
+        assert_eq!(blockchain.pending_transaction_count(), 0);
+    }
+
+    #[test]
+    fn test_route_transaction_and_mine() {
+        let mut blockchain = chain();
+        let body = json!({ "sender": "Alice", "receiver": "Bob", "amount": 3 * COIN, "memo": "rent" }).to_string();
+        let response = route(&mut blockchain, None, "POST", "/transaction", &body);
+        assert_eq!(response.status, 201);
+        let id = serde_json::from_str::<serde_json::Value>(&response.body).unwrap()["id"].clone();
+        assert_eq!(id, json!(blockchain.pending_transactions[0].id()));
+
+        let response = route(&mut blockchain, Some("Node"), "POST", "/mine", "");
+        assert_eq!(response.status, 201);
+        assert_eq!(blockchain.len(), 2);
+        assert_eq!(blockchain.balance("Node"), blockchain.block_reward as i128);
+
+        let balance: serde_json::Value =
+            serde_json::from_str(&route(&mut blockchain, None, "GET", "/balance/Bob", "").body).unwrap();
+        assert_eq!(balance["balance"], json!(3 * COIN));
+        assert_eq!(balance["formatted"], json!("3.00"));
+    }
+}
@@ -0,0 +1,77 @@
+//! RustChain library
+//!
+//! The core ledger types (`block`, `blockchain`, `consensus`, `crypto`,
+//! `storage`, `transaction`, `validation`), difficulty retargeting
+//! (`retarget`), transaction gossip (`node`),
+//! event callbacks (`observer`), the audit log (`audit`) and address aliases
+//! (`alias`) are always available. Everything that talks to a terminal or
+//! measures wall-clock time is opt-in through Cargo features:
+//!
+//! - `mining`: timestamped block production (`Blockchain::mine_block`) and
+//!   step-by-step mining with rewind (`stepper`)
+//! - `attacks`: Day 7 attack simulations
+//! - `experiments`: Day 7 security experiments
+//! - `visualization`: ASCII/ANSI chain visualizations
+//! - `http`: a JSON HTTP API server (`http::ApiServer`, on tiny_http)
+//! - `cli`: the interactive command-line interface (enables all of the above)
+//! - `tui`: a full-screen live dashboard (`tui::run`, on ratatui), opened
+//!   with the CLI's `tui` command (not enabled by `cli`)
+//! - `utxo`: transaction inputs/outputs and `utxo::UtxoSet`, next to the
+//!   account model (not enabled by `cli`)
+//! - `wasm`: `wasm-bindgen` bindings for browser demos
+//! - `async`: `Blockchain::mine_block_async`, mining on tokio's blocking pool
+//! - `rayon`: `validation::validate_chain_parallel`, hashing blocks on all cores
+//! - `scrypt`: `consensus::PowAlgorithm::ScryptLike`, memory-hard proof-of-work
+//!
+//! Build the core alone with `cargo build --no-default-features`.
+
+pub mod alias;
+pub mod audit;
+pub mod block;
+pub mod blockchain;
+pub mod clock;
+pub mod consensus;
+pub mod crypto;
+pub mod merkle;
+pub mod node;
+pub mod observer;
+pub mod retarget;
+pub mod storage;
+pub mod transaction;
+pub mod validation;
+
+#[cfg(feature = "attacks")]
+pub mod attacks;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "experiments")]
+pub mod experiments;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "mining")]
+pub mod stepper;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "visualization")]
+pub mod visualization;
+#[cfg(feature = "utxo")]
+pub mod utxo;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(test)]
+mod tests {
+    use crate::blockchain::Blockchain;
+    use crate::transaction::COIN;
+    use crate::validation;
+
+    #[test]
+    fn test_core_builds_without_optional_features() {
+        // Only uses APIs that are available with `--no-default-features`
+        let mut blockchain = Blockchain::new();
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+
+        assert_eq!(blockchain.pending_transaction_count(), 1);
+        assert!(validation::validate_chain(&blockchain).is_valid);
+    }
+}
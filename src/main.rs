@@ -1,19 +1,11 @@
-mod attacks;
-mod block;
-mod blockchain;
-mod cli;
-mod crypto;
-mod experiments;
-mod transaction;
-mod validation;
-mod visualization;
-
-use cli::Cli;
+use rustchain::cli::{self, Cli};
 use std::env;
 
 fn main() {
-    // Get command-line arguments
+    // Get command-line arguments, minus the logging flags
     let args: Vec<String> = env::args().collect();
+    let (log_level, args) = cli::split_log_flags(&args);
+    cli::init_logging(log_level);
 
     // Create CLI instance
     let mut cli = Cli::new();
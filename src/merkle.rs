@@ -0,0 +1,274 @@
+//! Merkle trees over block transactions
+//!
+//! Each block commits to its transactions through a single Merkle root, and
+//! the block hash covers that root. A `MerkleProof` lets anyone check that a
+//! transaction is in a block using only the proof and the block's root.
+//!
+//! Leaves and inner nodes are hashed under different one-byte tags (0x00 and
+//! 0x01), so an inner node can't be passed off as a leaf, and an odd node out
+//! is paired with an empty sibling rather than with itself, so no leaf can be
+//! proven at a second position. Blocks older than `TAGGED_MERKLE_VERSION`
+//! keep the untagged tree they were saved with (`legacy_merkle_root`).
+
+use crate::crypto::calculate_hash;
+use serde::{Deserialize, Serialize};
+
+/// First block version whose Merkle root uses the tagged tree
+pub const TAGGED_MERKLE_VERSION: u32 = 2;
+
+/// Which side of the running hash a sibling sits on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// One step of a Merkle path: the sibling hash at that level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub side: Side,
+}
+
+/// Inclusion proof for a single transaction in a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub tx_id: String,
+    pub block_index: u64,
+    pub leaf_index: usize,
+    pub path: Vec<ProofStep>,
+    pub merkle_root: String,
+}
+
+impl MerkleProof {
+    /// Recomputes the root by hashing the transaction id up the path
+    pub fn compute_root(&self) -> String {
+        self.path.iter().fold(hash_leaf(&self.tx_id), |current, step| match step.side {
+            Side::Left => hash_node(&step.hash, &current),
+            Side::Right => hash_node(&current, &step.hash),
+        })
+    }
+
+    /// Whether each step's side is the one `leaf_index` puts it on (bit `k`
+    /// set means the sibling at level `k` is on the left), with no bits of
+    /// the index left over once the path reaches the root
+    pub fn follows_leaf_index(&self) -> bool {
+        let bit = |level: usize| self.leaf_index.checked_shr(level as u32).unwrap_or(0) & 1 == 1;
+        self.path.iter().enumerate().all(|(level, step)| (step.side == Side::Left) == bit(level))
+            && self.leaf_index.checked_shr(self.path.len() as u32).unwrap_or(0) == 0
+    }
+
+    /// Checks the path against the root carried in the proof
+    pub fn verify(&self) -> bool {
+        self.verify_against(&self.merkle_root)
+    }
+
+    /// Checks the path against a root obtained elsewhere (e.g. a block header)
+    pub fn verify_against(&self, merkle_root: &str) -> bool {
+        self.merkle_root == merkle_root && self.follows_leaf_index() && self.compute_root() == merkle_root
+    }
+}
+
+/// Hashes a transaction id into its leaf node
+fn hash_leaf(leaf: &str) -> String {
+    calculate_hash(&format!("\u{0}{}", leaf))
+}
+
+/// Hashes two child nodes into their parent
+fn hash_node(left: &str, right: &str) -> String {
+    calculate_hash(&format!("\u{1}{}{}", left, right))
+}
+
+/// Builds the next level up; an odd node out is paired with an empty sibling
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => hash_node(single, ""),
+            _ => unreachable!("chunks(2) yields one or two items"),
+        })
+        .collect()
+}
+
+/// Computes the Merkle root of a list of leaf hashes (transaction ids)
+/// An empty block has the hash of the empty string as its root
+pub fn merkle_root(leaves: &[String]) -> String {
+    if leaves.is_empty() {
+        return calculate_hash("");
+    }
+
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+
+    level.remove(0)
+}
+
+/// The root blocks before `TAGGED_MERKLE_VERSION` commit to: untagged
+/// hashes, leaves used as they are, and an odd node paired with itself
+pub fn legacy_merkle_root(leaves: &[String]) -> String {
+    if leaves.is_empty() {
+        return calculate_hash("");
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level.chunks(2).map(|pair| calculate_hash(&format!("{}{}", pair[0], pair[pair.len() - 1]))).collect();
+    }
+
+    level.remove(0)
+}
+
+/// Builds the sibling path from leaf `index` up to the root
+/// Returns None if the index is out of range
+pub fn merkle_path(leaves: &[String], index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut path = Vec::new();
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    let mut position = index;
+
+    while level.len() > 1 {
+        let step = if position.is_multiple_of(2) {
+            // Odd node out is paired with an empty sibling
+            let sibling = level.get(position + 1).cloned().unwrap_or_default();
+            ProofStep { hash: sibling, side: Side::Right }
+        } else {
+            ProofStep { hash: level[position - 1].clone(), side: Side::Left }
+        };
+        path.push(step);
+
+        level = next_level(&level);
+        position /= 2;
+    }
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| calculate_hash(&format!("tx{}", i))).collect()
+    }
+
+    fn proof(leaves: &[String], leaf_index: usize) -> MerkleProof {
+        MerkleProof {
+            tx_id: leaves[leaf_index].clone(),
+            block_index: 1,
+            leaf_index,
+            path: merkle_path(leaves, leaf_index).unwrap(),
+            merkle_root: merkle_root(leaves),
+        }
+    }
+
+    #[test]
+    fn test_single_leaf_root_is_its_leaf_hash() {
+        let leaves = leaves(1);
+        assert_eq!(merkle_root(&leaves), hash_leaf(&leaves[0]));
+        assert_ne!(merkle_root(&leaves), leaves[0]);
+        assert_eq!(merkle_path(&leaves, 0), Some(Vec::new()));
+        assert_eq!(legacy_merkle_root(&leaves), leaves[0]);
+    }
+
+    #[test]
+    fn test_root_changes_with_any_leaf() {
+        let original = leaves(5);
+        let mut tampered = original.clone();
+        tampered[3] = calculate_hash("forged");
+
+        assert_ne!(merkle_root(&original), merkle_root(&tampered));
+    }
+
+    #[test]
+    fn test_every_leaf_has_a_valid_proof() {
+        for n in 1..=9 {
+            let leaves = leaves(n);
+            let root = merkle_root(&leaves);
+
+            for i in 0..n {
+                let proof = proof(&leaves, i);
+                assert_eq!(proof.merkle_root, root);
+                assert!(proof.verify(), "leaf {} of {} failed", i, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_forged_proof_fails() {
+        let leaves = leaves(4);
+        let root = merkle_root(&leaves);
+
+        // A transaction that isn't in the tree, reusing a real path
+        let forged = MerkleProof {
+            tx_id: calculate_hash("not in block"),
+            block_index: 1,
+            leaf_index: 2,
+            path: merkle_path(&leaves, 2).unwrap(),
+            merkle_root: root.clone(),
+        };
+        assert!(!forged.verify());
+
+        // A real transaction with one sibling hash altered
+        let mut tampered_path = merkle_path(&leaves, 2).unwrap();
+        tampered_path[0].hash = calculate_hash("bogus");
+        let tampered = MerkleProof { tx_id: leaves[2].clone(), path: tampered_path, ..forged.clone() };
+        assert!(!tampered.verify());
+
+        // A valid path claimed against a different root
+        let real = MerkleProof { tx_id: leaves[2].clone(), path: merkle_path(&leaves, 2).unwrap(), ..forged };
+        assert!(real.verify());
+        assert!(!real.verify_against(&calculate_hash("other root")));
+    }
+
+    #[test]
+    fn test_inner_node_cannot_pass_as_a_leaf() {
+        let leaves = leaves(4);
+        let real = proof(&leaves, 0);
+
+        // The parent of leaves 0 and 1, claimed as a transaction one level up
+        let parent = hash_node(&hash_leaf(&leaves[0]), &hash_leaf(&leaves[1]));
+        let forged = MerkleProof { tx_id: parent, leaf_index: 0, path: real.path[1..].to_vec(), ..real };
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn test_odd_leaf_has_only_its_own_position() {
+        let leaves = leaves(3);
+        let real = proof(&leaves, 2);
+        assert!(real.verify());
+        assert_eq!(real.path[0].hash, "");
+
+        // The untagged tree paired leaf 2 with itself, so it also "sat" at 3
+        let phantom = MerkleProof {
+            leaf_index: 3,
+            path: vec![ProofStep { hash: hash_leaf(&leaves[2]), side: Side::Left }, real.path[1].clone()],
+            ..real.clone()
+        };
+        assert!(!phantom.verify());
+    }
+
+    #[test]
+    fn test_path_must_follow_leaf_index() {
+        let leaves = leaves(4);
+        let real = proof(&leaves, 1);
+        assert!(real.verify());
+
+        // Same path and root, but claimed at another position
+        assert!(!MerkleProof { leaf_index: 0, ..real.clone() }.verify());
+        assert!(!MerkleProof { leaf_index: 5, ..real.clone() }.verify());
+        assert!(!MerkleProof { leaf_index: usize::MAX, ..real }.verify());
+    }
+
+    #[test]
+    fn test_out_of_range_index() {
+        assert!(merkle_path(&leaves(3), 3).is_none());
+        assert!(merkle_path(&[], 0).is_none());
+    }
+}
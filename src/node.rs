@@ -0,0 +1,184 @@
+//! Transaction gossip between nodes
+//!
+//! A `Node` wraps a `Blockchain` with a list of peer addresses. A transaction
+//! submitted to one node is forwarded to each of its peers, which forward it
+//! to theirs. Each node remembers the ids it has seen for `seen_ttl_ms`, so it
+//! forwards a transaction at most once and loops in the peer graph die out.
+//!
+//! Nodes don't do any I/O themselves: `submit_transaction` and `handle` return
+//! the messages to send, and the caller delivers them however it likes.
+
+use crate::blockchain::Blockchain;
+use crate::transaction::{Transaction, TransactionError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// How long a node remembers a transaction id (10 minutes)
+pub const DEFAULT_SEEN_TTL_MS: u128 = 10 * 60 * 1000;
+
+/// What nodes say to each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    /// A transaction for the mempool, to be passed on to the receiver's peers
+    NewTransaction(Transaction),
+}
+
+/// A message addressed to one peer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub to: String,
+    pub message: Message,
+}
+
+/// A blockchain node with peers
+pub struct Node {
+    address: String,
+    blockchain: Blockchain,
+    peers: Vec<String>,
+    /// Ids of transactions already handled, never forwarded again
+    seen_txids: HashSet<String>,
+    /// `seen_txids` by when they were first seen, oldest first
+    seen_order: VecDeque<(u128, String)>,
+    pub seen_ttl_ms: u128,
+}
+
+impl Node {
+    pub fn new(address: impl Into<String>, blockchain: Blockchain) -> Self {
+        Node {
+            address: address.into(),
+            blockchain,
+            peers: Vec::new(),
+            seen_txids: HashSet::new(),
+            seen_order: VecDeque::new(),
+            seen_ttl_ms: DEFAULT_SEEN_TTL_MS,
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn blockchain(&self) -> &Blockchain {
+        &self.blockchain
+    }
+
+    pub fn blockchain_mut(&mut self) -> &mut Blockchain {
+        &mut self.blockchain
+    }
+
+    pub fn peers(&self) -> &[String] {
+        &self.peers
+    }
+
+    /// Adds a peer to gossip with; false if it is this node or already a peer
+    pub fn add_peer(&mut self, addr: impl Into<String>) -> bool {
+        let addr = addr.into();
+        if addr == self.address || self.peers.contains(&addr) {
+            return false;
+        }
+        self.peers.push(addr);
+        true
+    }
+
+    /// Whether `txid` was seen within the last `seen_ttl_ms`
+    pub fn has_seen(&self, txid: &str) -> bool {
+        self.seen_txids.contains(txid)
+    }
+
+    /// Queues a transaction submitted to this node and returns the messages
+    /// announcing it to every peer
+    pub fn submit_transaction(&mut self, transaction: Transaction) -> Result<Vec<Outgoing>, TransactionError> {
+        self.accept(transaction, None)
+    }
+
+    /// Handles a message from peer `from`; returns the messages to pass on
+    /// A transaction already seen, or rejected by this node's mempool, stops here
+    pub fn handle(&mut self, from: &str, message: Message) -> Vec<Outgoing> {
+        match message {
+            Message::NewTransaction(transaction) => {
+                let id = transaction.id();
+                self.accept(transaction, Some(from)).unwrap_or_else(|e| {
+                    log::debug!("{}: not forwarding {} from {}: {}", self.address, id, from, e);
+                    Vec::new()
+                })
+            }
+        }
+    }
+
+    /// Queues `transaction` unless seen recently, then forwards it to every
+    /// peer except the one it came from
+    fn accept(&mut self, mut transaction: Transaction, from: Option<&str>) -> Result<Vec<Outgoing>, TransactionError> {
+        let now = self.blockchain.now();
+        self.forget_expired(now);
+
+        // Number the transfer here, since its nonce is part of the id peers see
+        if transaction.nonce == 0 && !transaction.is_coinbase() {
+            transaction.nonce = self.blockchain.next_nonce(&transaction.sender);
+        }
+        let id = transaction.id();
+        if self.seen_txids.contains(&id) {
+            return Ok(Vec::new());
+        }
+
+        self.blockchain.add_pending_transaction(transaction.clone())?;
+        self.seen_txids.insert(id.clone());
+        self.seen_order.push_back((now, id));
+
+        Ok(self.peers
+            .iter()
+            .filter(|peer| Some(peer.as_str()) != from)
+            .map(|peer| Outgoing { to: peer.clone(), message: Message::NewTransaction(transaction.clone()) })
+            .collect())
+    }
+
+    /// Drops ids first seen more than `seen_ttl_ms` before `now`
+    fn forget_expired(&mut self, now: u128) {
+        while let Some((seen_at, _)) = self.seen_order.front() {
+            if now.saturating_sub(*seen_at) <= self.seen_ttl_ms {
+                break;
+            }
+            let (_, id) = self.seen_order.pop_front().expect("checked above");
+            self.seen_txids.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::transaction::COIN;
+
+    #[test]
+    fn test_seen_ids_expire_after_ttl() {
+        let mut node = Node::new("a", Blockchain::with_clock(Clock::stepping(1_000, 100)));
+        node.seen_ttl_ms = 250;
+        node.add_peer("b");
+
+        let tx = Transaction::new(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+        let sent = node.submit_transaction(tx).unwrap();
+        assert_eq!(sent.len(), 1);
+        let Message::NewTransaction(numbered) = sent[0].message.clone();
+        assert!(node.has_seen(&numbered.id()));
+
+        // An echo is dropped while the id is remembered
+        assert!(node.handle("b", Message::NewTransaction(numbered.clone())).is_empty());
+
+        // Each read of the stepping clock moves it 100ms; after the TTL the id is forgotten
+        for _ in 0..3 {
+            node.blockchain().now();
+        }
+        let other = Transaction::new(String::from("Carol"), String::from("Dave"), COIN).unwrap();
+        node.submit_transaction(other).unwrap();
+        assert!(!node.has_seen(&numbered.id()));
+    }
+
+    #[test]
+    fn test_add_peer_skips_self_and_duplicates() {
+        let mut node = Node::new("a", Blockchain::new());
+        assert!(node.add_peer("b"));
+        assert!(!node.add_peer("b"));
+        assert!(!node.add_peer("a"));
+        assert_eq!(node.peers(), ["b".to_string()]);
+    }
+}
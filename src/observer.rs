@@ -0,0 +1,100 @@
+//! Callbacks for chain events, for code embedding a `Blockchain`
+//!
+//! Register an `Observer` with `Blockchain::register_observer` to hear about
+//! mined blocks, queued transactions and reorgs as they happen instead of
+//! polling. Every method has an empty default, so implement only what you
+//! need. Callbacks run synchronously, after the change has been made.
+
+use crate::block::Block;
+use crate::transaction::Transaction;
+
+/// Reacts to changes in a `Blockchain`
+pub trait Observer: Send + Sync {
+    /// A block was mined or accepted onto the tip
+    fn on_block_mined(&self, _block: &Block) {}
+
+    /// A transaction was queued in the mempool
+    fn on_transaction_added(&self, _transaction: &Transaction) {}
+
+    /// The chain switched to a longer fork, from tip `old_tip` to `new_tip`
+    fn on_reorg(&self, _old_tip: &str, _new_tip: &str) {}
+}
+
+/// The observers registered on one chain
+/// They belong to that value alone: a clone starts with none, so simulations
+/// run on a copy don't fire the original's callbacks
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Box<dyn Observer>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Box<dyn Observer>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &dyn Observer> {
+        self.0.iter().map(|observer| observer.as_ref())
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Observers::default()
+    }
+}
+
+#[cfg(all(test, feature = "mining"))]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+    use crate::transaction::COIN;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counts {
+        blocks: AtomicUsize,
+        transactions: AtomicUsize,
+        reorgs: AtomicUsize,
+    }
+
+    struct Counter(Arc<Counts>);
+
+    impl Observer for Counter {
+        fn on_block_mined(&self, _block: &Block) {
+            self.0.blocks.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_transaction_added(&self, _transaction: &Transaction) {
+            self.0.transactions.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_reorg(&self, _old_tip: &str, _new_tip: &str) {
+            self.0.reorgs.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_each_event_once() {
+        let counts = Arc::new(Counts::default());
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.register_observer(Box::new(Counter(counts.clone())));
+        let mut rival = blockchain.clone();
+
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+        blockchain.add_transaction(String::from("Alice"), String::from("Carol"), COIN).unwrap();
+        assert!(blockchain.add_transaction(String::from("Alice"), String::from("Alice"), COIN).is_err());
+        blockchain.mine_block();
+        blockchain.mine_block_to("Miner");
+
+        // A copy doesn't carry the observer, so mining the rival chain is silent
+        for _ in 0..3 {
+            rival.mine_block();
+        }
+        blockchain.replace_chain(rival).unwrap();
+
+        assert_eq!(counts.transactions.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.blocks.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.reorgs.load(Ordering::SeqCst), 1);
+    }
+}
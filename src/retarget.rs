@@ -0,0 +1,135 @@
+//! Difficulty retargeting: aiming block times at a target
+//!
+//! Each algorithm estimates the recent block time from block timestamps and
+//! moves the difficulty towards the target. Difficulty counts leading hex
+//! digits, so one step is 16 times the expected work: a chain mining 16 times
+//! too slowly drops one step. The algorithms differ in how they weigh recent
+//! blocks, and so in how fast they react to a change in hashrate.
+
+use crate::block::Block;
+use crate::consensus::MAX_DIFFICULTY;
+use serde::{Deserialize, Serialize};
+
+/// How the next block's difficulty is derived from recent block times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetargetAlgorithm {
+    /// Plain average over the last `window` block times
+    SimpleWindow { window: usize },
+    /// Exponential moving average with span `window` (alpha = 2 / (window + 1)),
+    /// so the newest block time counts most
+    Ema { window: usize },
+    /// Linearly weighted moving average: the newest of the last `window`
+    /// block times weighs `window`, the oldest 1 (as in Zcash-style LWMA)
+    Lwma { window: usize },
+}
+
+impl RetargetAlgorithm {
+    /// Number of block times the algorithm looks at
+    pub fn window(&self) -> usize {
+        match self {
+            RetargetAlgorithm::SimpleWindow { window }
+            | RetargetAlgorithm::Ema { window }
+            | RetargetAlgorithm::Lwma { window } => (*window).max(1),
+        }
+    }
+
+    /// The algorithm's estimate of the current block time in ms, from the
+    /// gaps between the last `window + 1` of `recent_blocks` (oldest first)
+    /// None with fewer than two blocks
+    pub fn observed_block_time(&self, recent_blocks: &[Block]) -> Option<f64> {
+        let start = recent_blocks.len().saturating_sub(self.window() + 1);
+        let times: Vec<f64> = recent_blocks[start..]
+            .windows(2)
+            .map(|pair| pair[1].timestamp.saturating_sub(pair[0].timestamp) as f64)
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+
+        Some(match self {
+            RetargetAlgorithm::SimpleWindow { .. } => times.iter().sum::<f64>() / times.len() as f64,
+            RetargetAlgorithm::Ema { .. } => {
+                let alpha = 2.0 / (self.window() as f64 + 1.0);
+                times[1..].iter().fold(times[0], |ema, time| alpha * time + (1.0 - alpha) * ema)
+            }
+            RetargetAlgorithm::Lwma { .. } => {
+                let weighted: f64 = times.iter().enumerate().map(|(i, time)| (i + 1) as f64 * time).sum();
+                let weights = (times.len() * (times.len() + 1) / 2) as f64;
+                weighted / weights
+            }
+        })
+    }
+
+    /// Difficulty for the block after `recent_blocks`, moving `current` by
+    /// log16(target / observed) steps, rounded, within `1..=MAX_DIFFICULTY`
+    /// Without block times to go on, `current` is kept
+    pub fn next_difficulty(&self, recent_blocks: &[Block], target_ms: u128, current: u32) -> u32 {
+        let Some(observed) = self.observed_block_time(recent_blocks) else {
+            return current;
+        };
+        let steps = (target_ms as f64 / observed.max(1.0)).log(16.0).round();
+        (current as f64 + steps).clamp(1.0, MAX_DIFFICULTY as f64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET_MS: u128 = 1_000;
+
+    /// Blocks spaced by `times` ms, after a first block at t = 0
+    fn blocks_with_times(times: &[u128]) -> Vec<Block> {
+        let mut timestamp = 0;
+        let mut blocks = vec![Block::new(0, timestamp, Vec::new(), String::from("0"), 4)];
+        for (i, time) in times.iter().enumerate() {
+            timestamp += time;
+            blocks.push(Block::new(i as u64 + 1, timestamp, Vec::new(), String::from("0"), 4));
+        }
+        blocks
+    }
+
+    fn algorithms() -> [RetargetAlgorithm; 3] {
+        [
+            RetargetAlgorithm::SimpleWindow { window: 10 },
+            RetargetAlgorithm::Lwma { window: 10 },
+            RetargetAlgorithm::Ema { window: 10 },
+        ]
+    }
+
+    #[test]
+    fn test_sudden_hashrate_drop_lowers_difficulty_by_different_amounts() {
+        // Eight blocks on target, then the hashrate falls 65536-fold (four difficulty steps)
+        let mut times = vec![TARGET_MS; 8];
+        times.extend([TARGET_MS * 65_536; 2]);
+        let blocks = blocks_with_times(&times);
+
+        let observed: Vec<f64> = algorithms().iter().map(|a| a.observed_block_time(&blocks).unwrap()).collect();
+        let next: Vec<u32> = algorithms().iter().map(|a| a.next_difficulty(&blocks, TARGET_MS, 8)).collect();
+
+        // All react; the weighted averages see the slow blocks more than the plain one
+        assert!(next.iter().all(|&difficulty| difficulty < 8), "{:?}", next);
+        assert!(observed[0] < observed[1].min(observed[2]), "{:?}", observed);
+        assert!(observed[1] != observed[2], "{:?}", observed);
+        assert_eq!(next, vec![5, 4, 4]);
+
+        // A lasting hashrate spike raises the difficulty instead
+        let mut times = vec![TARGET_MS; 2];
+        times.extend([TARGET_MS / 256; 8]);
+        let spike = blocks_with_times(&times);
+        assert!(algorithms().iter().all(|a| a.next_difficulty(&spike, TARGET_MS, 3) > 3));
+    }
+
+    #[test]
+    fn test_on_target_blocks_keep_difficulty() {
+        let blocks = blocks_with_times(&[TARGET_MS; 12]);
+        for algorithm in algorithms() {
+            assert_eq!(algorithm.next_difficulty(&blocks, TARGET_MS, 3), 3);
+            assert_eq!(algorithm.next_difficulty(&blocks[..1], TARGET_MS, 3), 3);
+        }
+
+        // Never below 1, however slow the blocks
+        let slow = blocks_with_times(&[TARGET_MS * 1_000_000]);
+        assert_eq!(RetargetAlgorithm::SimpleWindow { window: 5 }.next_difficulty(&slow, TARGET_MS, 2), 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Step-by-step mining for teaching consensus
+//!
+//! `ChainStepper` mines one block per `step` and remembers what it changed,
+//! so the chain can be inspected between blocks and `rewind` can put the last
+//! block's transactions back in the mempool exactly as they were.
+
+use crate::block::Block;
+use crate::blockchain::Blockchain;
+use crate::transaction::Transaction;
+use std::time::{Duration, Instant};
+
+/// What one `ChainStepper::step` added to the chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepReport {
+    pub index: u64,
+    pub hash: String,
+    pub previous_hash: String,
+    pub nonce: u64,
+    /// Block timestamp (ms since the Unix epoch, from the chain's clock)
+    pub timestamp: u128,
+    /// Wall-clock time spent mining the block
+    pub elapsed: Duration,
+    pub transactions: usize,
+    /// Coinbase amount in base units (0 when no miner address is set)
+    pub reward: u64,
+}
+
+/// Undo record for one step: the tip it produced, and the mempool and
+/// difficulty before it
+#[derive(Debug, Clone)]
+pub struct StepUndo {
+    tip: String,
+    pending: Vec<Transaction>,
+    difficulty: u32,
+}
+
+/// A `Blockchain` that is mined one block at a time and can be rewound
+pub struct ChainStepper {
+    blockchain: Blockchain,
+    miner_address: Option<String>,
+    history: Vec<StepUndo>,
+}
+
+impl ChainStepper {
+    pub fn new(blockchain: Blockchain) -> Self {
+        Self::resume(blockchain, Vec::new())
+    }
+
+    /// Picks up a stepper taken apart with `into_parts`
+    pub fn resume(blockchain: Blockchain, history: Vec<StepUndo>) -> Self {
+        ChainStepper { blockchain, miner_address: None, history }
+    }
+
+    /// Pays each stepped block's reward to `address`
+    pub fn with_miner(mut self, address: impl Into<String>) -> Self {
+        self.miner_address = Some(address.into());
+        self
+    }
+
+    pub fn blockchain(&self) -> &Blockchain {
+        &self.blockchain
+    }
+
+    /// Number of steps that can be rewound
+    pub fn steps(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn into_parts(self) -> (Blockchain, Vec<StepUndo>) {
+        (self.blockchain, self.history)
+    }
+
+    /// Mines exactly one block from the mempool
+    /// Returns None, and mines nothing, if no transactions are pending
+    pub fn step(&mut self) -> Option<StepReport> {
+        if self.blockchain.pending_transaction_count() == 0 {
+            return None;
+        }
+
+        let pending = self.blockchain.pending_transactions.clone();
+        let difficulty = self.blockchain.difficulty;
+        let start = Instant::now();
+        let block = match &self.miner_address {
+            Some(address) => self.blockchain.mine_block_to(address),
+            None => self.blockchain.mine_block(),
+        };
+        let elapsed = start.elapsed();
+
+        self.history.push(StepUndo { tip: block.hash.clone(), pending, difficulty });
+
+        Some(StepReport {
+            index: block.index,
+            hash: block.hash.clone(),
+            previous_hash: block.previous_hash.clone(),
+            nonce: block.nonce,
+            timestamp: block.timestamp,
+            elapsed,
+            transactions: block.transaction_count(),
+            reward: block.coinbase().map_or(0, |coinbase| coinbase.amount),
+        })
+    }
+
+    /// Undoes the last step: removes its block and restores the mempool, and
+    /// the difficulty if the block retargeted it
+    /// Fails if nothing was stepped or the tip is no longer the stepped block
+    pub fn rewind(&mut self) -> Result<Block, String> {
+        let undo = self.history.last().ok_or_else(|| String::from("Nothing to rewind"))?;
+        if undo.tip != self.blockchain.tip_hash() {
+            self.history.clear();
+            return Err(String::from("The chain has changed since the last step; step history cleared"));
+        }
+
+        let undo = self.history.pop().expect("checked above");
+        let block = self.blockchain.pop_block().expect("a stepped block is never genesis");
+        self.blockchain.pending_transactions = undo.pending;
+        self.blockchain.difficulty = undo.difficulty;
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::retarget::RetargetAlgorithm;
+    use crate::transaction::COIN;
+
+    fn stepper() -> ChainStepper {
+        let mut blockchain = Blockchain::with_clock(Clock::stepping(1_700_000_000_000, 1));
+        blockchain.set_difficulty(1);
+        // Room for the coinbase and one transfer, so each step takes one transfer
+        blockchain.max_transactions_per_block = 2;
+        for receiver in ["Bob", "Carol", "Dave"] {
+            blockchain.add_transaction(String::from("Alice"), String::from(receiver), COIN).unwrap();
+        }
+        ChainStepper::new(blockchain).with_miner("Miner")
+    }
+
+    #[test]
+    fn test_step_grows_chain_one_block_at_a_time() {
+        let mut stepper = stepper();
+        for n in 1..=3 {
+            let report = stepper.step().unwrap();
+            assert_eq!(report.index, n);
+            assert_eq!(report.hash, stepper.blockchain().tip_hash());
+            assert_eq!(report.reward, stepper.blockchain().block_reward);
+            assert_eq!(stepper.blockchain().len(), 1 + n as usize);
+        }
+
+        // The mempool is empty, so there is nothing left to step
+        assert_eq!(stepper.step(), None);
+        assert_eq!(stepper.steps(), 3);
+        assert!(stepper.blockchain().is_valid());
+    }
+
+    #[test]
+    fn test_rewind_restores_previous_tip() {
+        let mut stepper = stepper();
+        stepper.step().unwrap();
+        let tip = stepper.blockchain().tip_hash().to_string();
+        let pending = stepper.blockchain().get_pending_transactions().clone();
+
+        let report = stepper.step().unwrap();
+        let rewound = stepper.rewind().unwrap();
+        assert_eq!(rewound.hash, report.hash);
+        assert_eq!(stepper.blockchain().tip_hash(), tip);
+        assert_eq!(stepper.blockchain().get_pending_transactions(), &pending);
+
+        stepper.rewind().unwrap();
+        assert_eq!(stepper.blockchain().len(), 1);
+        assert_eq!(stepper.blockchain().pending_transaction_count(), 3);
+        assert!(stepper.rewind().is_err());
+    }
+
+    #[test]
+    fn test_rewind_restores_retargeted_difficulty() {
+        // Blocks hundreds of times slower than the 1 ms target
+        let mut blockchain = Blockchain::with_clock(Clock::stepping(1_700_000_000_000, 250));
+        blockchain.set_difficulty(3);
+        blockchain.retarget = Some(RetargetAlgorithm::Lwma { window: 5 });
+        blockchain.target_block_time_ms = 1;
+        for receiver in ["Bob", "Carol"] {
+            blockchain.add_transaction(String::from("Alice"), String::from(receiver), COIN).unwrap();
+        }
+        blockchain.max_transactions_per_block = 1;
+        let mut stepper = ChainStepper::new(blockchain);
+
+        // The first block has no block time to go on; the second retargets
+        stepper.step().unwrap();
+        assert_eq!(stepper.blockchain().get_difficulty(), 3);
+        stepper.step().unwrap();
+        let floor = stepper.blockchain().min_difficulty;
+        assert_eq!(stepper.blockchain().get_difficulty(), floor);
+
+        stepper.rewind().unwrap();
+        assert_eq!(stepper.blockchain().get_difficulty(), 3);
+        stepper.step().unwrap();
+        assert_eq!(stepper.blockchain().get_difficulty(), floor);
+        assert!(stepper.blockchain().is_valid());
+    }
+}
@@ -0,0 +1,237 @@
+//! Streaming storage for chains too large to hold in memory
+//!
+//! `save` / `load` in the CLI write the whole `Blockchain` as one JSON
+//! document. The functions here use newline-delimited JSON instead: one block
+//! per line, genesis first, so a chain can be written and read back a block
+//! at a time.
+//!
+//! `NodeState` is the other way round: one JSON file holding a whole working
+//! session, with the mempool and node-local settings next to the chain.
+
+use crate::block::Block;
+use crate::blockchain::{BlockVerifier, Blockchain};
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// Writes `blocks` as NDJSON, one block per line
+pub fn save_streaming<'a, W: Write>(
+    mut writer: W,
+    blocks: impl IntoIterator<Item = &'a Block>,
+) -> Result<(), String> {
+    for block in blocks {
+        serde_json::to_writer(&mut writer, block)
+            .map_err(|e| format!("Failed to write block #{}: {}", block.index, e))?;
+        writer.write_all(b"\n")
+            .map_err(|e| format!("Failed to write block #{}: {}", block.index, e))?;
+    }
+    writer.flush().map_err(|e| format!("Failed to flush: {}", e))
+}
+
+/// Reads an NDJSON block file lazily, checking each block as it arrives
+///
+/// Blocks go through a `BlockVerifier` under the default consensus rules, so
+/// each is checked as `Blockchain::verify_block_against_tip` would check it,
+/// and against the last block accepted before it. A line that can't be read
+/// or fails a check yields an `Err` and iteration carries on; since a
+/// rejected block is never accepted, the blocks after it fail their link
+/// check too. Blank lines are ignored.
+pub fn load_streaming<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Block, String>> {
+    let mut verifier = BlockVerifier::new(Blockchain::new());
+
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(move |(line_number, line)| {
+            let block = line
+                .map_err(|e| e.to_string())
+                .and_then(|line| serde_json::from_str::<Block>(&line).map_err(|e| e.to_string()))
+                .map_err(|e| format!("Line {}: {}", line_number + 1, e))?;
+            verifier
+                .push(&block)
+                .map(|()| block)
+                .map_err(|e| format!("Line {}: {}", line_number + 1, e))
+        })
+}
+
+/// Node-local settings saved by `NodeState`; difficulty and checkpoints
+/// travel inside the saved `Blockchain`, so they have one source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeConfig {
+    /// Address paid for blocks this node mines, if one was chosen
+    pub miner_address: Option<String>,
+}
+
+/// A whole working session: the chain, the mempool and node-local settings
+/// Unlike a plain chain save, restoring it brings the node back as it was
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeState {
+    pub blockchain: Blockchain,
+    pub mempool: Vec<Transaction>,
+    pub config: NodeConfig,
+}
+
+impl NodeState {
+    /// Captures `blockchain` and the node's miner address
+    pub fn capture(blockchain: &Blockchain, miner_address: Option<&str>) -> Self {
+        let mut blockchain = blockchain.clone();
+        let mempool = std::mem::take(&mut blockchain.pending_transactions);
+        let config = NodeConfig { miner_address: miner_address.map(String::from) };
+        NodeState { blockchain, mempool, config }
+    }
+
+    /// The chain with its mempool put back, and the miner address
+    /// The mempool is requeued through `set_pending`, so a saved transaction
+    /// the chain would refuse fails the restore instead of slipping in
+    pub fn restore(self) -> Result<(Blockchain, Option<String>), String> {
+        let mut blockchain = self.blockchain;
+        blockchain.set_pending(self.mempool).map_err(|errors| {
+            let reasons: Vec<String> = errors.iter().map(|(i, e)| format!("#{}: {}", i + 1, e)).collect();
+            format!("Saved mempool has rejected transactions ({})", reasons.join("; "))
+        })?;
+        Ok((blockchain, self.config.miner_address))
+    }
+
+    /// Writes the state to `path` as JSON
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Serialization failed: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write to '{}': {}", path.display(), e))
+    }
+
+    /// Reads a state written by `save_state`, checking its consensus
+    /// parameters and each block's stored hash and Merkle root as
+    /// `Blockchain::from_json` does
+    pub fn load_state(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read from '{}': {}", path.display(), e))?;
+        let mut state: NodeState = serde_json::from_str(&json)
+            .map_err(|e| format!("Deserialization failed: {}", e))?;
+        state.blockchain.check_loaded()?;
+        Ok(state)
+    }
+}
+
+#[cfg(all(test, feature = "mining"))]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+    use crate::transaction::COIN;
+    use std::io::Cursor;
+
+    fn sample_chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for receiver in ["Bob", "Carol", "Dave"] {
+            blockchain.add_transaction(String::from("Alice"), String::from(receiver), COIN).unwrap();
+            blockchain.mine_block();
+        }
+        blockchain
+    }
+
+    fn to_ndjson(blockchain: &Blockchain) -> String {
+        let mut buffer = Vec::new();
+        save_streaming(&mut buffer, blockchain.blocks()).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_streamed_load_matches_full_load() {
+        let blockchain = sample_chain();
+        let ndjson = to_ndjson(&blockchain);
+        assert_eq!(ndjson.lines().count(), blockchain.len());
+
+        let full: Blockchain = serde_json::from_str(&serde_json::to_string(&blockchain).unwrap()).unwrap();
+        let streamed: Vec<Block> = load_streaming(Cursor::new(ndjson))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let full_hashes: Vec<&String> = full.blocks().map(|b| &b.hash).collect();
+        let streamed_hashes: Vec<&String> = streamed.iter().map(|b| &b.hash).collect();
+        assert_eq!(streamed_hashes, full_hashes);
+        assert!(full.is_valid());
+    }
+
+    #[test]
+    fn test_node_state_round_trip() {
+        let mut blockchain = sample_chain();
+        blockchain.set_difficulty(2);
+        blockchain.add_checkpoint(2).unwrap();
+        blockchain.add_transaction(String::from("Bob"), String::from("Carol"), COIN).unwrap();
+        blockchain.add_pending_transaction(
+            Transaction::new(String::from("Dave"), String::from("Erin"), COIN).unwrap().with_fee(10),
+        ).unwrap();
+
+        let path = std::env::temp_dir().join(format!("rustchain-state-{}.json", std::process::id()));
+        NodeState::capture(&blockchain, Some("Miner")).save_state(&path).unwrap();
+        let loaded = NodeState::load_state(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let (restored, miner) = loaded.unwrap().restore().unwrap();
+        let ids = |chain: &Blockchain| chain.pending_transactions.iter().map(Transaction::id).collect::<Vec<_>>();
+        assert_eq!(ids(&restored), ids(&blockchain));
+        assert_eq!(restored.difficulty, 2);
+        assert_eq!(restored.checkpoints, blockchain.checkpoints);
+        assert_eq!(restored.tip_hash(), blockchain.tip_hash());
+        assert_eq!(miner.as_deref(), Some("Miner"));
+        assert!(restored.contains_transaction(&blockchain.chain[1].transactions[0].id()));
+        assert!(restored.is_valid());
+    }
+
+    #[test]
+    fn test_restore_refuses_a_mempool_the_chain_would_reject() {
+        let mut blockchain = sample_chain();
+        blockchain.add_transaction(String::from("Bob"), String::from("Carol"), COIN).unwrap();
+        let mut state = NodeState::capture(&blockchain, None);
+        state.mempool.push(Transaction::new(String::from("Bob"), String::from("Dave"), COIN).unwrap().with_nonce(9));
+
+        let error = state.restore().unwrap_err();
+        assert!(error.contains("#2"), "{}", error);
+    }
+
+    #[test]
+    fn test_corrupt_line_yields_err_and_iteration_continues() {
+        let blockchain = sample_chain();
+        let mut lines: Vec<String> = to_ndjson(&blockchain).lines().map(String::from).collect();
+        lines[1] = String::from("{ not a block");
+
+        // A parseable block whose contents no longer match its hash
+        let mut tampered = blockchain.chain[3].clone();
+        tampered.transactions[0].amount = 999 * COIN;
+        lines[3] = serde_json::to_string(&tampered).unwrap();
+
+        let results: Vec<Result<Block, String>> = load_streaming(Cursor::new(lines.join("\n"))).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        assert!(results[1].as_ref().unwrap_err().starts_with("Line 2:"));
+        // Block 2 follows the unreadable line, so it doesn't link to anything accepted
+        assert!(results[2].as_ref().unwrap_err().starts_with("Line 3:"));
+        assert!(results[3].is_err());
+    }
+
+    #[test]
+    fn test_streamed_blocks_get_the_tip_rules() {
+        let blockchain = sample_chain();
+        let mut lines: Vec<String> = to_ndjson(&blockchain).lines().map(String::from).collect();
+
+        // A parseable block whose contents no longer match its hash
+        let mut tampered = blockchain.chain[3].clone();
+        tampered.transactions[0].amount = 999 * COIN;
+        lines[3] = serde_json::to_string(&tampered).unwrap();
+        let results: Vec<Result<Block, String>> = load_streaming(Cursor::new(lines.join("\n"))).collect();
+        assert!(results[..3].iter().all(Result::is_ok));
+        assert!(results[3].as_ref().unwrap_err().contains("Invalid hash"));
+
+        // A well-formed, re-mined block that reuses Alice's first nonce
+        let mut replayed = blockchain.chain[3].clone();
+        replayed.transactions = vec![Transaction::new(String::from("Alice"), String::from("Eve"), COIN).unwrap().with_nonce(1)];
+        replayed.hash = replayed.calculate_hash();
+        replayed.mine_block_with_algorithm(blockchain.pow_prefix_char(), blockchain.pow_algorithm);
+        lines[3] = serde_json::to_string(&replayed).unwrap();
+        let results: Vec<Result<Block, String>> = load_streaming(Cursor::new(lines.join("\n"))).collect();
+        assert!(results[3].as_ref().unwrap_err().contains("nonce"), "{:?}", results[3]);
+    }
+}
@@ -1,57 +1,452 @@
+use crate::crypto::calculate_hash;
+#[cfg(feature = "utxo")]
+use crate::utxo::{OutPoint, TxOut};
 use std::fmt;
 use serde::{Deserialize, Serialize};
 
+/// Maximum memo size in bytes (the same budget as Bitcoin's OP_RETURN)
+pub const MAX_MEMO_BYTES: usize = 80;
+
+/// Base units per coin; amounts and fees are whole base units (like satoshis)
+pub const COIN: u64 = 100_000_000;
+
+/// Largest amount any transfer may carry: the 21 million coins that will ever
+/// exist. Chains may set a lower `max_amount`, checked when a transfer is queued
+pub const MAX_AMOUNT: u64 = 21_000_000 * COIN;
+
+/// Decimal places shown for an amount (base units are 10^-8 of a coin)
+pub const AMOUNT_DECIMALS: usize = 8;
+
+/// Sender of block reward transactions; reserved, so nobody can spend from it
+pub const COINBASE_SENDER: &str = "COINBASE";
+
+/// Parses a decimal coin amount such as "10.5" or "1,000.50" into base units, exactly
+/// Commas may group the whole part in threes. Rejects signs, exponents, words
+/// like "inf" or "NaN", more than `AMOUNT_DECIMALS` decimals, and overflow
+pub fn parse_amount(text: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid amount '{}': expected a decimal number of coins", text);
+    if text.trim().is_empty() {
+        return Err(String::from("Invalid amount: it is empty"));
+    }
+
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    let whole = if whole.contains(',') {
+        let mut groups = whole.split(',');
+        let first_ok = groups.next().is_some_and(|group| (1..=3).contains(&group.len()));
+        if !first_ok || !groups.all(|group| group.len() == 3) {
+            return Err(format!("Invalid amount '{}': commas must separate groups of three digits", text));
+        }
+        whole.replace(',', "")
+    } else {
+        whole.to_string()
+    };
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if fraction.len() > AMOUNT_DECIMALS {
+        return Err(format!(
+            "Invalid amount '{}': at most {} decimal places are supported",
+            text, AMOUNT_DECIMALS
+        ));
+    }
+
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let fraction: u64 = format!("{:0<width$}", fraction, width = AMOUNT_DECIMALS)
+        .parse()
+        .map_err(|_| invalid())?;
+
+    whole
+        .checked_mul(COIN)
+        .and_then(|units| units.checked_add(fraction))
+        .ok_or_else(|| format!("Invalid amount '{}': too large", text))
+}
+
+/// Formats base units as coins, trimming trailing zeros past two decimals
+/// e.g. 1_050_000_000 -> "10.50", 1 -> "0.00000001"
+pub fn format_amount(units: u64) -> String {
+    format_units(units as u128)
+}
+
+/// Formats a signed balance in base units (see `format_amount`)
+pub fn format_balance(units: i128) -> String {
+    let magnitude = format_units(units.unsigned_abs());
+    if units < 0 { format!("-{}", magnitude) } else { magnitude }
+}
+
+fn format_units(units: u128) -> String {
+    let coin = COIN as u128;
+    let mut text = format!("{}.{:0width$}", units / coin, units % coin, width = AMOUNT_DECIMALS);
+    let min_len = text.len() - AMOUNT_DECIMALS + 2;
+    while text.len() > min_len && text.ends_with('0') {
+        text.pop();
+    }
+    text
+}
+
+/// Why a transaction was rejected, by `Transaction::new` or the mempool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    EmptySender,
+    EmptyReceiver,
+    /// The sender is `COINBASE_SENDER`, which only block rewards may use
+    ReservedSender,
+    SameParty,
+    NonPositiveAmount,
+    MemoTooLong { len: usize, limit: usize },
+    /// The amount is above `MAX_AMOUNT`, or outside the chain's configured
+    /// bounds (base units)
+    AmountOutOfRange { amount: u64, min: u64, max: u64 },
+    /// The account nonce isn't the sender's next one
+    BadNonce { sender: String, expected: u64, got: u64 },
+    /// The sender or receiver is on the chain's blocklist
+    BlockedAddress { address: String },
+    /// The inputs and outputs don't form a valid spend (see `utxo::UtxoError`)
+    #[cfg(feature = "utxo")]
+    InvalidSpend(String),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::EmptySender => write!(f, "Sender cannot be empty"),
+            TransactionError::EmptyReceiver => write!(f, "Receiver cannot be empty"),
+            TransactionError::ReservedSender => {
+                write!(f, "{} is reserved for block rewards", COINBASE_SENDER)
+            }
+            TransactionError::SameParty => write!(f, "Sender and receiver cannot be the same"),
+            TransactionError::NonPositiveAmount => write!(f, "Amount must be greater than zero"),
+            TransactionError::MemoTooLong { len, limit } => {
+                write!(f, "Memo is {} bytes; the limit is {} bytes", len, limit)
+            }
+            TransactionError::AmountOutOfRange { amount, min, max } => write!(
+                f,
+                "Amount {} is outside the allowed range {} to {}",
+                format_amount(*amount),
+                format_amount(*min),
+                format_amount(*max)
+            ),
+            TransactionError::BadNonce { sender, expected, got } => {
+                write!(f, "Nonce {} for {} is out of order; expected {}", got, sender, expected)
+            }
+            TransactionError::BlockedAddress { address } => write!(f, "Address {} is blocked", address),
+            #[cfg(feature = "utxo")]
+            TransactionError::InvalidSpend(reason) => write!(f, "Invalid spend: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
 /// Represents a transaction in the blockchain
 /// Transfers amount from sender to receiver
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub sender: String,
     pub receiver: String,
-    pub amount: f64,
+    /// Amount in base units (see `COIN`)
+    pub amount: u64,
+    /// Fee offered to the miner, in base units; higher fees are mined first
+    #[serde(default)]
+    pub fee: u64,
+    /// Optional short note attached to the transfer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Per-sender sequence number (1, 2, 3, ...) so a transfer can't be replayed
+    /// into a later block; 0 means unassigned. Unrelated to the block's mining nonce
+    #[serde(default)]
+    pub nonce: u64,
+    /// When this node queued the transaction (ms since the Unix epoch), from
+    /// its own clock. Mempool bookkeeping only: not part of the hash preimage,
+    /// and never sent or saved, so no peer or client can pick its own age
+    #[serde(skip)]
+    pub created_at: u128,
+    /// Position in this node's arrival order (1, 2, 3, ...; 0 if never queued),
+    /// so the latest arrival is known even when `created_at` ties. Mempool
+    /// bookkeeping like `created_at`
+    #[serde(skip)]
+    pub arrival: u64,
+    /// Earlier outputs this transfer spends (UTXO model; empty for account transfers)
+    #[cfg(feature = "utxo")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<OutPoint>,
+    /// New outputs it creates, change included
+    #[cfg(feature = "utxo")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<TxOut>,
 }
 
 impl Transaction {
     /// Creates a new transaction with validation
-    pub fn new(sender: String, receiver: String, amount: f64) -> Result<Self, String> {
-        // Validate transaction
-        if sender.is_empty() {
-            return Err("Sender cannot be empty".to_string());
+    pub fn new(sender: String, receiver: String, amount: u64) -> Result<Self, TransactionError> {
+        Transaction::builder().sender(sender).receiver(receiver).amount(amount).build()
+    }
+
+    /// Starts a transaction with optional fields (memo, fee, nonce, ...);
+    /// `build` validates it like `new`
+    pub fn builder() -> TransactionBuilder {
+        TransactionBuilder {
+            transaction: Transaction {
+                sender: String::new(),
+                receiver: String::new(),
+                amount: 0,
+                fee: 0,
+                memo: None,
+                nonce: 0,
+                created_at: 0,
+                arrival: 0,
+                #[cfg(feature = "utxo")]
+                inputs: Vec::new(),
+                #[cfg(feature = "utxo")]
+                outputs: Vec::new(),
+            },
+        }
+    }
+
+    /// Checks the rules `new` and `with_memo` enforce, for a transaction
+    /// built elsewhere (e.g. received from another node)
+    pub fn validate(&self) -> Result<(), TransactionError> {
+        if self.sender.is_empty() {
+            return Err(TransactionError::EmptySender);
+        }
+        if self.sender == COINBASE_SENDER {
+            return Err(TransactionError::ReservedSender);
+        }
+        if self.receiver.is_empty() {
+            return Err(TransactionError::EmptyReceiver);
+        }
+        if self.sender == self.receiver {
+            return Err(TransactionError::SameParty);
         }
-        if receiver.is_empty() {
-            return Err("Receiver cannot be empty".to_string());
+        if self.amount == 0 {
+            return Err(TransactionError::NonPositiveAmount);
         }
-        if sender == receiver {
-            return Err("Sender and receiver cannot be the same".to_string());
+        if self.amount > MAX_AMOUNT {
+            return Err(TransactionError::AmountOutOfRange { amount: self.amount, min: 1, max: MAX_AMOUNT });
         }
-        if amount <= 0.0 {
-            return Err("Amount must be greater than zero".to_string());
+        if let Some(memo) = &self.memo
+            && memo.len() > MAX_MEMO_BYTES
+        {
+            return Err(TransactionError::MemoTooLong { len: memo.len(), limit: MAX_MEMO_BYTES });
         }
+        Ok(())
+    }
 
-        Ok(Transaction {
-            sender,
-            receiver,
+    /// Block reward paying `amount` to `miner`
+    /// The memo records the block height, so every reward has its own id
+    pub fn coinbase(miner: String, amount: u64, height: u64) -> Self {
+        Transaction {
+            sender: COINBASE_SENDER.to_string(),
+            receiver: miner,
             amount,
-        })
+            fee: 0,
+            memo: Some(format!("block {}", height)),
+            nonce: 0,
+            created_at: 0,
+            arrival: 0,
+            #[cfg(feature = "utxo")]
+            inputs: Vec::new(),
+            #[cfg(feature = "utxo")]
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Whether this is a block reward rather than a transfer
+    pub fn is_coinbase(&self) -> bool {
+        self.sender == COINBASE_SENDER
+    }
+
+    /// What the sender pays: the amount plus the fee
+    pub fn total_cost(&self) -> u64 {
+        self.amount.saturating_add(self.fee)
+    }
+
+    /// Sets the sender's account nonce (normally assigned by `add_pending_transaction`)
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Makes this a UTXO-model spend of `inputs` into `outputs`
+    #[cfg(feature = "utxo")]
+    pub fn with_utxos(mut self, inputs: Vec<OutPoint>, outputs: Vec<TxOut>) -> Self {
+        self.inputs = inputs;
+        self.outputs = outputs;
+        self
+    }
+
+    /// Attaches a miner fee (in base units) to the transaction
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Attaches a memo of at most `MAX_MEMO_BYTES` bytes
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Result<Self, TransactionError> {
+        let memo = memo.into();
+        if memo.len() > MAX_MEMO_BYTES {
+            return Err(TransactionError::MemoTooLong { len: memo.len(), limit: MAX_MEMO_BYTES });
+        }
+        self.memo = Some(memo);
+        Ok(self)
+    }
+
+    /// Returns the canonical string that is hashed for this transaction
+    /// The fee, memo, nonce and UTXO inputs/outputs are only appended when set,
+    /// so plain transactions hash as before
+    pub fn hash_preimage(&self) -> String {
+        let mut preimage = format!("{}{}{}", self.sender, self.receiver, self.amount);
+        if self.fee > 0 {
+            preimage.push_str(&format!("fee:{}", self.fee));
+        }
+        if let Some(memo) = &self.memo {
+            preimage.push_str(&format!("memo:{}", memo));
+        }
+        if self.nonce > 0 {
+            preimage.push_str(&format!("nonce:{}", self.nonce));
+        }
+        #[cfg(feature = "utxo")]
+        {
+            for input in &self.inputs {
+                preimage.push_str(&format!("in:{}", input));
+            }
+            for output in &self.outputs {
+                preimage.push_str(&format!("out:{}:{}", output.address, output.amount));
+            }
+        }
+        preimage
+    }
+
+    /// Approximate encoded size: the byte lengths of all fields summed
+    pub fn serialized_size(&self) -> usize {
+        let size = self.sender.len()
+            + self.receiver.len()
+            + size_of::<u64>() * 3
+            + self.memo.as_ref().map_or(0, String::len)
+            + size_of::<u128>();
+        #[cfg(feature = "utxo")]
+        let size = size
+            + self.inputs.iter().map(|input| input.txid.len() + size_of::<u32>()).sum::<usize>()
+            + self.outputs.iter().map(|output| output.address.len() + size_of::<u64>()).sum::<usize>();
+        size
+    }
+
+    /// Returns the transaction id (SHA-256 of the canonical preimage)
+    pub fn id(&self) -> String {
+        calculate_hash(&self.hash_preimage())
     }
 
     /// Creates a transaction without validation (for testing only)
     #[cfg(test)]
-    pub fn new_unvalidated(sender: String, receiver: String, amount: f64) -> Self {
+    pub fn new_unvalidated(sender: String, receiver: String, amount: u64) -> Self {
         Transaction {
             sender,
             receiver,
             amount,
+            fee: 0,
+            memo: None,
+            nonce: 0,
+            created_at: 0,
+            arrival: 0,
+            #[cfg(feature = "utxo")]
+            inputs: Vec::new(),
+            #[cfg(feature = "utxo")]
+            outputs: Vec::new(),
         }
     }
 }
 
+/// Builds a `Transaction` field by field, from `Transaction::builder`
+/// Unset fields stay empty or zero, so a missing sender, receiver or amount
+/// is reported by `build`
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    transaction: Transaction,
+}
+
+impl TransactionBuilder {
+    pub fn sender(mut self, sender: impl Into<String>) -> Self {
+        self.transaction.sender = sender.into();
+        self
+    }
+
+    pub fn receiver(mut self, receiver: impl Into<String>) -> Self {
+        self.transaction.receiver = receiver.into();
+        self
+    }
+
+    /// Amount in base units
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.transaction.amount = amount;
+        self
+    }
+
+    /// Miner fee in base units
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.transaction.fee = fee;
+        self
+    }
+
+    /// Memo of at most `MAX_MEMO_BYTES` bytes (checked by `build`)
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.transaction.memo = Some(memo.into());
+        self
+    }
+
+    /// Sender's account nonce; left at 0, `add_pending_transaction` assigns one
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.transaction.nonce = nonce;
+        self
+    }
+
+    /// Makes this a UTXO-model spend of `inputs` into `outputs`
+    #[cfg(feature = "utxo")]
+    pub fn utxos(mut self, inputs: Vec<OutPoint>, outputs: Vec<TxOut>) -> Self {
+        self.transaction.inputs = inputs;
+        self.transaction.outputs = outputs;
+        self
+    }
+
+    /// The transaction, if it passes `Transaction::validate`
+    pub fn build(self) -> Result<Transaction, TransactionError> {
+        self.transaction.validate()?;
+        Ok(self.transaction)
+    }
+}
+
+/// Two transactions are equal when they say the same thing; the mempool
+/// bookkeeping (`created_at`, `arrival`) differs from node to node and is ignored
+impl PartialEq for Transaction {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "utxo")]
+        if self.inputs != other.inputs || self.outputs != other.outputs {
+            return false;
+        }
+        self.sender == other.sender
+            && self.receiver == other.receiver
+            && self.amount == other.amount
+            && self.fee == other.fee
+            && self.memo == other.memo
+            && self.nonce == other.nonce
+    }
+}
+
 impl fmt::Display for Transaction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} -> {} : {:.2}",
-            self.sender, self.receiver, self.amount
-        )
+            "{} -> {} : {}",
+            self.sender, self.receiver, format_amount(self.amount)
+        )?;
+        if self.fee > 0 {
+            write!(f, " (fee {})", format_amount(self.fee))?;
+        }
+        if let Some(memo) = &self.memo {
+            write!(f, " \"{}\"", memo)?;
+        }
+        Ok(())
     }
 }
 
@@ -64,13 +459,13 @@ mod tests {
         let tx = Transaction::new(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
         assert!(tx.is_ok());
         let tx = tx.unwrap();
         assert_eq!(tx.sender, "Alice");
         assert_eq!(tx.receiver, "Bob");
-        assert_eq!(tx.amount, 10.0);
+        assert_eq!(tx.amount, 10 * COIN);
     }
 
     #[test]
@@ -78,9 +473,19 @@ mod tests {
         let tx = Transaction::new(
             String::from(""),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         );
-        assert!(tx.is_err());
+        assert_eq!(tx.unwrap_err(), TransactionError::EmptySender);
+    }
+
+    #[test]
+    fn test_coinbase_sender_reserved() {
+        let tx = Transaction::new(String::from(COINBASE_SENDER), String::from("Bob"), COIN);
+        assert_eq!(tx.unwrap_err(), TransactionError::ReservedSender);
+
+        let reward = Transaction::coinbase(String::from("Bob"), COIN, 7);
+        assert!(reward.is_coinbase());
+        assert_ne!(reward.id(), Transaction::coinbase(String::from("Bob"), COIN, 8).id());
     }
 
     #[test]
@@ -88,9 +493,9 @@ mod tests {
         let tx = Transaction::new(
             String::from("Alice"),
             String::from(""),
-            10.0,
+            10 * COIN,
         );
-        assert!(tx.is_err());
+        assert_eq!(tx.unwrap_err(), TransactionError::EmptyReceiver);
     }
 
     #[test]
@@ -98,9 +503,9 @@ mod tests {
         let tx = Transaction::new(
             String::from("Alice"),
             String::from("Alice"),
-            10.0,
+            10 * COIN,
         );
-        assert!(tx.is_err());
+        assert_eq!(tx.unwrap_err(), TransactionError::SameParty);
     }
 
     #[test]
@@ -108,19 +513,22 @@ mod tests {
         let tx = Transaction::new(
             String::from("Alice"),
             String::from("Bob"),
-            0.0,
+            0,
         );
-        assert!(tx.is_err());
+        assert_eq!(tx.unwrap_err(), TransactionError::NonPositiveAmount);
+    }
+
+    #[test]
+    fn test_amount_above_supply_cap_rejected() {
+        assert!(Transaction::new(String::from("Alice"), String::from("Bob"), MAX_AMOUNT).is_ok());
+        let built = Transaction::builder().sender("Alice").receiver("Bob").amount(u64::MAX).build();
+        assert_eq!(built.unwrap_err(), TransactionError::AmountOutOfRange { amount: u64::MAX, min: 1, max: MAX_AMOUNT });
     }
 
     #[test]
     fn test_negative_amount_rejected() {
-        let tx = Transaction::new(
-            String::from("Alice"),
-            String::from("Bob"),
-            -10.0,
-        );
-        assert!(tx.is_err());
+        // Amounts are unsigned, so a negative one can't even be parsed
+        assert!(parse_amount("-10").is_err());
     }
 
     #[test]
@@ -128,7 +536,7 @@ mod tests {
         let tx = Transaction::new(
             String::from("Alice"),
             String::from("Bob"),
-            10.5,
+            1_050_000_000,
         ).unwrap();
         let display = format!("{}", tx);
         assert!(display.contains("Alice"));
@@ -141,9 +549,161 @@ mod tests {
         let tx1 = Transaction::new(
             String::from("Alice"),
             String::from("Bob"),
-            10.0,
+            10 * COIN,
         ).unwrap();
         let tx2 = tx1.clone();
         assert_eq!(tx1, tx2);
     }
+
+    #[test]
+    fn test_equality_ignores_mempool_bookkeeping() {
+        let tx1 = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        // The same transfer as queued on another node
+        let mut tx2 = tx1.clone();
+        tx2.created_at = 1_700_000_000_000;
+        tx2.arrival = 7;
+        assert_eq!(tx1, tx2);
+
+        tx2.nonce = 1;
+        assert_ne!(tx1, tx2);
+    }
+
+    #[test]
+    fn test_transaction_fee() {
+        let tx = Transaction::new(
+            String::from("Alice"),
+            String::from("Bob"),
+            10 * COIN,
+        ).unwrap().with_fee(COIN / 2);
+        assert_eq!(tx.fee, 50_000_000);
+        assert!(format!("{}", tx).contains("fee 0.50"));
+    }
+
+    #[test]
+    fn test_transaction_id() {
+        let tx1 = Transaction::new_unvalidated(String::from("Alice"), String::from("Bob"), 10 * COIN);
+        let tx2 = tx1.clone();
+        assert_eq!(tx1.id(), tx2.id());
+        assert_eq!(tx1.id().len(), 64);
+
+        let tx3 = tx1.clone().with_fee(COIN);
+        assert_ne!(tx1.id(), tx3.id());
+    }
+
+    #[test]
+    fn test_transaction_memo() {
+        let tx = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN)
+            .unwrap()
+            .with_memo("coffee")
+            .unwrap();
+
+        assert_eq!(tx.memo.as_deref(), Some("coffee"));
+        assert_eq!(format!("{}", tx), "Alice -> Bob : 10.00 \"coffee\"");
+
+        // A memo is part of the transaction's identity
+        let plain = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        assert_ne!(tx.id(), plain.id());
+
+        // Exactly at the limit is allowed
+        let max = "x".repeat(MAX_MEMO_BYTES);
+        assert!(plain.with_memo(max).is_ok());
+    }
+
+    #[test]
+    fn test_oversized_memo_rejected() {
+        let tx = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+
+        let result = tx.clone().with_memo("x".repeat(MAX_MEMO_BYTES + 1));
+        assert_eq!(result.unwrap_err(), TransactionError::MemoTooLong { len: MAX_MEMO_BYTES + 1, limit: MAX_MEMO_BYTES });
+
+        // The limit is in bytes, not characters
+        let multibyte = "€".repeat(MAX_MEMO_BYTES / 3 + 1);
+        assert!(multibyte.chars().count() < MAX_MEMO_BYTES);
+        assert!(tx.with_memo(multibyte).is_err());
+    }
+
+    #[test]
+    fn test_builder_minimal_matches_new() {
+        let built = Transaction::builder().sender("Alice").receiver("Bob").amount(COIN).build().unwrap();
+        let new = Transaction::new(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+        assert_eq!(built, new);
+        assert_eq!(built.id(), new.id());
+    }
+
+    #[test]
+    fn test_builder_sets_every_field() {
+        let tx = Transaction::builder()
+            .sender("Alice")
+            .receiver("Bob")
+            .amount(5 * COIN)
+            .fee(COIN / 10)
+            .memo("rent")
+            .nonce(3)
+            .build()
+            .unwrap();
+        let expected = Transaction::new(String::from("Alice"), String::from("Bob"), 5 * COIN)
+            .unwrap()
+            .with_fee(COIN / 10)
+            .with_memo("rent")
+            .unwrap()
+            .with_nonce(3);
+        assert_eq!(tx, expected);
+    }
+
+    #[test]
+    fn test_builder_reports_validation_errors() {
+        let missing_amount = Transaction::builder().sender("Alice").receiver("Bob").build();
+        assert_eq!(missing_amount.unwrap_err(), TransactionError::NonPositiveAmount);
+
+        let missing_receiver = Transaction::builder().sender("Alice").amount(COIN).build();
+        assert_eq!(missing_receiver.unwrap_err(), TransactionError::EmptyReceiver);
+
+        let long_memo = Transaction::builder().sender("Alice").receiver("Bob").amount(COIN).memo("x".repeat(81)).build();
+        assert_eq!(long_memo.unwrap_err(), TransactionError::MemoTooLong { len: 81, limit: MAX_MEMO_BYTES });
+    }
+
+    #[test]
+    fn test_parse_and_format_amount() {
+        assert_eq!(parse_amount("10"), Ok(10 * COIN));
+        assert_eq!(parse_amount("10.5"), Ok(1_050_000_000));
+        assert_eq!(parse_amount("0.00000001"), Ok(1));
+        assert_eq!(parse_amount(".5"), Ok(COIN / 2));
+        assert!(parse_amount("0.000000001").is_err());
+        assert!(parse_amount("1e3").is_err());
+        assert!(parse_amount("").is_err());
+        assert!(parse_amount(".").is_err());
+        assert!(parse_amount("184467440738").is_err());
+
+        // Thousands separators, but only in groups of three
+        assert_eq!(parse_amount("1,000.50"), Ok(100_050_000_000));
+        assert_eq!(parse_amount("12,345,678"), Ok(12_345_678 * COIN));
+        assert!(parse_amount("1,00").is_err());
+        assert!(parse_amount(",100").is_err());
+        assert!(parse_amount("1.000,5").is_err());
+
+        // Nothing a float parser would let through
+        for text in ["inf", "-inf", "NaN", "infinity", "-1", "+1", " ", "1 000"] {
+            assert!(parse_amount(text).is_err(), "{:?} should be rejected", text);
+        }
+        assert_eq!(parse_amount("").unwrap_err(), "Invalid amount: it is empty");
+
+        assert_eq!(format_amount(10 * COIN), "10.00");
+        assert_eq!(format_amount(1_050_000_000), "10.50");
+        assert_eq!(format_amount(1), "0.00000001");
+        assert_eq!(format_balance(-(COIN as i128) / 4), "-0.25");
+    }
+
+    #[test]
+    fn test_small_amounts_accumulate_exactly() {
+        // 0.1 and 0.2 alternated 1000 times: exactly 150 coins in base units,
+        // where the same sum in f64 drifts
+        let tenth = parse_amount("0.1").unwrap();
+        let fifth = parse_amount("0.2").unwrap();
+        let total: u64 = (0..1000).map(|i| if i % 2 == 0 { tenth } else { fifth }).sum();
+        assert_eq!(total, 150 * COIN);
+        assert_eq!(format_amount(total), "150.00");
+
+        let float_total: f64 = (0..1000).map(|i| if i % 2 == 0 { 0.1 } else { 0.2 }).sum();
+        assert_ne!(float_total, 150.0);
+    }
 }
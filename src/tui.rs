@@ -0,0 +1,321 @@
+//! Full-screen dashboard over a chain (`tui` feature)
+//!
+//! `run` shows the height, tip hash, mempool, difficulty and a scrolling
+//! block list, redrawn several times a second. Keys mine a block, validate
+//! the chain, or toggle a watch mode that mines the way the CLI's `watch`
+//! command does. Blocks are mined a nonce slice at a time between redraws,
+//! so the view stays live while a block is being found.
+//!
+//! Everything drawn comes from `TuiState`, which is updated from the chain
+//! and can be tested without a terminal.
+
+use crate::block::Block;
+use crate::blockchain::Blockchain;
+use crate::cli::WatchPolicy;
+use crate::crypto::{short_hash, DEFAULT_HASH_LEN};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block as Panel, Borders, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Nonces tried between redraws while a block is being mined
+const MINE_SLICE: u64 = 10_000;
+
+/// How long to wait for a key before redrawing
+const POLL: Duration = Duration::from_millis(100);
+
+/// Key help shown until the first action reports something
+const KEY_HELP: &str = "m: mine  v: validate  w: watch  ↑/↓: scroll  q: quit";
+
+/// What a key asks the dashboard loop to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuiAction {
+    Mine,
+    Validate,
+    ToggleWatch,
+    Quit,
+}
+
+/// One line of the block list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRow {
+    pub index: u64,
+    pub hash: String,
+    pub transactions: usize,
+    pub nonce: u64,
+    pub difficulty: u32,
+}
+
+impl From<&Block> for BlockRow {
+    fn from(block: &Block) -> Self {
+        BlockRow {
+            index: block.index,
+            hash: block.hash.clone(),
+            transactions: block.transaction_count(),
+            nonce: block.nonce,
+            difficulty: block.difficulty,
+        }
+    }
+}
+
+/// Everything the dashboard shows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuiState {
+    pub height: usize,
+    pub tip_hash: String,
+    pub pending: usize,
+    pub difficulty: u32,
+    /// Newest block first
+    pub blocks: Vec<BlockRow>,
+    /// Rows of `blocks` scrolled past
+    pub scroll: usize,
+    /// Whether watch mode mines on its own
+    pub watching: bool,
+    /// Index of the block being mined, if any
+    pub mining: Option<u64>,
+    /// Outcome of the last validation; None until validated, or once the chain changes
+    pub valid: Option<bool>,
+    /// Outcome of the last action
+    pub status: String,
+}
+
+impl TuiState {
+    /// State showing `blockchain` as it is now
+    pub fn new(blockchain: &Blockchain) -> Self {
+        let mut state = TuiState { status: String::from(KEY_HELP), ..TuiState::default() };
+        state.refresh(blockchain);
+        state
+    }
+
+    /// Re-reads the figures and block list from `blockchain`
+    /// A new tip clears the last validation result, which no longer applies
+    pub fn refresh(&mut self, blockchain: &Blockchain) {
+        if blockchain.tip_hash() != self.tip_hash {
+            self.valid = None;
+        }
+        self.height = blockchain.height();
+        self.tip_hash = blockchain.tip_hash().to_string();
+        self.pending = blockchain.pending_transaction_count();
+        self.difficulty = blockchain.get_difficulty();
+        self.blocks = blockchain.blocks().rev().map(BlockRow::from).collect();
+        self.scroll = self.scroll.min(self.blocks.len().saturating_sub(1));
+    }
+
+    /// Notes a newly mined block and refreshes from the chain it joined
+    pub fn on_block_mined(&mut self, blockchain: &Blockchain, block: &Block) {
+        self.mining = None;
+        self.refresh(blockchain);
+        self.status = format!(
+            "Block #{} mined: {} transaction(s), nonce {}",
+            block.index,
+            block.transaction_count(),
+            block.nonce
+        );
+    }
+
+    /// Scrolls for arrow keys; returns the action any other bound key asks for
+    pub fn handle_key(&mut self, key: KeyCode) -> Option<TuiAction> {
+        match key {
+            KeyCode::Char('m') => Some(TuiAction::Mine),
+            KeyCode::Char('v') => Some(TuiAction::Validate),
+            KeyCode::Char('w') => Some(TuiAction::ToggleWatch),
+            KeyCode::Char('q') | KeyCode::Esc => Some(TuiAction::Quit),
+            KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+                None
+            }
+            KeyCode::Down => {
+                self.scroll = (self.scroll + 1).min(self.blocks.len().saturating_sub(1));
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Runs the dashboard until `q`, mining rewards to `miner_address`; watch
+/// mode follows `policy`. Returns a summary line for the caller to print
+pub fn run(blockchain: &mut Blockchain, miner_address: &str, policy: WatchPolicy) -> io::Result<String> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, blockchain, miner_address, policy);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    blockchain: &mut Blockchain,
+    miner_address: &str,
+    policy: WatchPolicy,
+) -> io::Result<String> {
+    let mut state = TuiState::new(blockchain);
+    let mut candidate: Option<Block> = None;
+    let mut last_block = Instant::now();
+    let mut mined = 0;
+
+    loop {
+        state.refresh(blockchain);
+        terminal.draw(|frame| draw(frame, &state))?;
+
+        // While a block is being mined, only glance at the keyboard
+        let timeout = if candidate.is_some() { Duration::ZERO } else { POLL };
+        if event::poll(timeout)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match state.handle_key(key.code) {
+                Some(TuiAction::Quit) => break,
+                Some(TuiAction::Mine) if candidate.is_none() => {
+                    candidate = Some(start_block(blockchain, miner_address, &mut state));
+                }
+                Some(TuiAction::Mine) => state.status = String::from("Already mining"),
+                Some(TuiAction::Validate) => {
+                    let valid = blockchain.is_valid();
+                    state.valid = Some(valid);
+                    state.status = String::from(if valid { "Chain is valid" } else { "Chain is INVALID" });
+                }
+                Some(TuiAction::ToggleWatch) => {
+                    state.watching = !state.watching;
+                    state.status = format!("Watch mode {}", if state.watching { "on" } else { "off" });
+                }
+                None => {}
+            }
+        }
+
+        if candidate.is_none()
+            && state.watching
+            && policy.should_mine(blockchain.pending_transaction_count(), last_block.elapsed())
+        {
+            candidate = Some(start_block(blockchain, miner_address, &mut state));
+        }
+
+        if let Some(block) = candidate.as_mut()
+            && block.mine_step_with_algorithm(MINE_SLICE, blockchain.pow_prefix_char(), blockchain.pow_algorithm)
+        {
+            let block = candidate.take().expect("candidate is being mined");
+            match blockchain.add_mined_block(block.clone()) {
+                Ok(()) => {
+                    mined += 1;
+                    last_block = Instant::now();
+                    state.on_block_mined(blockchain, &block);
+                }
+                Err(e) => {
+                    state.mining = None;
+                    state.status = format!("Mined block was rejected: {}", e);
+                }
+            }
+        }
+    }
+
+    // A block still being mined gives its transactions back
+    if let Some(block) = candidate {
+        blockchain.requeue_candidate(block);
+    }
+
+    Ok(format!("Left the dashboard after mining {} block(s)", mined))
+}
+
+/// Takes the next candidate block out of the mempool and notes it in `state`
+fn start_block(blockchain: &mut Blockchain, miner_address: &str, state: &mut TuiState) -> Block {
+    let timestamp = blockchain.now();
+    blockchain.prune_expired_transactions(timestamp);
+    let block = blockchain.create_reward_candidate_block(timestamp, miner_address);
+    state.mining = Some(block.index);
+    state.status = format!("Mining block #{} at difficulty {}...", block.index, block.difficulty);
+    block
+}
+
+fn draw(frame: &mut Frame, state: &TuiState) {
+    let [summary, blocks, status] = Layout::vertical([
+        Constraint::Length(6),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let validity = match state.valid {
+        Some(true) => "valid",
+        Some(false) => "INVALID",
+        None => "not checked",
+    };
+    let lines = vec![
+        Line::from(format!("Height:      {}", state.height)),
+        Line::from(format!("Tip:         {}...", short_hash(&state.tip_hash, DEFAULT_HASH_LEN))),
+        Line::from(format!("Mempool:     {} pending", state.pending)),
+        Line::from(format!(
+            "Difficulty:  {}   Watch: {}   Chain: {}",
+            state.difficulty,
+            if state.watching { "on" } else { "off" },
+            validity
+        )),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Panel::default().borders(Borders::ALL).title(" RustChain ")),
+        summary,
+    );
+
+    let items: Vec<ListItem> = state
+        .blocks
+        .iter()
+        .skip(state.scroll)
+        .map(|row| {
+            ListItem::new(format!(
+                "#{:<5} {}...  {:>3} tx  nonce {:<10} diff {}",
+                row.index,
+                short_hash(&row.hash, DEFAULT_HASH_LEN),
+                row.transactions,
+                row.nonce,
+                row.difficulty
+            ))
+        })
+        .collect();
+    let title = match state.mining {
+        Some(index) => format!(" Blocks (mining #{}) ", index),
+        None => String::from(" Blocks "),
+    };
+    frame.render_widget(List::new(items).block(Panel::default().borders(Borders::ALL).title(title)), blocks);
+
+    frame.render_widget(
+        Paragraph::new(state.status.as_str()).style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        status,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::COIN;
+
+    #[test]
+    fn test_state_follows_a_mined_block() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+
+        let mut state = TuiState::new(&blockchain);
+        assert_eq!((state.height, state.pending, state.blocks.len()), (0, 1, 1));
+        state.valid = Some(true);
+        assert_eq!(state.handle_key(KeyCode::Char('m')), Some(TuiAction::Mine));
+
+        let block = blockchain.mine_block_to("Miner").clone();
+        state.on_block_mined(&blockchain, &block);
+
+        assert_eq!(state.height, 1);
+        assert_eq!(state.pending, 0);
+        assert_eq!(state.tip_hash, block.hash);
+        assert_eq!(state.blocks[0], BlockRow::from(&block));
+        assert_eq!(state.blocks.len(), 2);
+        assert_eq!(state.valid, None);
+        assert!(state.status.starts_with("Block #1 mined"));
+
+        // Scrolling stops at the last row
+        assert_eq!(state.handle_key(KeyCode::Down), None);
+        assert_eq!(state.handle_key(KeyCode::Down), None);
+        assert_eq!(state.scroll, 1);
+        assert_eq!(state.handle_key(KeyCode::Char('q')), Some(TuiAction::Quit));
+    }
+}
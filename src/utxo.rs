@@ -0,0 +1,282 @@
+//! Unspent transaction outputs, alongside the account model
+//!
+//! With the `utxo` feature a transfer may also spend `inputs` (outputs of
+//! earlier transactions) into new `outputs`, Bitcoin-style, so coins have a
+//! provenance and can be partly spent with change. Transfers without inputs
+//! or outputs are plain account-model transfers and are ignored here.
+//!
+//! A block reward pays one implicit output (index 0) to its receiver, so
+//! mined coins can be spent. Nothing is burned: the outputs plus the fee must
+//! add up to the inputs exactly, and the outputs paid to the receiver must
+//! add up to the account-model `amount`, so both models agree on the transfer.
+//! There are no signatures: an input is accepted when its output belongs to
+//! the transaction's sender.
+
+use crate::block::Block;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Reference to output `index` of transaction `txid`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OutPoint {
+    pub txid: String,
+    pub index: u32,
+}
+
+impl OutPoint {
+    pub fn new(txid: impl Into<String>, index: u32) -> Self {
+        OutPoint { txid: txid.into(), index }
+    }
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.index)
+    }
+}
+
+/// Coins paid to `address`, spendable once as an input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxOut {
+    pub address: String,
+    /// Amount in base units
+    pub amount: u64,
+}
+
+impl TxOut {
+    pub fn new(address: impl Into<String>, amount: u64) -> Self {
+        TxOut { address: address.into(), amount }
+    }
+}
+
+/// Why a transaction can't be applied to a `UtxoSet`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UtxoError {
+    /// The input doesn't name an unspent output (never existed, or already spent)
+    MissingInput(OutPoint),
+    /// The same output is spent twice within one transaction
+    DuplicateInput(OutPoint),
+    /// The input's output belongs to someone other than the sender
+    NotOwner { outpoint: OutPoint, owner: String },
+    /// Outputs are created without spending anything
+    NoInputs,
+    /// The outputs plus the fee don't add up to the inputs
+    Unbalanced { inputs: u64, outputs: u64, fee: u64 },
+    /// The outputs paid to the receiver don't add up to the transfer amount
+    AmountMismatch { amount: u64, to_receiver: u64 },
+}
+
+impl fmt::Display for UtxoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UtxoError::MissingInput(outpoint) => write!(f, "Input {} is not an unspent output", outpoint),
+            UtxoError::DuplicateInput(outpoint) => write!(f, "Input {} is spent twice", outpoint),
+            UtxoError::NotOwner { outpoint, owner } => write!(f, "Input {} belongs to {}", outpoint, owner),
+            UtxoError::NoInputs => write!(f, "Outputs need at least one input"),
+            UtxoError::Unbalanced { inputs, outputs, fee } => {
+                write!(f, "Inputs total {} but outputs plus fee total {} + {}", inputs, outputs, fee)
+            }
+            UtxoError::AmountMismatch { amount, to_receiver } => {
+                write!(f, "Transfer amount is {} but the outputs pay the receiver {}", amount, to_receiver)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UtxoError {}
+
+/// Sum of `outputs`' amounts
+fn total<'a>(outputs: impl Iterator<Item = &'a TxOut>) -> u64 {
+    outputs.fold(0u64, |sum, out| sum.saturating_add(out.amount))
+}
+
+/// Every output not yet spent, by the outpoint that would spend it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UtxoSet {
+    unspent: HashMap<OutPoint, TxOut>,
+}
+
+impl UtxoSet {
+    /// Replays `blocks` in order; invalid spends are skipped (validation reports them)
+    pub fn from_blocks(blocks: &[Block]) -> Self {
+        let mut set = UtxoSet::default();
+        for tx in blocks.iter().flat_map(|block| &block.transactions) {
+            let _ = set.apply(tx);
+        }
+        set
+    }
+
+    pub fn get(&self, outpoint: &OutPoint) -> Option<&TxOut> {
+        self.unspent.get(outpoint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.unspent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unspent.is_empty()
+    }
+
+    /// Sum of the unspent outputs paid to `address`
+    pub fn balance(&self, address: &str) -> u64 {
+        self.unspent.values().filter(|out| out.address == address).map(|out| out.amount).sum()
+    }
+
+    /// Checks `tx` could be applied, without changing the set
+    pub fn check(&self, tx: &Transaction) -> Result<(), UtxoError> {
+        if tx.is_coinbase() || tx.inputs.is_empty() && tx.outputs.is_empty() {
+            return Ok(());
+        }
+        if tx.inputs.is_empty() {
+            return Err(UtxoError::NoInputs);
+        }
+
+        let mut seen = HashSet::new();
+        let mut inputs: u64 = 0;
+        for outpoint in &tx.inputs {
+            if !seen.insert(outpoint) {
+                return Err(UtxoError::DuplicateInput(outpoint.clone()));
+            }
+            let spent = self.unspent.get(outpoint).ok_or_else(|| UtxoError::MissingInput(outpoint.clone()))?;
+            if spent.address != tx.sender {
+                return Err(UtxoError::NotOwner { outpoint: outpoint.clone(), owner: spent.address.clone() });
+            }
+            inputs = inputs.saturating_add(spent.amount);
+        }
+
+        let outputs = total(tx.outputs.iter());
+        if outputs.checked_add(tx.fee) != Some(inputs) {
+            return Err(UtxoError::Unbalanced { inputs, outputs, fee: tx.fee });
+        }
+        let to_receiver = total(tx.outputs.iter().filter(|out| out.address == tx.receiver));
+        if to_receiver != tx.amount {
+            return Err(UtxoError::AmountMismatch { amount: tx.amount, to_receiver });
+        }
+        Ok(())
+    }
+
+    /// Spends `tx`'s inputs and adds its outputs; leaves the set unchanged on error
+    pub fn apply(&mut self, tx: &Transaction) -> Result<(), UtxoError> {
+        self.check(tx)?;
+        for outpoint in &tx.inputs {
+            self.unspent.remove(outpoint);
+        }
+
+        let id = tx.id();
+        if tx.is_coinbase() && tx.outputs.is_empty() {
+            self.unspent.insert(OutPoint::new(id, 0), TxOut::new(tx.receiver.clone(), tx.amount));
+            return Ok(());
+        }
+        for (index, out) in tx.outputs.iter().enumerate() {
+            self.unspent.insert(OutPoint::new(id.clone(), index as u32), out.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mining"))]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+    use crate::transaction::{TransactionError, COIN};
+    use crate::validation::{self, ValidationError};
+
+    /// A chain where Alice has mined one block reward, and that reward's outpoint
+    fn funded_chain() -> (Blockchain, OutPoint) {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.mine_block_to("Alice");
+        let reward = blockchain.get_latest_block().coinbase().unwrap().id();
+        (blockchain, OutPoint::new(reward, 0))
+    }
+
+    /// Alice spending `inputs` into `outputs`, the first of which is the transfer
+    fn spend(inputs: Vec<OutPoint>, outputs: Vec<TxOut>, fee: u64) -> Transaction {
+        let amount = outputs[0].amount;
+        Transaction::new(String::from("Alice"), outputs[0].address.clone(), amount)
+            .unwrap()
+            .with_fee(fee)
+            .with_utxos(inputs, outputs)
+    }
+
+    #[test]
+    fn test_spend_with_change() {
+        let (mut blockchain, reward) = funded_chain();
+        assert_eq!(blockchain.utxo_set().balance("Alice"), 50 * COIN);
+
+        let tx = spend(vec![reward.clone()], vec![TxOut::new("Bob", 10 * COIN), TxOut::new("Alice", 39 * COIN)], COIN);
+        blockchain.add_pending_transaction(tx).unwrap();
+        blockchain.mine_block();
+
+        let utxos = blockchain.utxo_set();
+        assert_eq!(utxos.get(&reward), None);
+        assert_eq!(utxos.balance("Bob"), 10 * COIN);
+        assert_eq!(utxos.balance("Alice"), 39 * COIN);
+        assert!(blockchain.is_valid());
+    }
+
+    #[test]
+    fn test_double_spend_is_rejected() {
+        let (mut blockchain, reward) = funded_chain();
+        let first = spend(vec![reward.clone()], vec![TxOut::new("Bob", 50 * COIN)], 0);
+        let second = spend(vec![reward.clone()], vec![TxOut::new("Carol", 50 * COIN)], 0);
+
+        blockchain.add_pending_transaction(first).unwrap();
+        assert_eq!(
+            blockchain.add_pending_transaction(second.clone()).unwrap_err(),
+            TransactionError::InvalidSpend(UtxoError::MissingInput(reward.clone()).to_string())
+        );
+        blockchain.mine_block();
+
+        // Forced into a block anyway, the second spend fails validation
+        let mut block = Block::new(3, blockchain.min_next_timestamp(), vec![second.with_nonce(2)], blockchain.tip_hash().to_string(), 1);
+        block.mine_block();
+        blockchain.chain.push(block);
+        let result = validation::validate_chain(&blockchain);
+        assert!(matches!(result.errors.as_slice(), [ValidationError::InvalidSpend { index: 3, .. }]));
+        assert!(!blockchain.is_valid());
+    }
+
+    #[test]
+    fn test_outputs_exceeding_inputs_are_rejected() {
+        let (mut blockchain, reward) = funded_chain();
+        let greedy = spend(vec![reward.clone()], vec![TxOut::new("Bob", 40 * COIN), TxOut::new("Alice", 11 * COIN)], 0);
+        assert_eq!(
+            blockchain.utxo_set().check(&greedy),
+            Err(UtxoError::Unbalanced { inputs: 50 * COIN, outputs: 51 * COIN, fee: 0 })
+        );
+        assert!(blockchain.add_pending_transaction(greedy).is_err());
+
+        let stolen = Transaction::new(String::from("Mallory"), String::from("Bob"), COIN)
+            .unwrap()
+            .with_utxos(vec![reward.clone()], vec![TxOut::new("Bob", COIN)]);
+        assert_eq!(
+            blockchain.utxo_set().check(&stolen),
+            Err(UtxoError::NotOwner { outpoint: reward, owner: String::from("Alice") })
+        );
+    }
+
+    #[test]
+    fn test_outputs_must_match_inputs_and_amount() {
+        let (blockchain, reward) = funded_chain();
+
+        // 10 to Bob and nothing back: 40 would be burned
+        let burning = spend(vec![reward.clone()], vec![TxOut::new("Bob", 10 * COIN)], COIN);
+        assert_eq!(
+            blockchain.utxo_set().check(&burning),
+            Err(UtxoError::Unbalanced { inputs: 50 * COIN, outputs: 10 * COIN, fee: COIN })
+        );
+
+        // Balanced, but Bob gets 20 while the transfer says 10
+        let overpaying = Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN)
+            .unwrap()
+            .with_utxos(vec![reward], vec![TxOut::new("Bob", 20 * COIN), TxOut::new("Alice", 30 * COIN)]);
+        assert_eq!(
+            blockchain.utxo_set().check(&overpaying),
+            Err(UtxoError::AmountMismatch { amount: 10 * COIN, to_receiver: 20 * COIN })
+        );
+    }
+}
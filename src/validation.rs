@@ -1,9 +1,16 @@
-use crate::block::Block;
-use crate::blockchain::Blockchain;
+use crate::block::{Block, BlockHeader, BLOCK_VERSION};
+use crate::blockchain::{Blockchain, MEDIAN_TIME_PAST_WINDOW};
+use crate::consensus::{ConsensusMode, PowAlgorithm, DEFAULT_POW_PREFIX, MAX_DIFFICULTY};
+use crate::crypto::calculate_hash;
+use crate::transaction::{format_amount, Transaction};
+#[cfg(feature = "utxo")]
+use crate::utxo::UtxoSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 
 /// Validation errors that can occur during chain validation
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum ValidationError {
     /// The stored hash doesn't match the computed hash
     InvalidHash { index: usize, stored: String, computed: String },
@@ -11,10 +18,110 @@ pub enum ValidationError {
     BrokenLink { index: usize, previous_hash: String, expected: String },
     /// The hash doesn't meet the difficulty requirement
     InvalidProofOfWork { index: usize, hash: String, difficulty: u32 },
+    /// The block declares a lower difficulty than the chain requires
+    InsufficientDifficulty { index: usize, declared: u32, required: u32 },
+    /// The block claims a difficulty above `MAX_DIFFICULTY`
+    DifficultyOutOfRange { index: usize, difficulty: u32, max: u32 },
+    /// The block's format version is unknown or older than its parent's
+    InvalidBlockVersion { index: usize, version: u32, min: u32, max: u32 },
     /// The block index is not sequential
     InvalidIndex { index: usize, expected: usize },
     /// The genesis block doesn't meet requirements
     InvalidGenesis { reason: String },
+    /// The stored Merkle root doesn't match the block's transactions
+    InvalidMerkleRoot { index: usize, stored: String, computed: String },
+    /// A transaction id already appeared earlier in this block (or, when
+    /// checking replays, in an earlier block)
+    DuplicateTransaction { index: usize, tx_id: String },
+    /// The block's serialized size exceeds the chain's limit
+    BlockTooLarge { index: usize, size: usize, limit: usize },
+    /// The block holds more transactions than the chain allows
+    TooManyTransactions { index: usize, count: usize, limit: usize },
+    /// A transfer amount is below the dust limit or above the maximum
+    AmountOutOfRange { index: usize, tx_id: String, amount: u64, min: u64, max: u64 },
+    /// A transaction sends to or from an address on the chain's blocklist
+    BlockedAddress { index: usize, tx_id: String, address: String },
+    /// A checkpointed block is missing or no longer has the recorded hash
+    CheckpointMismatch { index: usize, expected: String, found: Option<String> },
+    /// The block's producer isn't the validator the consensus mode selects
+    WrongProducer { index: usize, expected: Option<String>, found: Option<String> },
+    /// The coinbase doesn't pay exactly the block reward plus the block's fees
+    InvalidCoinbase { index: usize, expected: u64, found: u64 },
+    /// A coinbase appears after the first transaction (including a second coinbase)
+    MisplacedCoinbase { index: usize, position: usize },
+    /// Block rewards up to and including this block create more than the chain's `max_supply`
+    SupplyCapExceeded { index: usize, issued: u64, max_supply: u64 },
+    /// A sender's account nonce is out of sequence (replayed, reordered, or skipped)
+    BadAccountNonce { index: usize, sender: String, expected: u64, got: u64 },
+    /// The block's timestamp isn't later than the median time past of the blocks before it
+    TimestampTooEarly { index: usize, timestamp: u128, median_time_past: u128 },
+    /// The stored state root doesn't match the balances after applying the block
+    StateRootMismatch { index: usize, stored: String, computed: String },
+    /// A UTXO spend references a spent or missing output, or creates value
+    #[cfg(feature = "utxo")]
+    InvalidSpend { index: usize, tx_id: String, reason: String },
+}
+
+impl ValidationError {
+    /// Name of the error variant, used to group errors in a summary
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidHash { .. } => "InvalidHash",
+            ValidationError::BrokenLink { .. } => "BrokenLink",
+            ValidationError::InvalidProofOfWork { .. } => "InvalidProofOfWork",
+            ValidationError::DifficultyOutOfRange { .. } => "DifficultyOutOfRange",
+            ValidationError::InsufficientDifficulty { .. } => "InsufficientDifficulty",
+            ValidationError::InvalidBlockVersion { .. } => "InvalidBlockVersion",
+            ValidationError::InvalidIndex { .. } => "InvalidIndex",
+            ValidationError::InvalidGenesis { .. } => "InvalidGenesis",
+            ValidationError::InvalidMerkleRoot { .. } => "InvalidMerkleRoot",
+            ValidationError::DuplicateTransaction { .. } => "DuplicateTransaction",
+            ValidationError::BlockTooLarge { .. } => "BlockTooLarge",
+            ValidationError::TooManyTransactions { .. } => "TooManyTransactions",
+            ValidationError::AmountOutOfRange { .. } => "AmountOutOfRange",
+            ValidationError::BlockedAddress { .. } => "BlockedAddress",
+            ValidationError::CheckpointMismatch { .. } => "CheckpointMismatch",
+            ValidationError::WrongProducer { .. } => "WrongProducer",
+            ValidationError::InvalidCoinbase { .. } => "InvalidCoinbase",
+            ValidationError::MisplacedCoinbase { .. } => "MisplacedCoinbase",
+            ValidationError::SupplyCapExceeded { .. } => "SupplyCapExceeded",
+            ValidationError::BadAccountNonce { .. } => "BadAccountNonce",
+            ValidationError::TimestampTooEarly { .. } => "TimestampTooEarly",
+            ValidationError::StateRootMismatch { .. } => "StateRootMismatch",
+            #[cfg(feature = "utxo")]
+            ValidationError::InvalidSpend { .. } => "InvalidSpend",
+        }
+    }
+
+    /// Index of the block the error was found in (genesis errors are block 0)
+    pub fn block_index(&self) -> usize {
+        match self {
+            ValidationError::InvalidHash { index, .. }
+            | ValidationError::BrokenLink { index, .. }
+            | ValidationError::InvalidProofOfWork { index, .. }
+            | ValidationError::DifficultyOutOfRange { index, .. }
+            | ValidationError::InsufficientDifficulty { index, .. }
+            | ValidationError::InvalidBlockVersion { index, .. }
+            | ValidationError::InvalidIndex { index, .. }
+            | ValidationError::InvalidMerkleRoot { index, .. }
+            | ValidationError::DuplicateTransaction { index, .. }
+            | ValidationError::BlockTooLarge { index, .. }
+            | ValidationError::TooManyTransactions { index, .. }
+            | ValidationError::AmountOutOfRange { index, .. }
+            | ValidationError::BlockedAddress { index, .. }
+            | ValidationError::CheckpointMismatch { index, .. }
+            | ValidationError::WrongProducer { index, .. }
+            | ValidationError::InvalidCoinbase { index, .. }
+            | ValidationError::MisplacedCoinbase { index, .. }
+            | ValidationError::SupplyCapExceeded { index, .. }
+            | ValidationError::BadAccountNonce { index, .. }
+            | ValidationError::TimestampTooEarly { index, .. }
+            | ValidationError::StateRootMismatch { index, .. } => *index,
+            #[cfg(feature = "utxo")]
+            ValidationError::InvalidSpend { index, .. } => *index,
+            ValidationError::InvalidGenesis { .. } => 0,
+        }
+    }
 }
 
 impl fmt::Display for ValidationError {
@@ -30,12 +137,72 @@ impl fmt::Display for ValidationError {
                 write!(f, "Block #{}: Invalid proof-of-work\n  Hash:       {}\n  Difficulty: {} (requires {} leading zeros)",
                     index, hash, difficulty, difficulty)
             }
+            ValidationError::DifficultyOutOfRange { index, difficulty, max } => {
+                write!(f, "Block #{}: Difficulty {} is above the maximum of {}", index, difficulty, max)
+            }
+            ValidationError::InsufficientDifficulty { index, declared, required } => {
+                write!(f, "Block #{}: Declares difficulty {}; the chain requires at least {}", index, declared, required)
+            }
+            ValidationError::InvalidBlockVersion { index, version, min, max } => {
+                write!(f, "Block #{}: Format version {} is outside {}..={}", index, version, min, max)
+            }
             ValidationError::InvalidIndex { index, expected } => {
                 write!(f, "Block #{}: Invalid index (expected {})", index, expected)
             }
             ValidationError::InvalidGenesis { reason } => {
                 write!(f, "Genesis block: {}", reason)
             }
+            ValidationError::InvalidMerkleRoot { index, stored, computed } => {
+                write!(f, "Block #{}: Invalid Merkle root\n  Stored:   {}\n  Computed: {}", index, stored, computed)
+            }
+            ValidationError::DuplicateTransaction { index, tx_id } => {
+                write!(f, "Block #{}: Duplicate transaction {}", index, tx_id)
+            }
+            ValidationError::BlockTooLarge { index, size, limit } => {
+                write!(f, "Block #{}: Too large ({} bytes, limit {})", index, size, limit)
+            }
+            ValidationError::TooManyTransactions { index, count, limit } => {
+                write!(f, "Block #{}: Too many transactions ({}, limit {})", index, count, limit)
+            }
+            ValidationError::AmountOutOfRange { index, tx_id, amount, min, max } => {
+                write!(f, "Block #{}: Transaction {} amount {} is outside {}..={}",
+                    index, tx_id, format_amount(*amount), format_amount(*min), format_amount(*max))
+            }
+            ValidationError::BlockedAddress { index, tx_id, address } => {
+                write!(f, "Block #{}: Transaction {} touches blocked address {}", index, tx_id, address)
+            }
+            ValidationError::CheckpointMismatch { index, expected, found } => {
+                write!(f, "Block #{}: Checkpoint mismatch\n  Expected: {}\n  Found:    {}",
+                    index, expected, found.as_deref().unwrap_or("(missing)"))
+            }
+            ValidationError::WrongProducer { index, expected, found } => {
+                write!(f, "Block #{}: Wrong producer\n  Expected: {}\n  Found:    {}",
+                    index, expected.as_deref().unwrap_or("(none)"), found.as_deref().unwrap_or("(none)"))
+            }
+            ValidationError::InvalidCoinbase { index, expected, found } => {
+                write!(f, "Block #{}: Coinbase pays {} (block reward plus fees is {})",
+                    index, format_amount(*found), format_amount(*expected))
+            }
+            ValidationError::MisplacedCoinbase { index, position } => {
+                write!(f, "Block #{}: Coinbase at position {} (only the first transaction may be one)", index, position)
+            }
+            ValidationError::SupplyCapExceeded { index, issued, max_supply } => {
+                write!(f, "Block #{}: Rewards would bring issuance to {}, past the supply cap of {}",
+                    index, format_amount(*issued), format_amount(*max_supply))
+            }
+            ValidationError::BadAccountNonce { index, sender, expected, got } => {
+                write!(f, "Block #{}: {} used account nonce {} (expected {})", index, sender, got, expected)
+            }
+            ValidationError::TimestampTooEarly { index, timestamp, median_time_past } => {
+                write!(f, "Block #{}: Timestamp {} is not after the median time past {}", index, timestamp, median_time_past)
+            }
+            ValidationError::StateRootMismatch { index, stored, computed } => {
+                write!(f, "Block #{}: State root doesn't match the balances\n  Stored:   {}\n  Computed: {}", index, stored, computed)
+            }
+            #[cfg(feature = "utxo")]
+            ValidationError::InvalidSpend { index, tx_id, reason } => {
+                write!(f, "Block #{}: Transaction {} is an invalid spend: {}", index, tx_id, reason)
+            }
         }
     }
 }
@@ -66,6 +233,22 @@ impl ValidationResult {
         self.errors.first()
     }
 
+    /// Counts and locations of the errors, for reports and tooling
+    pub fn summary(&self) -> ValidationSummary {
+        let mut errors_by_type = BTreeMap::new();
+        for error in &self.errors {
+            *errors_by_type.entry(error.kind().to_string()).or_insert(0) += 1;
+        }
+
+        ValidationSummary {
+            is_valid: self.is_valid,
+            total_errors: self.errors.len(),
+            errors_by_type,
+            first_invalid_block: self.errors.iter().map(ValidationError::block_index).min(),
+            errors: self.errors.iter().map(|error| error.to_string()).collect(),
+        }
+    }
+
     pub fn display_errors(&self) {
         if self.is_valid {
             println!("Chain is valid ✓");
@@ -79,6 +262,26 @@ impl ValidationResult {
     }
 }
 
+/// Machine-readable digest of a `ValidationResult`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationSummary {
+    pub is_valid: bool,
+    pub total_errors: usize,
+    /// Error count per `ValidationError::kind`, sorted by name
+    pub errors_by_type: BTreeMap<String, usize>,
+    /// Lowest block index with any error
+    pub first_invalid_block: Option<usize>,
+    /// Every error's message, in the order found
+    pub errors: Vec<String>,
+}
+
+impl ValidationSummary {
+    /// Pretty-printed JSON form of the summary
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("validation summary is always serializable")
+    }
+}
+
 /// Validates a single block's hash
 pub fn verify_block_hash(block: &Block) -> Result<(), ValidationError> {
     let computed_hash = block.calculate_hash();
@@ -92,6 +295,283 @@ pub fn verify_block_hash(block: &Block) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Validates that a block's stored Merkle root matches its transactions
+pub fn verify_merkle_root(block: &Block) -> Result<(), ValidationError> {
+    let computed_root = block.compute_merkle_root();
+    if block.merkle_root != computed_root {
+        return Err(ValidationError::InvalidMerkleRoot {
+            index: block.index as usize,
+            stored: block.merkle_root.clone(),
+            computed: computed_root,
+        });
+    }
+    Ok(())
+}
+
+/// Validates that no transaction appears twice within a block
+pub fn verify_unique_transactions(block: &Block) -> Result<(), ValidationError> {
+    if let Some(tx_id) = block.duplicate_transaction_id() {
+        return Err(ValidationError::DuplicateTransaction {
+            index: block.index as usize,
+            tx_id,
+        });
+    }
+    Ok(())
+}
+
+/// Validates that a block fits within `limit` bytes (see `Block::serialized_size`)
+pub fn verify_block_size(block: &Block, limit: usize) -> Result<(), ValidationError> {
+    let size = block.serialized_size();
+    if size > limit {
+        return Err(ValidationError::BlockTooLarge {
+            index: block.index as usize,
+            size,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Validates that a block holds at most `limit` transactions
+pub fn verify_transaction_count(block: &Block, limit: usize) -> Result<(), ValidationError> {
+    let count = block.transaction_count();
+    if count > limit {
+        return Err(ValidationError::TooManyTransactions {
+            index: block.index as usize,
+            count,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Validates that every transfer in a block is within `min..=max` base units
+/// The coinbase isn't a transfer: `verify_coinbase` pins its amount, which
+/// halvings and small fees can take below the dust limit
+pub fn verify_transaction_amounts(block: &Block, min: u64, max: u64) -> Result<(), ValidationError> {
+    let mut transfers = block.transactions.iter().filter(|tx| !tx.is_coinbase());
+    if let Some(tx) = transfers.find(|tx| !(min..=max).contains(&tx.amount)) {
+        return Err(ValidationError::AmountOutOfRange {
+            index: block.index as usize,
+            tx_id: tx.id(),
+            amount: tx.amount,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Validates that no transaction in a block sends to or from a `blocked` address
+pub fn verify_no_blocked_addresses(block: &Block, blocked: &HashSet<String>) -> Result<(), ValidationError> {
+    for tx in &block.transactions {
+        if let Some(address) = [&tx.sender, &tx.receiver].into_iter().find(|address| blocked.contains(*address)) {
+            return Err(ValidationError::BlockedAddress {
+                index: block.index as usize,
+                tx_id: tx.id(),
+                address: address.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Validates that a block's coinbase, if any, comes first and pays
+/// `block_reward` (the reward at the block's height) plus the block's fees
+/// A coinbase anywhere else would create coins no rule accounts for
+pub fn verify_coinbase(block: &Block, block_reward: u64) -> Result<(), ValidationError> {
+    if let Some(position) = block.transactions.iter().skip(1).position(Transaction::is_coinbase) {
+        return Err(ValidationError::MisplacedCoinbase { index: block.index as usize, position: position + 1 });
+    }
+    if let Some(coinbase) = block.coinbase() {
+        let expected = block_reward.saturating_add(block.total_fees());
+        if coinbase.amount != expected {
+            return Err(ValidationError::InvalidCoinbase {
+                index: block.index as usize,
+                expected,
+                found: coinbase.amount,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Validates that the block's new coins, on top of `issued_before`, stay
+/// within `max_supply` (always true without a cap)
+pub fn verify_supply_cap(block: &Block, issued_before: u64, max_supply: Option<u64>) -> Result<(), ValidationError> {
+    let Some(max_supply) = max_supply else {
+        return Ok(());
+    };
+    let issued = issued_before.saturating_add(block.issuance());
+    if issued > max_supply {
+        return Err(ValidationError::SupplyCapExceeded { index: block.index as usize, issued, max_supply });
+    }
+    Ok(())
+}
+
+/// Applies the transfers in `block` to `balances`: senders pay the amount
+/// plus the fee, receivers get the amount (see `Blockchain::balances`)
+pub fn apply_balances(block: &Block, balances: &mut HashMap<String, i128>) {
+    for tx in &block.transactions {
+        *balances.entry(tx.sender.clone()).or_default() -= tx.total_cost() as i128;
+        *balances.entry(tx.receiver.clone()).or_default() += tx.amount as i128;
+    }
+}
+
+/// Hash of every balance in address order: one value committing to the whole state
+/// Addresses are length-prefixed, so no two balance maps encode the same
+pub fn state_root(balances: &HashMap<String, i128>) -> String {
+    let mut sorted: Vec<(&String, &i128)> = balances.iter().collect();
+    sorted.sort();
+    let encoded: String = sorted
+        .into_iter()
+        .map(|(address, balance)| format!("{}:{}={};", address.len(), address, balance))
+        .collect();
+    calculate_hash(&encoded)
+}
+
+/// Validates that a block's state root commits to `balances`, the balances
+/// once the block is applied. Blocks without a state root aren't checked
+pub fn verify_state_root(block: &Block, balances: &HashMap<String, i128>) -> Result<(), ValidationError> {
+    let Some(stored) = &block.state_root else {
+        return Ok(());
+    };
+    let computed = state_root(balances);
+    if *stored != computed {
+        return Err(ValidationError::StateRootMismatch { index: block.index as usize, stored: stored.clone(), computed });
+    }
+    Ok(())
+}
+
+/// Highest account nonce each sender has used in `blocks`
+pub fn account_nonces(blocks: &[Block]) -> HashMap<String, u64> {
+    let mut nonces = HashMap::new();
+    for block in blocks {
+        record_nonces(block, &mut nonces);
+    }
+    nonces
+}
+
+/// Raises each sender's entry in `nonces` to the highest nonce it used in `block`
+pub fn record_nonces(block: &Block, nonces: &mut HashMap<String, u64>) {
+    for tx in block.transactions.iter().filter(|tx| tx.nonce > 0) {
+        let highest = nonces.entry(tx.sender.clone()).or_default();
+        *highest = (*highest).max(tx.nonce);
+    }
+}
+
+/// Checks that every numbered transfer in `block` is its sender's next nonce
+/// `last` maps each sender to the nonce they used most recently and is
+/// advanced as the block is read. Transfers with nonce 0 (from before account
+/// nonces existed) and coinbases are not numbered and are skipped
+pub fn verify_block_nonces(block: &Block, last: &mut HashMap<String, u64>) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for tx in block.transactions.iter().filter(|tx| tx.nonce > 0 && !tx.is_coinbase()) {
+        let previous = last.entry(tx.sender.clone()).or_default();
+        let expected = *previous + 1;
+        if tx.nonce == expected {
+            *previous = expected;
+        } else {
+            errors.push(ValidationError::BadAccountNonce {
+                index: block.index as usize,
+                sender: tx.sender.clone(),
+                expected,
+                got: tx.nonce,
+            });
+        }
+    }
+    errors
+}
+
+/// Checks each sender's account nonces run 1, 2, 3, ... across `blocks`
+pub fn verify_account_nonces(blocks: &[Block]) -> Vec<ValidationError> {
+    let mut last = HashMap::new();
+    blocks.iter().flat_map(|block| verify_block_nonces(block, &mut last)).collect()
+}
+
+/// Applies a block's UTXO spends to `utxos`, reporting each one that can't be applied
+#[cfg(feature = "utxo")]
+pub fn verify_block_utxos(block: &Block, utxos: &mut UtxoSet) -> Vec<ValidationError> {
+    block.transactions
+        .iter()
+        .filter_map(|tx| utxos.apply(tx).err().map(|e| (tx, e)))
+        .map(|(tx, e)| ValidationError::InvalidSpend {
+            index: block.index as usize,
+            tx_id: tx.id(),
+            reason: e.to_string(),
+        })
+        .collect()
+}
+
+/// Validates that a block is stamped later than `median_time_past`, the median
+/// timestamp of the blocks before it (see `Blockchain::median_time_past`)
+pub fn verify_timestamp(block: &Block, median_time_past: Option<u128>) -> Result<(), ValidationError> {
+    match median_time_past {
+        Some(median_time_past) if block.timestamp <= median_time_past => Err(ValidationError::TimestampTooEarly {
+            index: block.index as usize,
+            timestamp: block.timestamp,
+            median_time_past,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Validates that a block names the producer `consensus` selects after `previous_block`
+pub fn verify_producer(block: &Block, previous_block: &Block, consensus: &ConsensusMode) -> Result<(), ValidationError> {
+    let expected = consensus.expected_producer(&previous_block.hash);
+    if block.producer.as_deref() != expected {
+        return Err(ValidationError::WrongProducer {
+            index: block.index as usize,
+            expected: expected.map(String::from),
+            found: block.producer.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks every checkpointed block against its recorded hash
+pub fn verify_checkpoints(blockchain: &Blockchain) -> Vec<ValidationError> {
+    blockchain.checkpoints
+        .iter()
+        .filter_map(|(index, expected)| {
+            let found = blockchain.chain.get(*index).map(|block| block.hash.clone());
+            (found.as_ref() != Some(expected)).then(|| ValidationError::CheckpointMismatch {
+                index: *index,
+                expected: expected.clone(),
+                found,
+            })
+        })
+        .collect()
+}
+
+/// Finds transactions replayed in a later block after already being confirmed
+///
+/// Transfers with an account nonce are already protected by `validate_chain`
+/// (see `verify_block_nonces`). Unnumbered ones (nonce 0, from older chains or
+/// built by hand) have no nonce in their id, so two honest, identical
+/// transfers look like a replay. This check is therefore opt-in.
+pub fn find_replayed_transactions(blockchain: &Blockchain) -> Vec<ValidationError> {
+    let mut confirmed = HashSet::new();
+    let mut errors = Vec::new();
+
+    for block in blockchain.blocks() {
+        // Intra-block repeats are reported by verify_unique_transactions
+        let mut in_block = HashSet::new();
+        for tx_id in block.transaction_ids() {
+            if confirmed.contains(&tx_id) && in_block.insert(tx_id.clone()) {
+                errors.push(ValidationError::DuplicateTransaction {
+                    index: block.index as usize,
+                    tx_id: tx_id.clone(),
+                });
+            }
+            in_block.insert(tx_id);
+        }
+        confirmed.extend(in_block);
+    }
+
+    errors
+}
+
 /// Validates the chain link between two consecutive blocks
 pub fn verify_chain_link(current_block: &Block, previous_block: &Block) -> Result<(), ValidationError> {
     if current_block.previous_hash != previous_block.hash {
@@ -104,18 +584,61 @@ pub fn verify_chain_link(current_block: &Block, previous_block: &Block) -> Resul
     Ok(())
 }
 
-/// Validates proof-of-work for a block
+/// Validates proof-of-work for a block, starting with its claimed difficulty
 pub fn verify_proof_of_work(block: &Block) -> Result<(), ValidationError> {
-    if !Block::is_hash_valid(&block.hash, block.difficulty) {
+    verify_proof_of_work_with_prefix(block, DEFAULT_POW_PREFIX)
+}
+
+/// `verify_proof_of_work` for a chain whose hashes must start with `prefix` digits
+pub fn verify_proof_of_work_with_prefix(block: &Block, prefix: char) -> Result<(), ValidationError> {
+    verify_proof_of_work_with_algorithm(block, prefix, PowAlgorithm::Sha256)
+}
+
+/// `verify_proof_of_work_with_prefix` on the block's `algorithm` digest; the
+/// error reports that digest, since it is what missed the target
+pub fn verify_proof_of_work_with_algorithm(block: &Block, prefix: char, algorithm: PowAlgorithm) -> Result<(), ValidationError> {
+    verify_difficulty(block.index as usize, block.difficulty)?;
+    let pow_hash = algorithm.pow_hash(&block.hash);
+    if !Block::meets_target(&pow_hash, block.difficulty, prefix) {
         return Err(ValidationError::InvalidProofOfWork {
             index: block.index as usize,
-            hash: block.hash.clone(),
+            hash: pow_hash.into_owned(),
             difficulty: block.difficulty,
         });
     }
     Ok(())
 }
 
+/// Checks a block's claimed difficulty is within `0..=MAX_DIFFICULTY`
+pub fn verify_difficulty(index: usize, difficulty: u32) -> Result<(), ValidationError> {
+    if difficulty > MAX_DIFFICULTY {
+        return Err(ValidationError::DifficultyOutOfRange { index, difficulty, max: MAX_DIFFICULTY });
+    }
+    Ok(())
+}
+
+/// Checks a block declares at least the chain's `required` difficulty
+pub fn verify_min_difficulty(block: &Block, required: u32) -> Result<(), ValidationError> {
+    if block.difficulty < required {
+        return Err(ValidationError::InsufficientDifficulty {
+            index: block.index as usize,
+            declared: block.difficulty,
+            required,
+        });
+    }
+    Ok(())
+}
+
+/// Checks a block's format version is between `min` (its parent's version,
+/// or `BLOCK_VERSION` for a new block) and `BLOCK_VERSION`, so a chain can
+/// move to a newer format but never back
+pub fn verify_block_version(index: usize, version: u32, min: u32) -> Result<(), ValidationError> {
+    if !(min..=BLOCK_VERSION).contains(&version) {
+        return Err(ValidationError::InvalidBlockVersion { index, version, min, max: BLOCK_VERSION });
+    }
+    Ok(())
+}
+
 /// Validates the genesis block
 pub fn verify_genesis_block(block: &Block) -> Result<(), ValidationError> {
     if block.index != 0 {
@@ -130,6 +653,19 @@ pub fn verify_genesis_block(block: &Block) -> Result<(), ValidationError> {
         });
     }
 
+    // Nothing is mined for genesis, so it can't claim any work
+    if block.difficulty != 0 {
+        return Err(ValidationError::InvalidGenesis {
+            reason: format!("Invalid difficulty: expected 0, got {}", block.difficulty),
+        });
+    }
+
+    if block.version != 0 {
+        return Err(ValidationError::InvalidGenesis {
+            reason: format!("Invalid version: expected 0, got {}", block.version),
+        });
+    }
+
     Ok(())
 }
 
@@ -147,31 +683,248 @@ pub fn verify_block_index(block: &Block, expected_index: usize) -> Result<(), Va
 /// Comprehensive validation of the entire blockchain
 /// Returns a detailed ValidationResult with all errors found
 pub fn validate_chain(blockchain: &Blockchain) -> ValidationResult {
-    let mut errors = Vec::new();
+    let errors: Vec<ValidationError> = iter_validation_errors(blockchain).collect();
 
-    // Validate genesis block
-    if let Some(genesis) = blockchain.chain.first() {
-        if let Err(e) = verify_genesis_block(genesis) {
+    if errors.is_empty() {
+        ValidationResult::valid()
+    } else {
+        ValidationResult::invalid(errors)
+    }
+}
+
+/// `validate_chain`, with every block's hash and Merkle root recomputed in
+/// parallel first; the link, index and proof-of-work checks that need
+/// neighbouring blocks still run in order, so the errors (and their order)
+/// are exactly those of `validate_chain`
+#[cfg(feature = "rayon")]
+pub fn validate_chain_parallel(blockchain: &Blockchain) -> ValidationResult {
+    use rayon::prelude::*;
+
+    let hash_checks = blockchain.chain.par_iter().map(|block| Some(HashChecks::of(block))).collect();
+    let errors: Vec<ValidationError> = ValidationErrors { hash_checks, ..iter_validation_errors(blockchain) }.collect();
+
+    if errors.is_empty() {
+        ValidationResult::valid()
+    } else {
+        ValidationResult::invalid(errors)
+    }
+}
+
+/// The errors `validate_chain` reports, in the same order, found one block
+/// at a time as the iterator is advanced
+pub fn iter_validation_errors(blockchain: &Blockchain) -> ValidationErrors<'_> {
+    ValidationErrors {
+        include_checkpoints: true,
+        ..ValidationErrors::blocks(blockchain, blockchain.chain.len())
+    }
+}
+
+/// `validate_chain` for blocks `0..=height` only, as if the chain ended there
+/// Checkpoints above `height` are ignored. Since every block commits to the one
+/// before it, prefix validity only ever goes from valid to invalid as `height`
+/// grows, so the first bad block can be found by binary search
+pub fn validate_prefix(blockchain: &Blockchain, height: usize) -> Result<ValidationResult, String> {
+    if height >= blockchain.chain.len() {
+        return Err(format!("Height {} is past the tip (height {})", height, blockchain.height()));
+    }
+
+    let mut errors: Vec<ValidationError> = ValidationErrors::blocks(blockchain, height + 1).collect();
+    errors.extend(verify_checkpoints(blockchain).into_iter().filter(|e| e.block_index() <= height));
+
+    if errors.is_empty() {
+        Ok(ValidationResult::valid())
+    } else {
+        Ok(ValidationResult::invalid(errors))
+    }
+}
+
+/// A block's hash and Merkle root checks, the costly part of validating it
+struct HashChecks {
+    hash: Result<(), ValidationError>,
+    merkle_root: Result<(), ValidationError>,
+}
+
+impl HashChecks {
+    fn of(block: &Block) -> Self {
+        HashChecks {
+            hash: verify_block_hash(block),
+            merkle_root: verify_merkle_root(block),
+        }
+    }
+}
+
+/// Lazy validation of a chain, yielding each block's errors before checking
+/// the next block (see `iter_validation_errors`)
+pub struct ValidationErrors<'a> {
+    blockchain: &'a Blockchain,
+    /// Blocks `0..end` are checked
+    end: usize,
+    next_block: usize,
+    include_checkpoints: bool,
+    nonces: HashMap<String, u64>,
+    /// Base units created by block rewards in the blocks checked so far
+    issued: u64,
+    /// Balances after the blocks checked so far
+    balances: HashMap<String, i128>,
+    #[cfg(feature = "utxo")]
+    utxos: UtxoSet,
+    /// Hash checks already done, by block index; blocks without one are
+    /// hashed as they are reached
+    hash_checks: Vec<Option<HashChecks>>,
+    found: std::vec::IntoIter<ValidationError>,
+}
+
+impl<'a> ValidationErrors<'a> {
+    /// Per-block checks on the first `end` blocks, without checkpoints
+    pub(crate) fn blocks(blockchain: &'a Blockchain, end: usize) -> Self {
+        ValidationErrors {
+            blockchain,
+            end,
+            next_block: 0,
+            include_checkpoints: false,
+            nonces: HashMap::new(),
+            issued: 0,
+            balances: HashMap::new(),
+            #[cfg(feature = "utxo")]
+            utxos: UtxoSet::default(),
+            hash_checks: Vec::new(),
+            found: Vec::new().into_iter(),
+        }
+    }
+
+    /// Checks for a single block on top of a state already verified: the
+    /// account `nonces` (at least those of the block's senders), the coins
+    /// `issued` so far and the `balances`
+    pub(crate) fn on_state(
+        blockchain: &'a Blockchain,
+        nonces: HashMap<String, u64>,
+        issued: u64,
+        balances: HashMap<String, i128>,
+    ) -> Self {
+        ValidationErrors { nonces, issued, balances, ..Self::blocks(blockchain, 0) }
+    }
+
+    /// `on_state` with the unspent outputs too
+    #[cfg(feature = "utxo")]
+    pub(crate) fn with_utxos(self, utxos: UtxoSet) -> Self {
+        ValidationErrors { utxos, ..self }
+    }
+
+    /// Number of blocks checked so far
+    pub fn blocks_checked(&self) -> usize {
+        self.next_block
+    }
+
+    /// Runs every per-block check on block `i` of the chain
+    fn verify_chain_block(&mut self, i: usize) -> Vec<ValidationError> {
+        let blockchain = self.blockchain;
+        let current_block = &blockchain.chain[i];
+
+        if i > 0 {
+            let previous_block = &blockchain.chain[i - 1];
+            let median = blockchain.median_time_past(i, MEDIAN_TIME_PAST_WINDOW);
+            return self.verify_block(current_block, i, previous_block, previous_block.version, median);
+        }
+
+        // Validate genesis block
+        let hash_checks = self.take_hash_checks(current_block, 0);
+        let mut errors = Vec::new();
+        apply_balances(current_block, &mut self.balances);
+        if let Err(e) = verify_genesis_block(current_block) {
             errors.push(e);
         }
         // Also verify genesis block hash integrity for educational purposes
-        if let Err(e) = verify_block_hash(genesis) {
+        if let Err(e) = hash_checks.hash {
+            errors.push(e);
+        }
+        // The hash recomputes the Merkle root, so check the stored one too
+        if let Err(e) = hash_checks.merkle_root {
             errors.push(e);
         }
+        errors
     }
 
-    // Validate each block in the chain
-    for i in 1..blockchain.chain.len() {
-        let current_block = &blockchain.chain[i];
-        let previous_block = &blockchain.chain[i - 1];
+    /// The hash checks for the block at `height`, done now unless done ahead
+    fn take_hash_checks(&mut self, block: &Block, height: usize) -> HashChecks {
+        self.hash_checks
+            .get_mut(height)
+            .and_then(Option::take)
+            .unwrap_or_else(|| HashChecks::of(block))
+    }
+
+    /// Runs every per-block check on `block` at position `height`, following
+    /// `previous_block`: at least version `min_version` and later than
+    /// `median_time_past`. Every consensus rule a block after genesis must
+    /// follow lives here
+    pub(crate) fn verify_block(
+        &mut self,
+        current_block: &Block,
+        height: usize,
+        previous_block: &Block,
+        min_version: u32,
+        median_time_past: Option<u128>,
+    ) -> Vec<ValidationError> {
+        let blockchain = self.blockchain;
+        let hash_checks = self.take_hash_checks(current_block, height);
+        let mut errors = Vec::new();
+
+        // Each block's state root commits to the balances once it is applied
+        apply_balances(current_block, &mut self.balances);
 
         // Check index sequencing
-        if let Err(e) = verify_block_index(current_block, i) {
+        if let Err(e) = verify_block_index(current_block, height) {
             errors.push(e);
         }
 
         // Verify hash integrity
-        if let Err(e) = verify_block_hash(current_block) {
+        if let Err(e) = hash_checks.hash {
+            errors.push(e);
+        }
+
+        // Verify the stored Merkle root commits to these transactions
+        if let Err(e) = hash_checks.merkle_root {
+            errors.push(e);
+        }
+
+        // Each transaction may appear only once per block
+        if let Err(e) = verify_unique_transactions(current_block) {
+            errors.push(e);
+        }
+
+        // Blocks must respect the chain's size and transaction count limits
+        if let Err(e) = verify_block_size(current_block, blockchain.max_block_bytes) {
+            errors.push(e);
+        }
+        if let Err(e) = verify_transaction_count(current_block, blockchain.max_transactions_per_block) {
+            errors.push(e);
+        }
+
+        // Transfer amounts are consensus rules too
+        if let Err(e) = verify_transaction_amounts(current_block, blockchain.min_amount, blockchain.max_amount) {
+            errors.push(e);
+        }
+        if let Err(e) = verify_no_blocked_addresses(current_block, &blockchain.blocked_addresses) {
+            errors.push(e);
+        }
+
+        // The miner may claim the block reward and the fees, nothing more, and
+        // the reward shrinks to whatever the supply cap leaves
+        let reward = blockchain.capped_block_reward(current_block.index, self.issued);
+        if let Err(e) = verify_supply_cap(current_block, self.issued, blockchain.max_supply) {
+            errors.push(e);
+        } else if let Err(e) = verify_coinbase(current_block, reward) {
+            errors.push(e);
+        }
+        self.issued = self.issued.saturating_add(current_block.issuance());
+
+        // Each sender's transfers must follow its account nonce sequence
+        errors.extend(verify_block_nonces(current_block, &mut self.nonces));
+
+        // UTXO spends must consume unspent outputs worth at least what they create
+        #[cfg(feature = "utxo")]
+        errors.extend(verify_block_utxos(current_block, &mut self.utxos));
+
+        if let Err(e) = verify_state_root(current_block, &self.balances) {
             errors.push(e);
         }
 
@@ -179,12 +932,60 @@ pub fn validate_chain(blockchain: &Blockchain) -> ValidationResult {
         if let Err(e) = verify_chain_link(current_block, previous_block) {
             errors.push(e);
         }
+        if let Err(e) = verify_block_version(current_block.index as usize, current_block.version, min_version) {
+            errors.push(e);
+        }
+
+        // Timestamps may wander, but not back past the recent median
+        if let Err(e) = verify_timestamp(current_block, median_time_past) {
+            errors.push(e);
+        }
+
+        // Verify proof-of-work, at no less than the chain's minimum difficulty
+        if let Err(e) = verify_min_difficulty(current_block, blockchain.required_difficulty()) {
+            errors.push(e);
+        }
+        if let Err(e) = verify_proof_of_work_with_algorithm(current_block, blockchain.pow_prefix_char(), blockchain.pow_algorithm) {
+            errors.push(e);
+        }
 
-        // Verify proof-of-work
-        if let Err(e) = verify_proof_of_work(current_block) {
+        // Proof-of-stake blocks must come from the selected validator
+        if let Err(e) = verify_producer(current_block, previous_block, &blockchain.consensus) {
             errors.push(e);
         }
+
+        errors
+    }
+}
+
+impl Iterator for ValidationErrors<'_> {
+    type Item = ValidationError;
+
+    fn next(&mut self) -> Option<ValidationError> {
+        loop {
+            if let Some(error) = self.found.next() {
+                return Some(error);
+            }
+
+            if self.next_block < self.end {
+                let i = self.next_block;
+                self.next_block += 1;
+                self.found = self.verify_chain_block(i).into_iter();
+            } else if self.include_checkpoints {
+                // Checkpointed blocks must keep their recorded hashes
+                self.include_checkpoints = false;
+                self.found = verify_checkpoints(self.blockchain).into_iter();
+            } else {
+                return None;
+            }
+        }
     }
+}
+
+/// `validate_chain` plus the cross-block replay check from `find_replayed_transactions`
+pub fn validate_chain_with_replays(blockchain: &Blockchain) -> ValidationResult {
+    let mut errors = validate_chain(blockchain).errors;
+    errors.extend(find_replayed_transactions(blockchain));
 
     if errors.is_empty() {
         ValidationResult::valid()
@@ -193,33 +994,92 @@ pub fn validate_chain(blockchain: &Blockchain) -> ValidationResult {
     }
 }
 
-/// Quick validation check (stops at first error)
-pub fn validate_chain_quick(blockchain: &Blockchain) -> bool {
-    for i in 1..blockchain.chain.len() {
-        let current_block = &blockchain.chain[i];
-        let previous_block = &blockchain.chain[i - 1];
+/// Validates a header-only (pruned) chain: genesis, index sequencing, hash
+/// integrity, links, and proof-of-work. Without transactions the Merkle roots
+/// can't be checked against their contents, which is the SPV trade-off.
+/// Headers carry no chain parameters, so hashes are held to the default prefix.
+pub fn validate_headers(headers: &[BlockHeader]) -> ValidationResult {
+    let mut errors = Vec::new();
 
-        // Quick checks: hash, link, and proof-of-work
-        if current_block.hash != current_block.calculate_hash() {
-            return false;
+    if let Some(genesis) = headers.first() {
+        if genesis.index != 0 {
+            errors.push(ValidationError::InvalidGenesis {
+                reason: format!("Invalid index: expected 0, got {}", genesis.index),
+            });
+        }
+        if genesis.previous_hash != "0" {
+            errors.push(ValidationError::InvalidGenesis {
+                reason: format!("Invalid previous_hash: expected '0', got '{}'", genesis.previous_hash),
+            });
+        }
+        if genesis.version != 0 {
+            errors.push(ValidationError::InvalidGenesis {
+                reason: format!("Invalid version: expected 0, got {}", genesis.version),
+            });
+        }
+    }
+
+    for (i, header) in headers.iter().enumerate() {
+        let index = header.index as usize;
+
+        if i > 0 && index != i {
+            errors.push(ValidationError::InvalidIndex { index, expected: i });
+        }
+
+        let computed = header.calculate_hash();
+        if header.hash != computed {
+            errors.push(ValidationError::InvalidHash {
+                index,
+                stored: header.hash.clone(),
+                computed,
+            });
         }
 
-        if current_block.previous_hash != previous_block.hash {
-            return false;
+        if i == 0 {
+            continue;
         }
 
-        if !Block::is_hash_valid(&current_block.hash, current_block.difficulty) {
-            return false;
+        let previous = &headers[i - 1];
+        if header.previous_hash != previous.hash {
+            errors.push(ValidationError::BrokenLink {
+                index,
+                previous_hash: header.previous_hash.clone(),
+                expected: previous.hash.clone(),
+            });
+        }
+        if let Err(e) = verify_block_version(index, header.version, previous.version) {
+            errors.push(e);
+        }
+
+        if let Err(e) = verify_difficulty(index, header.difficulty) {
+            errors.push(e);
+        } else if !Block::is_hash_valid(&header.hash, header.difficulty) {
+            errors.push(ValidationError::InvalidProofOfWork {
+                index,
+                hash: header.hash.clone(),
+                difficulty: header.difficulty,
+            });
         }
     }
 
-    true
+    if errors.is_empty() {
+        ValidationResult::valid()
+    } else {
+        ValidationResult::invalid(errors)
+    }
+}
+
+/// Quick validation check (stops at the first block with an error)
+pub fn validate_chain_quick(blockchain: &Blockchain) -> bool {
+    ValidationErrors::blocks(blockchain, blockchain.chain.len()).next().is_none()
+        && verify_checkpoints(blockchain).is_empty()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::transaction::Transaction;
+    #[cfg(feature = "mining")]
+    use crate::transaction::COIN;
 
     #[test]
     fn test_verify_block_hash_valid() {
@@ -297,9 +1157,68 @@ mod tests {
     }
 
     #[test]
+    fn test_genesis_merkle_root_and_difficulty_are_checked() {
+        let mut blockchain = Blockchain::new();
+        blockchain.chain[0].merkle_root = String::new();
+        assert!(!blockchain.is_valid());
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(matches!(validate_chain(&blockchain).errors.as_slice(), [ValidationError::InvalidMerkleRoot { index: 0, .. }]));
+
+        let mut blockchain = Blockchain::new();
+        blockchain.chain[0].difficulty = 1;
+        assert!(!blockchain.is_valid());
+        assert!(validate_chain(&blockchain).errors.iter().any(|e| matches!(e, ValidationError::InvalidGenesis { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_state_roots_commit_to_the_balances() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block_to("Miner");
+        blockchain.add_transaction(String::from("Bob"), String::from("Carol"), 4 * COIN).unwrap();
+        blockchain.mine_block();
+
+        assert!(blockchain.is_valid());
+        assert!(validate_chain_quick(&blockchain));
+        assert_eq!(blockchain.get_latest_block().state_root.as_deref(), Some(blockchain.state_root().as_str()));
+        assert_eq!(blockchain.get_latest_block().header().state_root, blockchain.get_latest_block().state_root);
+        assert!(blockchain.chain[0].state_root.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_doctored_balance_with_valid_hashes_fails_the_state_root_check() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+        let committed = blockchain.get_latest_block().state_root.clone().unwrap();
+
+        // Raise Bob's balance and redo the Merkle root and proof-of-work, but
+        // leave the state root the block was mined with
+        let block = &mut blockchain.chain[1];
+        block.transactions[0].amount = 1_000 * COIN;
+        block.merkle_root = block.compute_merkle_root();
+        block.hash.clear();
+        block.mine_block();
+        assert!(blockchain.chain[1].verify_integrity().is_ok());
+
+        assert!(!blockchain.is_valid());
+        assert!(!validate_chain_quick(&blockchain));
+        assert_eq!(validate_chain(&blockchain).errors, vec![ValidationError::StateRootMismatch {
+            index: 1,
+            stored: committed,
+            computed: blockchain.state_root(),
+        }]);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
     fn test_validate_chain_valid() {
         let mut blockchain = Blockchain::new();
-        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
         blockchain.mine_block();
 
         let result = validate_chain(&blockchain);
@@ -308,13 +1227,14 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "mining")]
     fn test_validate_chain_tampered_block() {
         let mut blockchain = Blockchain::new();
-        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
         blockchain.mine_block();
 
         // Tamper with the block
-        blockchain.chain[1].transactions[0].amount = 999.0;
+        blockchain.chain[1].transactions[0].amount = 999 * COIN;
 
         let result = validate_chain(&blockchain);
         assert!(!result.is_valid);
@@ -322,16 +1242,466 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "mining")]
+    fn test_every_validity_check_enforces_block_index() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.mine_block();
+        blockchain.mine_block();
+
+        // A correctly hashed and mined tip that claims the wrong height
+        let tip = &mut blockchain.chain[2];
+        tip.index = 5;
+        tip.hash = tip.calculate_hash();
+        while !tip.mine_step(1_000) {}
+
+        assert!(validate_chain(&blockchain).errors.contains(&ValidationError::InvalidIndex { index: 5, expected: 2 }));
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+        assert!(blockchain.is_valid_up_to(1));
+        assert!(!blockchain.is_valid_up_to(2));
+
+        let tip = blockchain.chain.pop().unwrap();
+        assert!(matches!(blockchain.verify_block_against_tip(&tip), Err(ValidationError::InvalidIndex { index: 5, expected: 2 })));
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
     fn test_validate_chain_quick() {
         let mut blockchain = Blockchain::new();
-        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
         blockchain.mine_block();
 
         assert!(validate_chain_quick(&blockchain));
 
         // Tamper with the block
-        blockchain.chain[1].transactions[0].amount = 999.0;
+        blockchain.chain[1].transactions[0].amount = 999 * COIN;
 
         assert!(!validate_chain_quick(&blockchain));
     }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_headers_matches_full_validation() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for i in 0..3 {
+            blockchain.add_transaction(format!("User{}", i), format!("User{}", i + 1), 10 * COIN).unwrap();
+            blockchain.mine_block();
+        }
+
+        // Valid chain: both views agree
+        let full = validate_chain(&blockchain);
+        let headers = validate_headers(&blockchain.to_headers_only());
+        assert!(full.is_valid);
+        assert!(headers.is_valid);
+
+        // Broken link: both views report the same errors
+        blockchain.tamper_with_previous_hash(2, String::from("bogus"));
+        let full = validate_chain(&blockchain);
+        let headers = validate_headers(&blockchain.to_headers_only());
+        assert!(!headers.is_valid);
+        assert_eq!(full.errors, headers.errors);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_headers_detects_header_tampering() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+        blockchain.mine_block();
+
+        let pristine = blockchain.to_headers_only();
+
+        let mut tampered = pristine.clone();
+        tampered[1].merkle_root = String::from("forged");
+        let result = validate_headers(&tampered);
+        assert!(matches!(result.get_first_error(), Some(ValidationError::InvalidHash { index: 1, .. })));
+
+        let mut tampered = pristine.clone();
+        tampered[2].index = 5;
+        let result = validate_headers(&tampered);
+        assert!(result.errors.contains(&ValidationError::InvalidIndex { index: 5, expected: 2 }));
+
+        let mut tampered = pristine;
+        tampered[0].previous_hash = String::from("not zero");
+        assert!(!validate_headers(&tampered).is_valid);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_headers_cannot_see_transactions() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+
+        // Transaction edits only show up against the full blocks
+        blockchain.chain[1].transactions[0].amount = 999 * COIN;
+        assert!(!validate_chain(&blockchain).is_valid);
+        assert!(validate_headers(&blockchain.to_headers_only()).is_valid);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_chain_detects_intra_block_duplicate() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+
+        // Bypass mine_block, which would drop the copy
+        let tx = crate::transaction::Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        let tip = blockchain.tip_hash().to_string();
+        let mut block = Block::new(1, 1_700_000_000_000, vec![tx.clone(), tx.clone()], tip, 1);
+        block.mine_block();
+        blockchain.chain.push(block);
+
+        let result = validate_chain(&blockchain);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors, vec![ValidationError::DuplicateTransaction { index: 1, tx_id: tx.id() }]);
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_replay_check_detects_cross_block_duplicate() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        // Unnumbered (nonce 0) transfers, as in chains from before account nonces
+        let mut transfer = crate::transaction::Transaction::new(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        transfer.created_at = blockchain.now();
+        blockchain.pending_transactions.push(transfer.clone());
+        blockchain.mine_block();
+        blockchain.add_transaction(String::from("Carol"), String::from("Dave"), COIN).unwrap();
+        blockchain.pending_transactions.push(transfer);
+        blockchain.mine_block();
+
+        // Without nonces an identical transfer is a legitimate block on its own
+        assert!(validate_chain(&blockchain).is_valid);
+
+        let replayed = blockchain.chain[1].transactions[0].id();
+        let result = validate_chain_with_replays(&blockchain);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors, vec![ValidationError::DuplicateTransaction { index: 2, tx_id: replayed }]);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_summary_counts_mixed_errors() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for i in 0..3 {
+            blockchain.add_transaction(format!("User{}", i), String::from("Bob"), COIN).unwrap();
+            blockchain.mine_block();
+        }
+
+        // Redirecting block 2 breaks its link and its hash; block 3's hash is forged
+        blockchain.chain[2].previous_hash = String::from("0000bogus");
+        blockchain.chain[3].hash = String::from("0000forged");
+
+        let summary = validate_chain(&blockchain).summary();
+        assert!(!summary.is_valid);
+        assert_eq!(summary.errors_by_type.get("BrokenLink"), Some(&1));
+        assert_eq!(summary.errors_by_type.get("InvalidHash"), Some(&2));
+        assert_eq!(summary.errors_by_type.len(), 2);
+        assert_eq!(summary.total_errors, summary.errors.len());
+        assert_eq!(summary.total_errors, summary.errors_by_type.values().sum::<usize>());
+        assert_eq!(summary.first_invalid_block, Some(2));
+
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json()).unwrap();
+        assert_eq!(json["errors_by_type"]["InvalidHash"], 2);
+        assert_eq!(json["first_invalid_block"], 2);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_error_iterator_is_lazy() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for i in 0..5 {
+            blockchain.add_transaction(format!("User{}", i), String::from("Bob"), COIN).unwrap();
+            blockchain.mine_block();
+        }
+        blockchain.chain[1].transactions[0].amount = 99 * COIN;
+        blockchain.chain[4].hash = String::from("0000forged");
+        blockchain.checkpoints.push((9, String::from("0000missing")));
+
+        let lazy: Vec<ValidationError> = blockchain.iter_validation_errors().collect();
+        assert_eq!(lazy, validate_chain(&blockchain).errors);
+        assert!(matches!(lazy.last(), Some(ValidationError::CheckpointMismatch { index: 9, .. })));
+
+        // The first error is in block 1, so blocks 2..5 are never checked
+        let mut errors = iter_validation_errors(&blockchain);
+        assert!(matches!(errors.next(), Some(ValidationError::InvalidHash { index: 1, .. })));
+        assert_eq!(errors.blocks_checked(), 2);
+    }
+
+    #[test]
+    fn test_summary_of_valid_result() {
+        let summary = ValidationResult::valid().summary();
+        assert!(summary.is_valid);
+        assert_eq!(summary.total_errors, 0);
+        assert!(summary.errors_by_type.is_empty());
+        assert_eq!(summary.first_invalid_block, None);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_chain_rejects_oversized_block() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+
+        // Tighten the limit after the fact
+        let size = blockchain.chain[1].serialized_size();
+        blockchain.max_block_bytes = size - 1;
+
+        let result = validate_chain(&blockchain);
+        assert_eq!(result.errors, vec![ValidationError::BlockTooLarge { index: 1, size, limit: size - 1 }]);
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_chain_rejects_too_many_transactions() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.max_transactions_per_block = 3;
+
+        // A crafted block that skips the mempool's cap
+        let transactions = (0..5)
+            .map(|i| crate::transaction::Transaction::new(format!("User{}", i), String::from("Bob"), COIN).unwrap())
+            .collect();
+        let latest = blockchain.get_latest_block();
+        let mut block = Block::new(1, latest.timestamp + 1, transactions, latest.hash.clone(), 1);
+        block.mine_block();
+        assert!(blockchain.add_mined_block(block.clone()).is_err());
+        blockchain.chain.push(block);
+
+        let result = validate_chain(&blockchain);
+        assert_eq!(result.errors, vec![ValidationError::TooManyTransactions { index: 1, count: 5, limit: 3 }]);
+        assert!(!validate_chain_quick(&blockchain));
+
+        // The load path checks it too
+        let loaded: Blockchain = serde_json::from_str(&serde_json::to_string(&blockchain).unwrap()).unwrap();
+        assert!(!loaded.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_chain_rejects_out_of_range_amounts() {
+        use crate::blockchain::{DEFAULT_MAX_AMOUNT, DEFAULT_MIN_AMOUNT};
+        use crate::transaction::Transaction;
+
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        let dust = Transaction::new(String::from("Alice"), String::from("Bob"), DEFAULT_MIN_AMOUNT - 1).unwrap();
+        // Transaction::new refuses anything above the cap, so edit the field
+        let mut huge = Transaction::new(String::from("Carol"), String::from("Dave"), DEFAULT_MAX_AMOUNT).unwrap();
+        huge.amount += 1;
+        assert!(blockchain.add_pending_transaction(dust.clone()).is_err());
+        assert!(blockchain.add_pending_transaction(huge.clone()).is_err());
+
+        // Mined directly, as a dishonest node would
+        for tx in [&dust, &huge] {
+            let latest = blockchain.get_latest_block();
+            let mut block = Block::new(latest.index + 1, latest.timestamp + 1, vec![tx.clone()], latest.hash.clone(), 1);
+            block.mine_block();
+            assert!(blockchain.add_mined_block(block.clone()).is_err());
+            blockchain.chain.push(block);
+        }
+
+        let result = validate_chain(&blockchain);
+        assert_eq!(result.errors, vec![
+            ValidationError::AmountOutOfRange {
+                index: 1, tx_id: dust.id(), amount: dust.amount, min: DEFAULT_MIN_AMOUNT, max: DEFAULT_MAX_AMOUNT,
+            },
+            ValidationError::AmountOutOfRange {
+                index: 2, tx_id: huge.id(), amount: huge.amount, min: DEFAULT_MIN_AMOUNT, max: DEFAULT_MAX_AMOUNT,
+            },
+        ]);
+        assert!(!validate_chain_quick(&blockchain));
+
+        let loaded: Blockchain = serde_json::from_str(&serde_json::to_string(&blockchain).unwrap()).unwrap();
+        assert!(!loaded.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_validate_chain_checks_checkpoints() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+        let pinned = blockchain.add_checkpoint(1).unwrap();
+        assert!(validate_chain(&blockchain).is_valid);
+
+        // A checkpoint recorded with a different hash (e.g. from another node)
+        blockchain.checkpoints = vec![(1, String::from("0bogus")), (5, pinned.clone())];
+
+        let result = validate_chain(&blockchain);
+        assert_eq!(result.errors, vec![
+            ValidationError::CheckpointMismatch { index: 1, expected: String::from("0bogus"), found: Some(pinned.clone()) },
+            ValidationError::CheckpointMismatch { index: 5, expected: pinned, found: None },
+        ]);
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_loaded_block_with_excessive_difficulty_is_rejected() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+
+        // Claim an impossible difficulty; rehash so only the difficulty is wrong
+        let mut json: serde_json::Value = serde_json::to_value(&blockchain).unwrap();
+        json["chain"][1]["difficulty"] = serde_json::json!(64);
+        let mut loaded: Blockchain = serde_json::from_value(json).unwrap();
+        loaded.chain[1].hash = loaded.chain[1].calculate_hash();
+
+        let result = validate_chain(&loaded);
+        assert_eq!(result.errors, vec![
+            ValidationError::DifficultyOutOfRange { index: 1, difficulty: 64, max: MAX_DIFFICULTY },
+        ]);
+        assert!(!validate_chain_quick(&loaded));
+        assert!(!loaded.is_valid());
+
+        // Mining never targets more than the maximum either
+        blockchain.set_difficulty(64);
+        assert_eq!(blockchain.get_difficulty(), MAX_DIFFICULTY);
+    }
+
+    #[test]
+    fn test_block_below_min_difficulty_is_rejected() {
+        let mut blockchain = Blockchain::new();
+        blockchain.min_difficulty = 4;
+        blockchain.set_difficulty(4);
+
+        // Difficulty 0 needs no work at all, so any nonce "meets" it
+        let forged = Block::new(1, 1, Vec::new(), blockchain.genesis_hash().to_string(), 0);
+        blockchain.chain.push(forged);
+
+        let result = validate_chain(&blockchain);
+        assert_eq!(result.errors, vec![
+            ValidationError::InsufficientDifficulty { index: 1, declared: 0, required: 4 },
+        ]);
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+
+        blockchain.set_difficulty(0);
+        assert_eq!(blockchain.get_difficulty(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_loaded_chain_with_blocked_address_is_invalid() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Mallory"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+        let tx_id = blockchain.chain[1].transactions[0].id();
+
+        // A node that blocks Mallory loads the same blocks and rejects them
+        let mut value = serde_json::to_value(&blockchain).unwrap();
+        value["blocked_addresses"] = serde_json::json!(["Mallory"]);
+        let loaded = Blockchain::from_json(&value.to_string()).unwrap();
+
+        let result = validate_chain(&loaded);
+        assert_eq!(result.errors, vec![ValidationError::BlockedAddress {
+            index: 1,
+            tx_id,
+            address: String::from("Mallory"),
+        }]);
+        assert!(!validate_chain_quick(&loaded));
+        assert!(!loaded.is_valid());
+        assert!(blockchain.is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_chain_is_checked_against_its_pow_prefix() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(2);
+        blockchain.set_pow_prefix_char('a').unwrap();
+        blockchain.mine_block();
+        blockchain.mine_block();
+        assert!(blockchain.chain[1..].iter().all(|block| block.hash.starts_with("aa")));
+        assert!(blockchain.is_valid());
+
+        // The same blocks fail a chain configured for the default prefix
+        blockchain.set_pow_prefix_char('0').unwrap();
+        let result = validate_chain(&blockchain);
+        let kinds: Vec<_> = result.errors.iter().map(|e| (e.kind(), e.block_index())).collect();
+        assert_eq!(kinds, vec![("InvalidProofOfWork", 1), ("InvalidProofOfWork", 2)]);
+        assert!(!validate_chain_quick(&blockchain));
+        assert!(!blockchain.is_valid());
+
+        // No hex digest could ever start with a non-hex digit
+        assert!(blockchain.set_pow_prefix_char('g').is_err());
+        assert!(blockchain.set_pow_prefix_char('A').is_err());
+        assert_eq!(blockchain.pow_prefix_char(), '0');
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_blocks_only_validate_under_the_pow_algorithm_they_were_mined_with() {
+        let algorithms = [
+            PowAlgorithm::Sha256,
+            PowAlgorithm::DoubleSha256,
+            #[cfg(feature = "scrypt")]
+            PowAlgorithm::ScryptLike,
+        ];
+
+        for &algorithm in &algorithms {
+            let mut blockchain = Blockchain::with_clock(crate::clock::Clock::stepping(1_700_000_000_000, 1_000));
+            blockchain.set_difficulty(1);
+            blockchain.pow_algorithm = algorithm;
+            let block = blockchain.mine_block().clone();
+            assert!(block.meets_pow(DEFAULT_POW_PREFIX, algorithm), "{:?}", algorithm);
+            assert!(blockchain.is_valid(), "{:?}", algorithm);
+
+            // The same block misses the target under every other algorithm
+            for &other in algorithms.iter().filter(|&&other| other != algorithm) {
+                let mut checked = blockchain.clone();
+                checked.pow_algorithm = other;
+                let kinds: Vec<_> = validate_chain(&checked).errors.iter().map(|e| (e.kind(), e.block_index())).collect();
+                assert_eq!(kinds, vec![("InvalidProofOfWork", 1)], "{:?} under {:?}", algorithm, other);
+                assert!(!validate_chain_quick(&checked));
+                assert!(!checked.is_valid());
+            }
+        }
+    }
+    #[test]
+    #[cfg(all(feature = "rayon", feature = "mining"))]
+    fn test_parallel_validation_matches_sequential() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for receiver in ["Bob", "Carol", "Dave", "Erin", "Frank"] {
+            blockchain.add_transaction(String::from("Alice"), String::from(receiver), COIN).unwrap();
+            blockchain.mine_block();
+        }
+        blockchain.add_checkpoint(2).unwrap();
+        assert!(validate_chain_parallel(&blockchain).is_valid);
+
+        // Several errors across blocks, plus a checkpoint reported after them
+        blockchain.chain[1].transactions[0].amount = 99 * COIN;
+        blockchain.chain[2].hash = "0".repeat(64);
+        blockchain.chain[3].nonce += 1;
+        blockchain.chain[4].previous_hash = "f".repeat(64);
+        blockchain.chain[5].index = 9;
+
+        let sequential = validate_chain(&blockchain);
+        let parallel = validate_chain_parallel(&blockchain);
+        assert!(sequential.errors.len() > 4);
+        assert_eq!(sequential.errors.last().map(ValidationError::kind), Some("CheckpointMismatch"));
+        assert_eq!(parallel.errors, sequential.errors);
+        assert!(!parallel.is_valid);
+    }
 }
@@ -3,8 +3,13 @@
 //! This module provides ASCII art and display helpers for visualizing
 //! blockchain state, attack results, and chain structures.
 
+use crate::alias::Aliases;
+use crate::block::Block;
 use crate::blockchain::Blockchain;
 use crate::validation::ValidationResult;
+use std::ops::Range;
+
+pub use crate::crypto::{short_hash, DEFAULT_HASH_LEN};
 
 /// Colors for terminal output (using ANSI codes)
 #[allow(dead_code)]
@@ -19,29 +24,81 @@ pub mod colors {
     pub const WHITE: &str = "\x1b[37m";
     pub const BOLD: &str = "\x1b[1m";
 
+    /// False when `NO_COLOR` is set to a non-empty value (see no-color.org)
+    pub fn enabled() -> bool {
+        std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
+
+    /// Whether stdout should get colors: `enabled` and a terminal, not a pipe or file
+    pub fn stdout_supports_color() -> bool {
+        use std::io::IsTerminal;
+        enabled() && std::io::stdout().is_terminal()
+    }
+
+    /// `text` in bold `color`, whether or not colors are `enabled`
+    pub fn paint(color: &str, text: &str) -> String {
+        format!("{}{}{}{}", color, BOLD, text, RESET)
+    }
+
+    fn paint_if_enabled(color: &str, text: &str) -> String {
+        if enabled() { paint(color, text) } else { text.to_string() }
+    }
+
     /// Red bold text for invalid/bad
     pub fn error(text: &str) -> String {
-        format!("{}{}{}{}", RED, BOLD, text, RESET)
+        paint_if_enabled(RED, text)
     }
 
     /// Green bold text for valid/good
     pub fn success(text: &str) -> String {
-        format!("{}{}{}{}", GREEN, BOLD, text, RESET)
+        paint_if_enabled(GREEN, text)
     }
 
     /// Yellow bold text for warnings
     pub fn warning(text: &str) -> String {
-        format!("{}{}{}{}", YELLOW, BOLD, text, RESET)
+        paint_if_enabled(YELLOW, text)
     }
 
     /// Blue bold text for info
     pub fn info(text: &str) -> String {
-        format!("{}{}{}{}", BLUE, BOLD, text, RESET)
+        paint_if_enabled(BLUE, text)
     }
 
     /// Cyan bold text for headers
     pub fn header(text: &str) -> String {
-        format!("{}{}{}{}", CYAN, BOLD, text, RESET)
+        paint_if_enabled(CYAN, text)
+    }
+}
+
+/// Blocks per page when `chain --page` is given no `--per-page`
+pub const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// Pages needed for `items` at `per_page` each, counting a partial last page;
+/// always at least one, so an empty list still has a (blank) page 1
+pub fn page_count(items: usize, per_page: usize) -> usize {
+    items.div_ceil(per_page.max(1)).max(1)
+}
+
+/// Positions of the items on 1-based `page`, or None past the last page
+pub fn page_range(items: usize, page: usize, per_page: usize) -> Option<Range<usize>> {
+    let per_page = per_page.max(1);
+    if page == 0 || page > page_count(items, per_page) {
+        return None;
+    }
+    let start = (page - 1) * per_page;
+    Some(start..(start + per_page).min(items))
+}
+
+/// Display settings shared by every visualizer view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayConfig {
+    /// How many leading characters of a hash to show
+    pub hash_len: usize,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig { hash_len: DEFAULT_HASH_LEN }
     }
 }
 
@@ -49,13 +106,17 @@ pub mod colors {
 pub struct BlockchainVisualizer {
     /// Whether to use colors
     pub use_colors: bool,
+    /// Hash display length and similar settings
+    pub display: DisplayConfig,
 }
 
 impl BlockchainVisualizer {
-    /// Create a new visualizer
+    /// Create a new visualizer, colored only if stdout is a terminal and
+    /// `NO_COLOR` isn't set (see `with_colors` to override)
     pub fn new() -> Self {
         BlockchainVisualizer {
-            use_colors: true,
+            use_colors: colors::stdout_supports_color(),
+            display: DisplayConfig::default(),
         }
     }
 
@@ -63,24 +124,41 @@ impl BlockchainVisualizer {
     pub fn without_colors() -> Self {
         BlockchainVisualizer {
             use_colors: false,
+            display: DisplayConfig::default(),
         }
     }
 
-    /// Display blockchain as ASCII art
-    pub fn display_chain(&self, blockchain: &Blockchain) {
+    /// Forces colors on or off, whatever the terminal and `NO_COLOR` say
+    pub fn with_colors(mut self, use_colors: bool) -> Self {
+        self.use_colors = use_colors;
+        self
+    }
+
+    /// `text` in bold `color` when this visualizer uses colors, else plain
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.use_colors { colors::paint(color, text) } else { text.to_string() }
+    }
+
+    /// A hash cut to the configured display length
+    pub fn short(&self, hash: &str) -> String {
+        short_hash(hash, self.display.hash_len).to_string()
+    }
+
+    /// Display blockchain as ASCII art, naming addresses by their `aliases`
+    pub fn display_chain(&self, blockchain: &Blockchain, aliases: &Aliases) {
         println!("\n╔════════════════════════════════════════════════════════╗");
         println!("║                    Blockchain View                     ║");
         println!("╚════════════════════════════════════════════════════════╝\n");
 
-        for (i, block) in blockchain.chain.iter().enumerate() {
+        for (i, block) in blockchain.blocks().enumerate() {
             let is_valid = block.hash == block.calculate_hash();
             let status = if is_valid { "✓" } else { "✗" };
             let status_color = if is_valid { colors::GREEN } else { colors::RED };
 
-            println!("{} Block #{} {}{}", status_color, status, colors::RESET, colors::header(&format!("(Diff: {})", block.difficulty)));
+            println!("{} {}", self.paint(status_color, &format!("Block #{}", status)), self.paint(colors::CYAN, &format!("(Diff: {})", block.difficulty)));
             println!("┌──────────────────────────────────────────────────────┐");
-            println!("│ Hash:       {}...│", &block.hash[..32.min(block.hash.len())]);
-            println!("│ Previous:   {}...│", &block.previous_hash[..32.min(block.previous_hash.len())]);
+            println!("│ Hash:       {:<50}│", format!("{}...", self.short(&block.hash)));
+            println!("│ Previous:   {:<50}│", format!("{}...", self.short(&block.previous_hash)));
             println!("│ Nonce:      {:>50}│", block.nonce);
             println!("│ Time:       {:>50}│", block.timestamp);
             println!("│ Txs:        {:>50}│", block.transaction_count());
@@ -89,8 +167,8 @@ impl BlockchainVisualizer {
                 println!("├──────────────────────────────────────────────────────┤");
                 for tx in &block.transactions {
                     println!("│ {} → {} : {:>38.2}│",
-                        tx.sender,
-                        tx.receiver,
+                        aliases.label(&tx.sender),
+                        aliases.label(&tx.receiver),
                         tx.amount
                     );
                 }
@@ -108,15 +186,16 @@ impl BlockchainVisualizer {
         // Show chain validity
         let chain_valid = blockchain.is_valid();
         let status_text = if chain_valid {
-            colors::success("CHAIN VALID ✓")
+            self.paint(colors::GREEN, "CHAIN VALID ✓")
         } else {
-            colors::error("CHAIN INVALID ✗")
+            self.paint(colors::RED, "CHAIN INVALID ✗")
         };
 
         println!("\n═════════════════════════════════════════════════════════");
         println!("Status: {}", status_text);
-        println!("Blocks:  {} | Difficulty: {} | Pending: {}",
+        println!("Blocks:  {} | Height: {} | Difficulty: {} | Pending: {}",
             blockchain.len(),
+            blockchain.height(),
             blockchain.get_difficulty(),
             blockchain.pending_transaction_count()
         );
@@ -125,29 +204,62 @@ impl BlockchainVisualizer {
 
     /// Display chain in compact format
     pub fn display_compact_chain(&self, blockchain: &Blockchain) {
-        println!("\n┌─ Blockchain ({} blocks, difficulty {}) ──────────────┐",
+        println!("\n┌─ Blockchain ({} blocks, height {}, difficulty {}) ───┐",
             blockchain.len(),
+            blockchain.height(),
             blockchain.get_difficulty()
         );
 
-        for block in &blockchain.chain {
-            let status = if block.hash == block.calculate_hash() { "✓" } else { "✗" };
-            let hash_preview = &block.hash[..12.min(block.hash.len())];
-
-            println!("│ {} #{} {}... [{} txs, nonce: {}] │",
-                status,
-                block.index,
-                hash_preview,
-                block.transaction_count(),
-                block.nonce
-            );
+        for block in blockchain.blocks() {
+            println!("{}", self.compact_row(block));
         }
 
-        let valid = if blockchain.is_valid() { colors::success("Valid") } else { colors::error("Invalid") };
+        let valid = if blockchain.is_valid() { self.paint(colors::GREEN, "Valid") } else { self.paint(colors::RED, "Invalid") };
         println!("└────────────────────────────────────────────────────────┘");
         println!("Status: {} | Pending: {}\n", valid, blockchain.pending_transaction_count());
     }
 
+    /// One block as a line of the compact view
+    fn compact_row(&self, block: &Block) -> String {
+        let status = if block.hash == block.calculate_hash() { "✓" } else { "✗" };
+        format!("│ {} #{} {}... [{} txs, nonce: {}] │",
+            status,
+            block.index,
+            self.short(&block.hash),
+            block.transaction_count(),
+            block.nonce
+        )
+    }
+
+    /// Display one page of the compact view (see `format_page`)
+    pub fn display_page(&self, blockchain: &Blockchain, page: usize, per_page: usize) {
+        match self.format_page(blockchain, page, per_page) {
+            Ok(output) => println!("{}", output),
+            Err(e) => println!("{}", self.paint(colors::RED, &e)),
+        }
+    }
+
+    /// Blocks on 1-based `page` of the chain, `per_page` to a page, in the
+    /// compact format, with a "Page 2/17" footer. Errors past the last page
+    pub fn format_page(&self, blockchain: &Blockchain, page: usize, per_page: usize) -> Result<String, String> {
+        let pages = page_count(blockchain.len(), per_page);
+        let range = page_range(blockchain.len(), page, per_page)
+            .ok_or_else(|| format!("Page {} does not exist; the chain has {} page(s) of {} block(s)", page, pages, per_page))?;
+
+        let mut output = format!("\n┌─ Blockchain ({} blocks, height {}, difficulty {}) ───┐\n",
+            blockchain.len(),
+            blockchain.height(),
+            blockchain.get_difficulty()
+        );
+        for block in &blockchain.chain[range.clone()] {
+            output.push_str(&self.compact_row(block));
+            output.push('\n');
+        }
+        output.push_str("└────────────────────────────────────────────────────────┘\n");
+        output.push_str(&format!("Page {}/{} (blocks #{}-#{})", page, pages, range.start, range.end.saturating_sub(1)));
+        Ok(output)
+    }
+
     /// Display validation result with details
     pub fn display_validation_result(&self, result: &ValidationResult) {
         println!("\n╔════════════════════════════════════════════════════════╗");
@@ -155,9 +267,9 @@ impl BlockchainVisualizer {
         println!("╚════════════════════════════════════════════════════════╝\n");
 
         let status = if result.is_valid {
-            colors::success("✓ CHAIN VALID")
+            self.paint(colors::GREEN, "✓ CHAIN VALID")
         } else {
-            colors::error("✗ CHAIN INVALID")
+            self.paint(colors::RED, "✗ CHAIN INVALID")
         };
 
         println!("Status: {}\n", status);
@@ -176,16 +288,34 @@ impl BlockchainVisualizer {
                     crate::validation::ValidationError::BrokenLink { .. } => "Broken Link",
                     crate::validation::ValidationError::InvalidProofOfWork { .. } => "Invalid PoW",
                     crate::validation::ValidationError::InvalidIndex { .. } => "Index Error",
+                    crate::validation::ValidationError::InvalidBlockVersion { .. } => "Block Version Error",
                     crate::validation::ValidationError::InvalidGenesis { .. } => "Genesis Error",
+                    crate::validation::ValidationError::InvalidMerkleRoot { .. } => "Merkle Root Mismatch",
+                    crate::validation::ValidationError::DuplicateTransaction { .. } => "Duplicate Transaction",
+                    crate::validation::ValidationError::BlockTooLarge { .. } => "Block Too Large",
+                    crate::validation::ValidationError::TooManyTransactions { .. } => "Too Many Transactions",
+                    crate::validation::ValidationError::AmountOutOfRange { .. } => "Amount Out Of Range",
+                    crate::validation::ValidationError::BlockedAddress { .. } => "Blocked Address",
+                    crate::validation::ValidationError::CheckpointMismatch { .. } => "Checkpoint Mismatch",
+                    crate::validation::ValidationError::WrongProducer { .. } => "Wrong Producer",
+                    crate::validation::ValidationError::InvalidCoinbase { .. } => "Invalid Coinbase",
+                    crate::validation::ValidationError::MisplacedCoinbase { .. } => "Misplaced Coinbase",
+                    crate::validation::ValidationError::SupplyCapExceeded { .. } => "Supply Cap Exceeded",
+                    crate::validation::ValidationError::BadAccountNonce { .. } => "Bad Account Nonce",
+                    crate::validation::ValidationError::TimestampTooEarly { .. } => "Timestamp Too Early",
+                    crate::validation::ValidationError::StateRootMismatch { .. } => "State Root Mismatch",
+                    crate::validation::ValidationError::DifficultyOutOfRange { .. } => "Difficulty Out of Range",
+                    crate::validation::ValidationError::InsufficientDifficulty { .. } => "Insufficient Difficulty",
+                    #[cfg(feature = "utxo")]
+                    crate::validation::ValidationError::InvalidSpend { .. } => "Invalid Spend",
                 };
 
-                println!("  {}. {}:", i + 1, colors::error(error_type));
+                println!("  {}. {}:", i + 1, self.paint(colors::RED, error_type));
                 println!("     {}", error);
             }
 
-            println!("\n{} {}\n",
-                colors::warning("⚠ WARNING:"),
-                "The blockchain has been tampered with or is corrupted."
+            println!("\n{} The blockchain has been tampered with or is corrupted.\n",
+                self.paint(colors::YELLOW, "⚠ WARNING:")
             );
         }
     }
@@ -205,7 +335,7 @@ impl BlockchainVisualizer {
         println!("│ Valid: {} │ Blocks: {} │ Hash: {}... │",
             if before.is_valid() { "✓" } else { "✗" },
             before.len(),
-            &before.get_latest_block().hash[..12]
+            self.short(before.tip_hash())
         );
         println!("└────────────────────────────────────────────────────────┘");
 
@@ -219,7 +349,7 @@ impl BlockchainVisualizer {
         println!("│ Valid: {} │ Blocks: {} │ Hash: {}... │",
             if after.is_valid() { "✓" } else { "✗" },
             after.len(),
-            &after.get_latest_block().hash[..12.min(after.get_latest_block().hash.len())]
+            self.short(after.tip_hash())
         );
         println!("└────────────────────────────────────────────────────────┘\n");
 
@@ -248,6 +378,115 @@ impl BlockchainVisualizer {
         }
     }
 
+    /// Display two chains block by block, highlighting where they diverge
+    pub fn display_chain_diff(&self, a: &Blockchain, b: &Blockchain) {
+        println!("{}", self.format_chain_diff(a, b));
+    }
+
+    /// Build the chain diff view: matching blocks green, divergent blocks red,
+    /// an arrow at the first divergence, then blocks found in only one chain
+    /// Without colors, rows are marked `[OK]` / `[DIFF]` instead
+    pub fn format_chain_diff(&self, a: &Blockchain, b: &Blockchain) -> String {
+        let diff = a.compare_chains(b);
+        let rows = a.len().max(b.len());
+        // Room for the hash, "..." and a gap; "(none)" needs at least 6
+        let width = self.display.hash_len.max(6) + 4;
+
+        let mut output = format!(
+            "\n=== Chain Diff (A: {} blocks, B: {} blocks) ===\n\n     #   {:<8}{:<width$}Chain B\n",
+            a.len(),
+            b.len(),
+            "",
+            "Chain A"
+        );
+
+        for i in 0..rows {
+            let hash_a = a.get_block(i).map(|block| block.hash.as_str());
+            let hash_b = b.get_block(i).map(|block| block.hash.as_str());
+            let matches = hash_a.is_some() && hash_a == hash_b;
+
+            let arrow = if diff.first_divergence == Some(i) { " → " } else { "   " };
+            let cell = |hash: Option<&str>| match hash {
+                Some(hash) => format!("{}...", self.short(hash)),
+                None => String::from("(none)"),
+            };
+            let columns = format!("{:<width$}{:<width$}", cell(hash_a), cell(hash_b));
+
+            let row = match (self.use_colors, matches) {
+                (true, true) => format!("{}  {}", self.paint(colors::GREEN, "✓ "), self.paint(colors::GREEN, &columns)),
+                (true, false) => format!("{}  {}", self.paint(colors::RED, "✗ "), self.paint(colors::RED, &columns)),
+                (false, true) => format!("{:<8}{}", "[OK]", columns),
+                (false, false) => format!("{:<8}{}", "[DIFF]", columns),
+            };
+
+            output.push_str(&format!("{}{:>3}   {}", arrow, i, row.trim_end()));
+            if diff.first_divergence == Some(i) {
+                output.push_str("  ← first divergence");
+            }
+            output.push('\n');
+        }
+
+        // Blocks that exist on only one side
+        let common = a.len().min(b.len());
+        for (name, chain) in [("A", a), ("B", b)] {
+            if chain.len() > common {
+                let extra: Vec<String> = (common..chain.len()).map(|i| format!("#{}", i)).collect();
+                output.push_str(&format!("\nOnly in chain {}: {}", name, extra.join(", ")));
+            }
+        }
+
+        if diff.first_divergence.is_none() {
+            output.push_str("\nChains are identical");
+        }
+        output.push('\n');
+
+        output
+    }
+
+    /// Build a unified-diff-style listing of how chain `b` differs from `a`,
+    /// one plain line per block from the first divergence on:
+    /// `+` only in B, `-` only in A, `~` in both with different hashes
+    pub fn format_chain_patch(&self, a: &Blockchain, b: &Blockchain, names: [&str; 2]) -> String {
+        let diff = a.compare_chains(b);
+        let mut output = format!("--- {} ({} blocks)\n+++ {} ({} blocks)\n", names[0], a.len(), names[1], b.len());
+
+        let Some(start) = diff.first_divergence else {
+            output.push_str("No differences\n");
+            return output;
+        };
+
+        let end = a.len().max(b.len());
+        output.push_str(&format!("@@ #{}..#{} @@\n", start, end - 1));
+
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for i in start..end {
+            let line = match (a.get_block(i), b.get_block(i)) {
+                (Some(old), Some(new)) => {
+                    changed += 1;
+                    let mut line = format!("~ #{} {} -> {}", i, self.short(&old.hash), self.short(&new.hash));
+                    if let Some((_, tx_index)) = diff.first_tx_divergence.filter(|&(index, _)| index == i) {
+                        line.push_str(&format!(" (transactions differ from #{})", tx_index));
+                    }
+                    line
+                }
+                (Some(old), None) => {
+                    removed += 1;
+                    format!("- #{} {}", i, self.short(&old.hash))
+                }
+                (None, Some(new)) => {
+                    added += 1;
+                    format!("+ #{} {}", i, self.short(&new.hash))
+                }
+                (None, None) => unreachable!("index is below the longer chain's length"),
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+
+        output.push_str(&format!("{} added, {} removed, {} changed\n", added, removed, changed));
+        output
+    }
+
     /// Display cascading failure diagram
     pub fn display_cascading_failure(&self, tamper_block: usize, chain_len: usize) {
         println!("\n╔════════════════════════════════════════════════════════╗");
@@ -259,16 +498,16 @@ impl BlockchainVisualizer {
         for i in 0..chain_len {
             if i == tamper_block {
                 println!("  Block #{} {} TAMPERED ✗",
-                    colors::error(&format!("#{}", i)),
-                    colors::error("→")
+                    self.paint(colors::RED, &format!("#{}", i)),
+                    self.paint(colors::RED, "→")
                 );
                 println!("           ↓");
                 println!("           (invalid hash)");
                 println!("           ↓");
             } else if i > tamper_block {
                 println!("  Block #{} {} INVALID ✗",
-                    colors::error(&format!("#{}", i)),
-                    colors::error("→")
+                    self.paint(colors::RED, &format!("#{}", i)),
+                    self.paint(colors::RED, "→")
                 );
                 println!("           ↓");
                 println!("           (previous_hash mismatch)");
@@ -277,8 +516,8 @@ impl BlockchainVisualizer {
                 }
             } else {
                 println!("  Block #{} {} Valid ✓",
-                    colors::success(&format!("#{}", i)),
-                    colors::success("→")
+                    self.paint(colors::GREEN, &format!("#{}", i)),
+                    self.paint(colors::GREEN, "→")
                 );
                 if i < tamper_block {
                     println!("           ↓");
@@ -305,7 +544,7 @@ impl BlockchainVisualizer {
     /// Display proof-of-work visualization
     pub fn display_pow_visualization(&self, block_index: u64, difficulty: u32, nonce: u64, hash: &str) {
         let target_zeros = "0".repeat(difficulty as usize);
-        let hash_start = &hash[..(difficulty as usize).min(hash.len())];
+        let hash_start = short_hash(hash, difficulty as usize);
 
         println!("\n╔════════════════════════════════════════════════════════╗");
         println!("║            Proof-of-Work Visualization                ║");
@@ -323,12 +562,12 @@ impl BlockchainVisualizer {
         println!("  Attempted nonces: 0 → {} ({} attempts)", nonce, nonce + 1);
 
         let matches = if hash_start == target_zeros {
-            colors::success("✓ MATCHES")
+            self.paint(colors::GREEN, "✓ MATCHES")
         } else {
-            colors::error("✗ NO MATCH")
+            self.paint(colors::RED, "✗ NO MATCH")
         };
 
-        println!("\n  Result: {} {}...\n", matches, &hash[..32]);
+        println!("\n  Result: {} {}...\n", matches, self.short(hash));
 
         println!("What this means:");
         println!("  • The miner tried {} different nonces", nonce + 1);
@@ -558,26 +797,146 @@ impl Default for BlockchainVisualizer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "mining")]
+    use crate::transaction::COIN;
 
     #[test]
     fn test_visualizer_creation() {
         let viz = BlockchainVisualizer::new();
-        assert!(viz.use_colors);
+        assert_eq!(viz.use_colors, colors::stdout_supports_color());
+        assert!(viz.with_colors(true).use_colors);
 
         let viz_no_color = BlockchainVisualizer::without_colors();
         assert!(!viz_no_color.use_colors);
+        assert!(!BlockchainVisualizer::new().with_colors(false).use_colors);
     }
 
     #[test]
     fn test_visualizer_default() {
         let viz = BlockchainVisualizer::default();
-        assert!(viz.use_colors);
+        assert_eq!(viz.use_colors, colors::stdout_supports_color());
     }
 
     #[test]
     fn test_format_colors() {
-        assert!(colors::success("test").contains("32")); // Green
-        assert!(colors::error("test").contains("31")); // Red
-        assert!(colors::warning("test").contains("33")); // Yellow
+        assert!(colors::paint(colors::GREEN, "test").contains("32")); // Green
+        assert!(colors::paint(colors::RED, "test").contains("31")); // Red
+        assert!(colors::paint(colors::YELLOW, "test").contains("33")); // Yellow
+
+        // The helpers only differ from `paint` under NO_COLOR (tests/no_color.rs)
+        if colors::enabled() {
+            assert_eq!(colors::error("test"), colors::paint(colors::RED, "test"));
+        }
+    }
+
+    #[cfg(feature = "mining")]
+    fn tampered_chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+        blockchain.mine_block();
+        blockchain.tamper_with_hash(1, String::from("wrong_hash"));
+        blockchain.tamper_with_previous_hash(1, String::from("é"));
+        blockchain
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_display_functions_survive_short_hashes() {
+        let viz = BlockchainVisualizer::without_colors();
+        let honest = {
+            let mut blockchain = Blockchain::new();
+            blockchain.set_difficulty(1);
+            blockchain.mine_block();
+            blockchain
+        };
+        let tampered = tampered_chain();
+
+        viz.display_chain(&tampered, &Aliases::new());
+        viz.display_compact_chain(&tampered);
+        viz.display_attack_comparison(&tampered, &honest, "Short hash");
+        viz.display_attack_comparison(&honest, &tampered, "Short hash");
+        viz.display_pow_visualization(1, 4, 7, "wrong_hash");
+        viz.display_pow_visualization(1, 4, 7, "0é");
+    }
+
+    #[test]
+    fn test_hash_len_is_configurable() {
+        let hash = "00ab34cd56ef7890aabbccddeeff0011";
+        let mut viz = BlockchainVisualizer::without_colors();
+        assert_eq!(viz.short(hash).len(), DEFAULT_HASH_LEN);
+
+        viz.display.hash_len = 8;
+        assert_eq!(viz.short(hash), "00ab34cd");
+
+        // Shorter than the configured length: shown whole, no panic
+        viz.display.hash_len = 64;
+        assert_eq!(viz.short(hash), hash);
+        assert_eq!(viz.short("bad"), "bad");
+        #[cfg(feature = "mining")]
+        viz.display_chain(&tampered_chain(), &Aliases::new());
+    }
+
+    #[test]
+    fn test_pagination_counts_a_partial_last_page() {
+        assert_eq!(page_count(95, 10), 10);
+        assert_eq!(page_range(95, 10, 10), Some(90..95));
+        assert_eq!(page_range(95, 2, 10), Some(10..20));
+        assert_eq!(page_range(95, 11, 10), None);
+        assert_eq!(page_range(95, 0, 10), None);
+        assert_eq!(page_count(100, 10), 10);
+        assert_eq!(page_count(1, 10), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_format_page_shows_a_partial_last_page() {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        for _ in 0..4 {
+            blockchain.mine_block();
+        }
+        let viz = BlockchainVisualizer::without_colors();
+        let last = viz.format_page(&blockchain, 3, 2).unwrap();
+        assert_eq!(last.lines().filter(|line| line.starts_with("│ ")).count(), 1);
+        assert!(last.ends_with("Page 3/3 (blocks #4-#4)"), "{}", last);
+        assert!(viz.format_page(&blockchain, 4, 2).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "mining")]
+    fn test_chain_diff_marks_first_divergence() {
+        use crate::clock::Clock;
+
+        let build = |last_receiver: &str| {
+            let mut blockchain = Blockchain::with_clock(Clock::stepping(1_000, 1_000));
+            blockchain.set_difficulty(1);
+            blockchain.add_transaction(String::from("Alice"), String::from("Bob"), 10 * COIN).unwrap();
+            blockchain.mine_block();
+            blockchain.add_transaction(String::from("Bob"), String::from(last_receiver), 5 * COIN).unwrap();
+            blockchain.mine_block();
+            blockchain
+        };
+
+        let a = build("Charlie");
+        let mut b = build("Mallory");
+        b.mine_block();
+
+        let viz = BlockchainVisualizer::without_colors();
+        let output = viz.format_chain_diff(&a, &b);
+        let rows: Vec<&str> = output.lines().filter(|line| line.contains("...") || line.contains("(none)")).collect();
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].contains("[OK]") && !rows[0].contains('→'));
+        assert!(rows[1].contains("[OK]") && !rows[1].contains('→'));
+        assert!(rows[2].starts_with(" → ") && rows[2].contains("  2   [DIFF]"));
+        assert!(rows[3].contains("[DIFF]") && rows[3].contains("(none)"));
+        assert!(output.contains("Only in chain B: #3"));
+        assert!(!output.contains("\x1b["));
+
+        // Colored output drops the text markers
+        let colored = BlockchainVisualizer::new().with_colors(true).format_chain_diff(&a, &b);
+        assert!(colored.contains(colors::RED) && colored.contains(colors::GREEN));
+        assert!(!colored.contains("[DIFF]"));
     }
 }
@@ -0,0 +1,228 @@
+//! WASM bindings for browser-based demos
+//!
+//! `JsBlockchain` wraps a `Blockchain` with JS-friendly types. Mining in the
+//! browser happens in slices through `mine_step`, so a page can yield to the
+//! event loop between calls instead of freezing the UI.
+
+use crate::block::Block;
+use crate::blockchain::Blockchain;
+use crate::transaction::parse_amount;
+use wasm_bindgen::prelude::*;
+
+/// Current time in milliseconds, from the JS clock when running in a browser
+fn now_millis() -> u128 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now() as u128
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis()
+    }
+}
+
+/// Blockchain handle exported to JavaScript
+#[wasm_bindgen]
+pub struct JsBlockchain {
+    blockchain: Blockchain,
+    /// Block currently being mined by `mine_step`, if any
+    candidate: Option<Block>,
+}
+
+#[wasm_bindgen]
+impl JsBlockchain {
+    /// Creates a new blockchain with only the genesis block
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsBlockchain {
+        JsBlockchain {
+            blockchain: Blockchain::new_with_clock(now_millis),
+            candidate: None,
+        }
+    }
+
+    /// Adds a transaction to the mempool; `amount` is in coins
+    /// Converted through its shortest decimal form, so 0.1 becomes exactly 0.1 coins
+    pub fn add_transaction(&mut self, sender: String, receiver: String, amount: f64) -> Result<(), JsError> {
+        let amount = parse_amount(&amount.to_string()).map_err(|e| JsError::new(&e))?;
+        self.blockchain
+            .add_transaction(sender, receiver, amount)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Mines a whole block in one call (blocks the caller until done)
+    pub fn mine_block(&mut self) -> Result<(), JsError> {
+        while !self.mine_step(u32::MAX)? {}
+        Ok(())
+    }
+
+    /// Tries up to `max_iters` nonces on the block being mined
+    /// Returns true when a block was found and appended to the chain
+    pub fn mine_step(&mut self, max_iters: u32) -> Result<bool, JsError> {
+        self.try_mine_step(max_iters).map_err(|e| JsError::new(&e))
+    }
+
+    /// Abandons the block being mined, putting its transactions back in the mempool
+    pub fn cancel_mining(&mut self) {
+        if let Some(block) = self.candidate.take() {
+            self.blockchain.requeue_candidate(block);
+        }
+    }
+
+    /// Whether a block is partway through `mine_step`
+    #[wasm_bindgen(getter)]
+    pub fn is_mining(&self) -> bool {
+        self.candidate.is_some()
+    }
+
+    /// Validates the whole chain
+    pub fn is_valid(&self) -> bool {
+        self.blockchain.is_valid()
+    }
+
+    /// Serializes the chain to JSON (same format as `save`)
+    pub fn to_json(&self) -> Result<String, JsError> {
+        serde_json::to_string(&self.blockchain).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Number of blocks, including genesis
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.blockchain.len()
+    }
+
+    /// Mining difficulty (leading zeros required)
+    #[wasm_bindgen(getter)]
+    pub fn difficulty(&self) -> u32 {
+        self.blockchain.get_difficulty()
+    }
+
+    /// Changes the difficulty for blocks mined from now on
+    #[wasm_bindgen(setter)]
+    pub fn set_difficulty(&mut self, difficulty: u32) {
+        self.blockchain.set_difficulty(difficulty);
+    }
+
+    /// Number of transactions waiting in the mempool
+    #[wasm_bindgen(getter)]
+    pub fn pending_count(&self) -> usize {
+        self.blockchain.pending_transaction_count()
+    }
+}
+
+impl JsBlockchain {
+    /// `mine_step` with a plain error, since `JsError` needs a JS host
+    fn try_mine_step(&mut self, max_iters: u32) -> Result<bool, String> {
+        let blockchain = &mut self.blockchain;
+        let candidate = self
+            .candidate
+            .get_or_insert_with(|| blockchain.create_candidate_block(blockchain.now()));
+
+        if !candidate.mine_step_with_algorithm(max_iters as u64, blockchain.pow_prefix_char(), blockchain.pow_algorithm) {
+            return Ok(false);
+        }
+
+        // A rejected block's transactions go back in the mempool
+        let block = self.candidate.take().expect("candidate was just mined");
+        self.blockchain.add_mined_block(block)?;
+        Ok(true)
+    }
+}
+
+impl Default for JsBlockchain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    // `wasm-pack test --node -- --features wasm` runs these in a JS host;
+    // natively they run as plain unit tests
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn test_add_mine_validate_round_trip() {
+        let mut chain = JsBlockchain::new();
+        chain.set_difficulty(2);
+
+        chain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+        assert_eq!(chain.pending_count(), 1);
+
+        // Mine in small slices like a page would between animation frames
+        while !chain.mine_step(64).unwrap() {
+            assert!(chain.is_mining());
+        }
+
+        assert!(!chain.is_mining());
+        assert_eq!(chain.pending_count(), 0);
+        assert_eq!(chain.length(), 2);
+        assert!(chain.is_valid());
+
+        let json = chain.to_json().unwrap();
+        let restored: Blockchain = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(restored.is_valid());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn test_mine_block_in_one_call() {
+        let mut chain = JsBlockchain::new();
+        chain.set_difficulty(1);
+
+        chain.mine_block().unwrap();
+
+        assert_eq!(chain.length(), 2);
+        assert_eq!(chain.difficulty(), 1);
+        assert!(chain.is_valid());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn test_failed_mining_keeps_the_transactions() {
+        let mut chain = JsBlockchain::new();
+        chain.set_difficulty(1);
+        chain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+
+        // A candidate that no longer links to the tip is rejected once mined
+        let mut stale = chain.blockchain.create_candidate_block(chain.blockchain.now());
+        stale.previous_hash = String::from("stale");
+        chain.candidate = Some(stale);
+        assert_eq!(chain.pending_count(), 0);
+        let result = loop {
+            match chain.try_mine_step(64) {
+                Ok(false) => continue,
+                other => break other,
+            }
+        };
+
+        assert!(result.is_err());
+        assert!(!chain.is_mining());
+        assert_eq!(chain.length(), 1);
+        assert_eq!(chain.pending_count(), 1);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn test_cancel_mining_keeps_the_transactions() {
+        let mut chain = JsBlockchain::new();
+        chain.set_difficulty(6);
+        chain.add_transaction(String::from("Alice"), String::from("Bob"), 10.0).unwrap();
+
+        assert!(!chain.mine_step(0).unwrap());
+        assert!(chain.is_mining());
+        chain.cancel_mining();
+
+        assert!(!chain.is_mining());
+        assert_eq!(chain.length(), 1);
+        assert_eq!(chain.pending_count(), 1);
+    }
+}
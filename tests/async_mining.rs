@@ -0,0 +1,40 @@
+//! Async mining: the proof-of-work runs on tokio's blocking pool, and the
+//! mempool only changes once the mined block is appended
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Waker};
+
+use rustchain::blockchain::Blockchain;
+use rustchain::transaction::COIN;
+
+#[tokio::test]
+async fn test_mine_block_async() {
+    let mut blockchain = Blockchain::new();
+    blockchain.set_difficulty(2);
+    blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
+
+    blockchain.mine_block_async().await.unwrap();
+
+    assert_eq!(blockchain.len(), 2);
+    assert_eq!(blockchain.get_latest_block().transaction_count(), 1);
+    assert_eq!(blockchain.pending_transaction_count(), 0);
+    assert!(blockchain.is_valid());
+}
+
+#[tokio::test]
+async fn test_dropped_mining_future_keeps_the_mempool() {
+    let mut blockchain = Blockchain::new();
+    blockchain.set_difficulty(4);
+    blockchain.add_transaction("Alice".to_string(), "Bob".to_string(), 10 * COIN).unwrap();
+
+    {
+        let mut mining = pin!(blockchain.mine_block_async());
+        // One poll starts the nonce search, still running at difficulty 4 when it returns
+        let poll = mining.as_mut().poll(&mut Context::from_waker(Waker::noop()));
+        assert!(poll.is_pending());
+    }
+
+    assert_eq!(blockchain.len(), 1);
+    assert_eq!(blockchain.pending_transaction_count(), 1);
+}
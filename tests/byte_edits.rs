@@ -0,0 +1,73 @@
+//! Property tests: a single-byte edit to a serialized chain is never accepted
+//!
+//! Each case builds a small valid chain, serializes its blocks, replaces one
+//! byte and decodes the result. The edit must either fail to decode or leave
+//! a chain that validation rejects. Edits the decoder can't see (an unknown
+//! key it ignores, say) leave the blocks as they were and are skipped.
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+use rustchain::block::Block;
+use rustchain::blockchain::Blockchain;
+use rustchain::clock::Clock;
+use rustchain::transaction::{Transaction, COIN};
+use rustchain::validation;
+
+/// A valid chain of `blocks` mined blocks with `transfers` transfers each;
+/// odd blocks pay a reward, and every other transfer carries a fee and a memo
+fn valid_chain(blocks: usize, transfers: usize) -> Blockchain {
+    let mut blockchain = Blockchain::with_clock(Clock::stepping(1_700_000_000_000, 1_000));
+    blockchain.set_difficulty(1);
+    for block in 0..blocks {
+        for n in 0..transfers {
+            let mut builder = Transaction::builder()
+                .sender(format!("sender{}", n))
+                .receiver(format!("receiver{}", block))
+                .amount((n as u64 + 1) * COIN)
+                .nonce(block as u64 + 1);
+            if n % 2 == 1 {
+                builder = builder.fee(n as u64).memo("rent");
+            }
+            blockchain.add_pending_transaction(builder.build().unwrap()).unwrap();
+        }
+        if block % 2 == 1 {
+            blockchain.mine_block_to("miner");
+        } else {
+            blockchain.mine_block();
+        }
+    }
+    assert!(blockchain.is_valid());
+    blockchain
+}
+
+/// The blocks as `serde_json` writes them
+fn canonical(blocks: &[Block]) -> String {
+    serde_json::to_string(blocks).unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn single_byte_edits_never_validate(blocks in 1..4usize, transfers in 0..3usize, at in any::<Index>(), byte in any::<u8>()) {
+        let original = valid_chain(blocks, transfers);
+        let json = serde_json::to_string(&original.chain).unwrap();
+        let mut bytes = json.into_bytes();
+        let at = at.index(bytes.len());
+        bytes[at] = byte;
+
+        // Failing to decode is a clean rejection
+        let Ok(edited) = serde_json::from_slice::<Vec<Block>>(&bytes) else {
+            return Ok(());
+        };
+        if canonical(&edited) == canonical(&original.chain) {
+            return Ok(());
+        }
+
+        let mut tampered = original.clone();
+        tampered.chain = edited;
+        prop_assert!(!tampered.is_valid(), "edit at byte {} validated: {}", at, String::from_utf8_lossy(&bytes));
+        prop_assert!(!validation::validate_chain(&tampered).is_valid);
+        prop_assert!(!validation::validate_chain_quick(&tampered));
+    }
+}
@@ -0,0 +1,76 @@
+//! Gossips transactions through small networks of in-process nodes
+
+use rustchain::blockchain::Blockchain;
+use rustchain::node::{Node, Outgoing};
+use rustchain::transaction::{Transaction, COIN};
+use std::collections::{HashMap, VecDeque};
+
+/// Nodes by address, wired up along `links`
+fn network(addresses: &[&str], links: &[(&str, &str)]) -> HashMap<String, Node> {
+    let mut nodes: HashMap<String, Node> = addresses
+        .iter()
+        .map(|address| (address.to_string(), Node::new(*address, Blockchain::new())))
+        .collect();
+    for (a, b) in links {
+        nodes.get_mut(*a).unwrap().add_peer(*b);
+        nodes.get_mut(*b).unwrap().add_peer(*a);
+    }
+    nodes
+}
+
+/// Delivers messages until the network goes quiet; returns how many
+/// messages each node received
+fn run(nodes: &mut HashMap<String, Node>, from: &str, outgoing: Vec<Outgoing>) -> HashMap<String, usize> {
+    let mut queue: VecDeque<(String, Outgoing)> = outgoing.into_iter().map(|out| (from.to_string(), out)).collect();
+    let mut received = HashMap::new();
+
+    while let Some((sender, out)) = queue.pop_front() {
+        assert!(received.values().sum::<usize>() < 100, "gossip never settled");
+        *received.entry(out.to.clone()).or_insert(0) += 1;
+
+        let node = nodes.get_mut(&out.to).unwrap();
+        let forwarded = node.handle(&sender, out.message);
+        queue.extend(forwarded.into_iter().map(|next| (out.to.clone(), next)));
+    }
+    received
+}
+
+#[test]
+fn test_transaction_crosses_a_line_once() {
+    let mut nodes = network(&["A", "B", "C"], &[("A", "B"), ("B", "C")]);
+
+    let tx = Transaction::new(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+    let outgoing = nodes.get_mut("A").unwrap().submit_transaction(tx).unwrap();
+    let received = run(&mut nodes, "A", outgoing);
+
+    assert_eq!(received.get("C"), Some(&1));
+    assert_eq!(received.get("B"), Some(&1));
+    // B doesn't send the transaction back to A
+    assert_eq!(received.get("A"), None);
+
+    let id = nodes["A"].blockchain().pending_transactions[0].id();
+    for node in nodes.values() {
+        let pending = &node.blockchain().pending_transactions;
+        assert_eq!(pending.len(), 1, "{}", node.address());
+        assert_eq!(pending[0].id(), id);
+        assert!(node.has_seen(&id));
+    }
+}
+
+#[test]
+fn test_loop_in_peer_graph_settles() {
+    let mut nodes = network(&["A", "B", "C"], &[("A", "B"), ("B", "C"), ("C", "A")]);
+
+    let tx = Transaction::new(String::from("Alice"), String::from("Bob"), COIN).unwrap();
+    let outgoing = nodes.get_mut("A").unwrap().submit_transaction(tx).unwrap();
+    let received = run(&mut nodes, "A", outgoing);
+
+    // B and C hear it from A and from each other, then the gossip stops;
+    // neither sends it back to A
+    assert_eq!(received.get("B"), Some(&2));
+    assert_eq!(received.get("C"), Some(&2));
+    assert_eq!(received.get("A"), None);
+    for node in nodes.values() {
+        assert_eq!(node.blockchain().pending_transaction_count(), 1, "{}", node.address());
+    }
+}
@@ -0,0 +1,86 @@
+//! Drives the HTTP API over a real socket
+
+use rustchain::blockchain::Blockchain;
+use rustchain::http::ApiServer;
+use rustchain::transaction::COIN;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+/// Sends one HTTP/1.1 request and returns (status, body)
+fn request(port: u16, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn test_transaction_mine_and_balance_over_http() {
+    let server = ApiServer::bind("127.0.0.1:0").unwrap().with_miner("Node");
+    let port = server.port().unwrap();
+
+    thread::spawn(move || {
+        let mut blockchain = Blockchain::new();
+        blockchain.set_difficulty(1);
+        loop {
+            server.handle_next(&mut blockchain, Duration::from_millis(50)).unwrap();
+        }
+    });
+
+    let transfer = serde_json::json!({ "sender": "Alice", "receiver": "Bob", "amount": 10 * COIN });
+    let (status, body) = request(port, "POST", "/transaction", &transfer.to_string());
+    assert_eq!(status, 201);
+    assert_eq!(body["pending"], 1);
+
+    let (status, block) = request(port, "POST", "/mine", "");
+    assert_eq!(status, 201);
+    assert_eq!(block["index"], 1);
+
+    let (status, balance) = request(port, "GET", "/balance/Bob", "");
+    assert_eq!(status, 200);
+    assert_eq!(balance["balance"], 10 * COIN);
+
+    let (status, chain) = request(port, "GET", "/chain", "");
+    assert_eq!(status, 200);
+    assert_eq!(chain["chain"].as_array().unwrap().len(), 2);
+
+    let (status, _) = request(port, "GET", "/block/7", "");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn test_oversized_body_is_refused() {
+    let server = ApiServer::bind("127.0.0.1:0").unwrap().with_max_body_bytes(64);
+    let port = server.port().unwrap();
+
+    thread::spawn(move || {
+        let mut blockchain = Blockchain::new();
+        loop {
+            server.handle_next(&mut blockchain, Duration::from_millis(50)).unwrap();
+        }
+    });
+
+    let memo = "x".repeat(100);
+    let transfer = serde_json::json!({ "sender": "Alice", "receiver": "Bob", "amount": COIN, "memo": memo });
+    let (status, body) = request(port, "POST", "/transaction", &transfer.to_string());
+    assert_eq!(status, 413);
+    assert!(body["error"].as_str().unwrap().contains("64 bytes"));
+
+    let (status, body) = request(port, "GET", "/chain", "");
+    assert_eq!(status, 200);
+    assert!(body["pending_transactions"].as_array().unwrap().is_empty());
+}
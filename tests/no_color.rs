@@ -0,0 +1,26 @@
+//! The color helpers honor the NO_COLOR convention (https://no-color.org)
+//!
+//! Setting an environment variable affects the whole process, so this lives
+//! in its own test binary with a single test.
+
+use rustchain::visualization::{colors, BlockchainVisualizer};
+
+#[test]
+fn no_color_strips_escape_codes_unless_forced() {
+    // SAFETY: this binary's only test, so no other thread reads the environment
+    unsafe { std::env::set_var("NO_COLOR", "1") };
+
+    assert!(!colors::enabled());
+    assert_eq!(colors::error("broken"), "broken");
+    assert_eq!(colors::success("fine"), "fine");
+    assert!(!colors::header("title").contains('\x1b'));
+    assert!(!BlockchainVisualizer::new().use_colors);
+
+    // An explicit choice still wins
+    assert!(BlockchainVisualizer::new().with_colors(true).use_colors);
+
+    // An empty value doesn't count as set
+    unsafe { std::env::set_var("NO_COLOR", "") };
+    assert!(colors::enabled());
+    assert!(colors::error("broken").contains(colors::RED));
+}